@@ -0,0 +1,11 @@
+//! Recover what the lenient parser can from arbitrary bytes
+
+#![no_main]
+
+use aiogguf::io::SliceReader;
+use aiogguf::GgufFile;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = GgufFile::from_reader_lenient(&mut SliceReader::new(data));
+});
@@ -0,0 +1,32 @@
+//! Parse arbitrary bytes as a GGUF file, then run the analyses that only
+//! read what was parsed
+
+#![no_main]
+
+use aiogguf::io::SliceReader;
+use aiogguf::{GgufFile, QuantComparison};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(gguf_file) = GgufFile::from_reader(&mut SliceReader::new(data)) else {
+        return;
+    };
+    let _ = gguf_file.inspect();
+    let _ = gguf_file.total_size();
+    let _ = gguf_file.data_layout();
+    let _ = gguf_file.data_gaps();
+    let _ = gguf_file.vocab();
+    let _ = gguf_file.estimated_param_count();
+    let _ = gguf_file.verify_complete(data.len() as u64);
+    let _ = gguf_file.model_card();
+    let _ = QuantComparison::build(core::slice::from_ref(&gguf_file));
+    for tensor in &gguf_file.tensors {
+        let _ = tensor.byte_range(gguf_file.data_offset());
+    }
+    if let Ok(config) = gguf_file.model_config() {
+        let _ = config.effective_context();
+        let _ = config.kv_cache_bytes(4096, 1, 2);
+        let _ = config.derived_size_label();
+        let _ = gguf_file.validate_against_config(&config);
+    }
+});
@@ -0,0 +1,20 @@
+//! Read a metadata value of every type from arbitrary bytes, and check that
+//! generated values survive a write and read unchanged
+
+#![no_main]
+
+use aiogguf::io::SliceReader;
+use aiogguf::{GgufValue, GgufValueType};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (u8, &[u8], GgufValue)| {
+    let (type_id, bytes, value) = input;
+    if let Ok(value_type) = GgufValueType::try_from(u32::from(type_id)) {
+        let _ = GgufValue::read(&mut SliceReader::new(bytes), value_type);
+    }
+
+    let mut written = Vec::new();
+    value.write(&mut written).unwrap();
+    let read = GgufValue::read(&mut SliceReader::new(&written), value.value_type()).unwrap();
+    assert_eq!(read, value);
+});
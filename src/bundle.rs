@@ -0,0 +1,220 @@
+/*!
+ * Companion File Discovery
+ *
+ * Model repositories ship more than one GGUF: split shards, vision
+ * projectors, LoRA adapters, importance matrices and the original
+ * Hugging Face configs. `ModelBundle` groups them around one model file.
+ */
+
+use crate::error::Result;
+use crate::header::GgufHeader;
+use crate::keys;
+use crate::kind::GgufKind;
+use crate::metadata::GgufMetadata;
+use crate::split::SplitInfo;
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+/// Original Hugging Face files often shipped alongside GGUFs, matched case-insensitively
+const ORIGINAL_FILES: &[&str] = &[
+    "config.json",
+    "generation_config.json",
+    "tokenizer.json",
+    "tokenizer_config.json",
+    "special_tokens_map.json",
+    "readme.md",
+];
+
+/// Something about a bundle that looks wrong
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleWarning {
+    /// The projector's `general.name` doesn't resemble the model's
+    ProjectorNameMismatch { model: String, projector: String },
+    /// Fewer split shards were found than `split.count` or the file names declare
+    MissingShards { expected: u32, found: usize },
+}
+
+/// A model file and the related artifacts found next to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelBundle {
+    model: PathBuf,
+    model_name: Option<String>,
+    shards: Vec<PathBuf>,
+    expected_shards: u32,
+    projector: Option<PathBuf>,
+    projector_name: Option<String>,
+    adapters: Vec<PathBuf>,
+    imatrix_files: Vec<PathBuf>,
+    originals: Vec<PathBuf>,
+}
+
+impl ModelBundle {
+    /// Group the artifacts in `path`'s directory that belong to its model
+    ///
+    /// - Split shards named `<name>-NNNNN-of-NNNNN.gguf`
+    /// - Vision projectors, by `GgufKind`
+    /// - Adapters for the same architecture, by `GgufKind`
+    /// - `*.imatrix` and `imatrix*.dat` importance matrices
+    /// - Original `config.json`, `tokenizer.json` and similar files
+    pub fn discover<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let model = path.as_ref().to_path_buf();
+        let metadata = read_metadata(&model)?;
+        let architecture = metadata.get_string_opt(keys::GENERAL_ARCHITECTURE).map(str::to_string);
+
+        let directory = match model.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut entries: Vec<PathBuf> = fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file())
+            .collect();
+        entries.sort();
+
+        let model_file_name = file_name(&model);
+        let split = split_name(&model_file_name);
+        let mut bundle = Self {
+            model: model.clone(),
+            model_name: metadata.get_string_opt(keys::GENERAL_NAME).map(str::to_string),
+            shards: Vec::new(),
+            expected_shards: SplitInfo::from_metadata(&metadata)
+                .map(|info| info.count)
+                .or(split.as_ref().map(|(_, _, count)| *count))
+                .unwrap_or(1),
+            projector: None,
+            projector_name: None,
+            adapters: Vec::new(),
+            imatrix_files: Vec::new(),
+            originals: Vec::new(),
+        };
+
+        for entry in entries {
+            let name = file_name(&entry);
+            let lower = name.to_ascii_lowercase();
+
+            let is_shard = match (&split, split_name(&name)) {
+                (Some((prefix, _, count)), Some((entry_prefix, _, entry_count))) => {
+                    *prefix == entry_prefix && *count == entry_count
+                }
+                _ => name == model_file_name,
+            };
+            if is_shard {
+                bundle.shards.push(entry);
+            } else if lower.ends_with(".imatrix") || (lower.starts_with("imatrix") && lower.ends_with(".dat")) {
+                bundle.imatrix_files.push(entry);
+            } else if ORIGINAL_FILES.contains(&lower.as_str()) {
+                bundle.originals.push(entry);
+            } else if lower.ends_with(".gguf") {
+                // Unreadable GGUFs are not part of the bundle
+                let Ok(companion) = read_metadata(&entry) else {
+                    continue;
+                };
+                let companion_arch = companion.get_string_opt(keys::GENERAL_ARCHITECTURE);
+                match GgufKind::from_metadata(&companion) {
+                    GgufKind::VisionProjector if bundle.projector.is_none() => {
+                        bundle.projector_name = companion.get_string_opt(keys::GENERAL_NAME).map(str::to_string);
+                        bundle.projector = Some(entry);
+                    }
+                    GgufKind::Adapter if companion_arch.is_some() && companion_arch == architecture.as_deref() => {
+                        bundle.adapters.push(entry);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(bundle)
+    }
+
+    /// The model file the bundle was discovered from
+    pub fn model(&self) -> &Path {
+        &self.model
+    }
+
+    /// Every shard of the model in order, just the model itself when unsplit
+    pub fn shards(&self) -> &[PathBuf] {
+        &self.shards
+    }
+
+    /// Vision projector (`mmproj`) for multimodal models
+    pub fn projector(&self) -> Option<&Path> {
+        self.projector.as_deref()
+    }
+
+    /// LoRA adapters for the model's architecture
+    pub fn adapters(&self) -> &[PathBuf] {
+        &self.adapters
+    }
+
+    /// Importance matrix files
+    pub fn imatrix_files(&self) -> &[PathBuf] {
+        &self.imatrix_files
+    }
+
+    /// Original Hugging Face configuration, tokenizer and README files
+    pub fn originals(&self) -> &[PathBuf] {
+        &self.originals
+    }
+
+    /// Check the bundle for parts that don't seem to belong together
+    pub fn validate(&self) -> Vec<BundleWarning> {
+        let mut warnings = Vec::new();
+
+        if let (Some(model), Some(projector)) = (&self.model_name, &self.projector_name) {
+            let (model_key, projector_key) = (name_key(model), name_key(projector));
+            if !model_key.contains(&projector_key) && !projector_key.contains(&model_key) {
+                warnings.push(BundleWarning::ProjectorNameMismatch {
+                    model: model.clone(),
+                    projector: projector.clone(),
+                });
+            }
+        }
+
+        if self.shards.len() < self.expected_shards as usize {
+            warnings.push(BundleWarning::MissingShards {
+                expected: self.expected_shards,
+                found: self.shards.len(),
+            });
+        }
+
+        warnings
+    }
+}
+
+/// Read just the header and the keys needed to classify a file
+///
+/// Tokenizer arrays are skipped, so vocab-only files look like models here,
+/// which doesn't matter for finding projectors and adapters. `adapter.*`
+/// and `clip.*` keys are kept for `GgufKind` to recognize untyped files.
+fn read_metadata(path: &Path) -> Result<GgufMetadata> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let header = GgufHeader::read(&mut reader)?;
+    GgufMetadata::read_selected(&mut reader, header.metadata_kv_count, |key| {
+        ["general.", "split.", "adapter.", "clip."].iter().any(|prefix| key.starts_with(prefix))
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Split `<prefix>-00001-of-00003.gguf` into prefix, shard number and count
+fn split_name(name: &str) -> Option<(String, u32, u32)> {
+    let stem = name.strip_suffix(".gguf")?;
+    let (rest, count) = stem.rsplit_once("-of-")?;
+    let (prefix, number) = rest.rsplit_once('-')?;
+    if number.len() != 5 || count.len() != 5 {
+        return None;
+    }
+    Some((prefix.to_string(), number.parse().ok()?, count.parse().ok()?))
+}
+
+/// Lowercase alphanumerics only, so "Gemma-3 4B" and "gemma 3 4b" compare equal
+fn name_key(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
@@ -0,0 +1,160 @@
+/*!
+ * Sidecar Cache for Parsed GGUF Descriptors
+ *
+ * Parsing metadata for many large models at every startup adds up. A parsed
+ * `GgufFile` is stored next to the model as `<file>.aiocache`, keyed by a
+ * cheap fingerprint of the model file. Any problem with the cache falls back
+ * to a full parse.
+ */
+
+use crate::error::Result;
+use crate::options::ParseOptions;
+use crate::GgufFile;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Extension appended to the model file name for its cache
+pub const CACHE_EXTENSION: &str = "aiocache";
+
+/// Bumped whenever the cached layout changes
+const CACHE_FORMAT_VERSION: u32 = 7;
+
+/// Bytes hashed at each end of the model file for the fingerprint
+const FINGERPRINT_WINDOW: u64 = 64 * 1024;
+
+/// Options for `GgufCache::load_or_parse_with_options`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CacheOptions {
+    /// Write a fresh cache after a full parse, disable for read-only directories
+    pub write_cache: bool,
+    /// Options for the full parse when the cache can't be used
+    pub parse_options: ParseOptions,
+}
+
+impl Default for CacheOptions {
+    fn default() -> Self {
+        Self {
+            write_cache: true,
+            parse_options: ParseOptions::default(),
+        }
+    }
+}
+
+/// Identifies one version of a model file without reading all of it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Fingerprint {
+    len: u64,
+    modified_nanos: u128,
+    head_hash: u64,
+    tail_hash: u64,
+}
+
+/// Leading part of a cache file, checked before the cached descriptor is decoded
+///
+/// The parse options are part of the key, so a descriptor parsed leniently
+/// is never returned to a caller asking for a stricter parse.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct CacheHeader {
+    format_version: u32,
+    crate_version: String,
+    fingerprint: Fingerprint,
+    parse_options: ParseOptions,
+}
+
+/// Loads parsed GGUF descriptors from sidecar caches
+pub struct GgufCache;
+
+impl GgufCache {
+    /// Load a GGUF descriptor from its sidecar cache, or parse the file and
+    /// write the cache when it is missing or stale
+    pub fn load_or_parse<P: AsRef<Path>>(path: P) -> Result<GgufFile> {
+        Self::load_or_parse_with_options(path, &CacheOptions::default())
+    }
+
+    /// Load a GGUF descriptor using custom cache options
+    ///
+    /// A cache written with different parse options is treated as stale.
+    /// Corrupt or unwritable caches never cause an error, only a re-parse.
+    pub fn load_or_parse_with_options<P: AsRef<Path>>(path: P, options: &CacheOptions) -> Result<GgufFile> {
+        let path = path.as_ref();
+        let header = CacheHeader {
+            format_version: CACHE_FORMAT_VERSION,
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            fingerprint: fingerprint(path)?,
+            parse_options: options.parse_options.clone(),
+        };
+
+        let cache_path = Self::cache_path(path);
+        if let Some(gguf_file) = read_cache(&cache_path, &header) {
+            return Ok(gguf_file);
+        }
+
+        let gguf_file = GgufFile::from_file_with_options(path, &options.parse_options)?;
+        if options.write_cache {
+            // The cache is an optimization, failing to write it is not an error
+            let _ = write_cache(&cache_path, &header, &gguf_file);
+        }
+        Ok(gguf_file)
+    }
+
+    /// Path of the sidecar cache for a model file, e.g. `model.gguf.aiocache`
+    pub fn cache_path<P: AsRef<Path>>(path: P) -> PathBuf {
+        let mut name = path.as_ref().as_os_str().to_owned();
+        name.push(".");
+        name.push(CACHE_EXTENSION);
+        PathBuf::from(name)
+    }
+}
+
+fn fingerprint(path: &Path) -> Result<Fingerprint> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let len = metadata.len();
+    let modified_nanos = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |duration| duration.as_nanos());
+
+    let mut window = Vec::new();
+    (&mut file).take(FINGERPRINT_WINDOW).read_to_end(&mut window)?;
+    let head_hash = fnv1a(&window);
+
+    window.clear();
+    file.seek(SeekFrom::Start(len.saturating_sub(FINGERPRINT_WINDOW)))?;
+    file.read_to_end(&mut window)?;
+    let tail_hash = fnv1a(&window);
+
+    Ok(Fingerprint { len, modified_nanos, head_hash, tail_hash })
+}
+
+/// 64-bit FNV-1a, stable across Rust releases unlike the std hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+fn read_cache(cache_path: &Path, expected: &CacheHeader) -> Option<GgufFile> {
+    let bytes = fs::read(cache_path).ok()?;
+    let (header, rest): (CacheHeader, _) = postcard::take_from_bytes(&bytes).ok()?;
+    if header != *expected {
+        return None;
+    }
+    postcard::from_bytes(rest).ok()
+}
+
+fn write_cache(cache_path: &Path, header: &CacheHeader, gguf_file: &GgufFile) -> Option<()> {
+    let mut bytes = postcard::to_allocvec(header).ok()?;
+    bytes.extend(postcard::to_allocvec(gguf_file).ok()?);
+
+    // Write then rename so readers never see a partial cache
+    let mut temp_name = cache_path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+    fs::write(&temp_path, bytes).ok()?;
+    fs::rename(&temp_path, cache_path).ok()
+}
@@ -0,0 +1,187 @@
+/*!
+ * Quantization Variant Comparison
+ *
+ * A model is usually published in several quantizations (Q3_K_M, Q4_K_M,
+ * Q5_K_M, ...). `QuantComparison` checks that a set of files really are
+ * variants of one model, then lines up their size, bits per weight and
+ * memory needs so the right trade-off can be picked.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::file_type::FileType;
+use crate::tensor::QuantizationType;
+use crate::GgufFile;
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Bytes per element of the KV cache assumed for RAM estimates, an F16 cache
+const KV_CACHE_DTYPE_BYTES: u64 = 2;
+
+/// Side-by-side summary of quantizations of the same model
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuantComparison {
+    pub architecture: String,
+    pub name: Option<String>,
+    /// Estimated parameter count shared by every variant
+    pub param_count: u64,
+    /// Context length the RAM estimates are for
+    pub context_length: u64,
+    /// One entry per file, smallest first
+    pub variants: Vec<QuantVariant>,
+    /// Tensors whose type isn't the same in every variant, by name
+    pub differing_tensors: Vec<TensorTypeDiff>,
+}
+
+/// Size and memory figures for one quantization of the model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuantVariant {
+    /// `general.file_type` label, else the dominant tensor type
+    pub label: String,
+    pub file_type: Option<FileType>,
+    /// Header, metadata, tensor infos and tensor data
+    pub file_size: u64,
+    /// Tensor data bits divided by tensor elements, across all tensors
+    pub bits_per_weight: f64,
+    /// Tensor data plus an F16 KV cache for one sequence of `context_length`
+    /// tokens, `None` if the head size can't be derived
+    pub estimated_ram: Option<u64>,
+}
+
+/// Types one tensor has across the variants
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorTypeDiff {
+    pub name: String,
+    /// In the order of `QuantComparison::variants`, `None` where the
+    /// variant lacks the tensor
+    pub types: Vec<Option<QuantizationType>>,
+}
+
+impl QuantComparison {
+    /// Compare variants with RAM estimated at the model's own context length
+    ///
+    /// Fails with `GgufError::ModelMismatch` naming the first field that
+    /// differs between the files.
+    pub fn build(files: &[GgufFile]) -> Result<Self> {
+        let context_length = match files.first() {
+            Some(gguf_file) => gguf_file.model_config()?.context_length,
+            None => 0,
+        };
+        Self::build_with_context(files, context_length)
+    }
+
+    /// Compare variants with RAM estimated at `context_length` tokens
+    pub fn build_with_context(files: &[GgufFile], context_length: u64) -> Result<Self> {
+        let Some(first) = files.first() else {
+            return Ok(Self { context_length, ..Default::default() });
+        };
+        let config = first.model_config()?;
+        let param_count = first.estimated_param_count()?;
+
+        for other in &files[1..] {
+            let other_config = other.model_config()?;
+            let fields = [
+                ("architecture", config.architecture.clone(), other_config.architecture.clone()),
+                ("block_count", config.block_count.to_string(), other_config.block_count.to_string()),
+                ("embedding_length", config.embedding_length.to_string(), other_config.embedding_length.to_string()),
+                ("vocab_size", config.vocab_size.to_string(), other_config.vocab_size.to_string()),
+                ("parameter count", param_count.to_string(), other.estimated_param_count()?.to_string()),
+                ("general.name", format!("{:?}", first.name()), format!("{:?}", other.name())),
+            ];
+            if let Some((field, expected, found)) = fields.into_iter().find(|(_, a, b)| a != b) {
+                return Err(GgufError::ModelMismatch { field, expected, found });
+            }
+        }
+
+        let mut variants = Vec::with_capacity(files.len());
+        for (i, gguf_file) in files.iter().enumerate() {
+            let data_size = gguf_file.total_size()?;
+            let elements = gguf_file.tensors.iter().fold(0u64, |total, t| total.saturating_add(t.element_count()));
+            let file_type = gguf_file.quant_label();
+            let label = match (file_type, gguf_file.dominant_quantization_type()) {
+                (Some(file_type), _) => file_type.to_string(),
+                (None, Some(quantization_type)) => quantization_type.to_string(),
+                (None, None) => "unknown".to_string(),
+            };
+            let kv_cache = gguf_file.model_config()?.kv_cache_bytes(context_length, 1, KV_CACHE_DTYPE_BYTES);
+            let variant = QuantVariant {
+                label,
+                file_type,
+                file_size: gguf_file
+                    .data_offset()
+                    .checked_add(data_size)
+                    .ok_or_else(|| GgufError::SizeOverflow("file size".to_string()))?,
+                bits_per_weight: if elements == 0 { 0.0 } else { data_size as f64 * 8.0 / elements as f64 },
+                estimated_ram: kv_cache.and_then(|kv_cache| data_size.checked_add(kv_cache)),
+            };
+            variants.push((i, variant));
+        }
+        variants.sort_by_key(|(_, variant)| variant.file_size);
+
+        let mut types_by_name: BTreeMap<&str, Vec<Option<QuantizationType>>> = BTreeMap::new();
+        for (position, &(i, _)) in variants.iter().enumerate() {
+            for tensor in &files[i].tensors {
+                let types = types_by_name.entry(&tensor.name).or_insert_with(|| vec![None; files.len()]);
+                types[position] = Some(tensor.quantization_type);
+            }
+        }
+        let differing_tensors = types_by_name
+            .into_iter()
+            .filter(|(_, types)| types.iter().collect::<BTreeSet<_>>().len() > 1)
+            .map(|(name, types)| TensorTypeDiff { name: name.to_string(), types })
+            .collect();
+
+        Ok(Self {
+            architecture: config.architecture,
+            name: first.name().map(str::to_string),
+            param_count,
+            context_length,
+            variants: variants.into_iter().map(|(_, variant)| variant).collect(),
+            differing_tensors,
+        })
+    }
+}
+
+impl fmt::Display for QuantComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} ({}, {} parameters), RAM at {} tokens",
+            self.name.as_deref().unwrap_or("unnamed model"),
+            self.architecture,
+            self.param_count,
+            self.context_length
+        )?;
+        let width = self.variants.iter().map(|variant| variant.label.len()).max().unwrap_or(0).max(7);
+        writeln!(f, "  {:<width$}  {:>14} {:>6} {:>14}", "VARIANT", "FILE BYTES", "BPW", "RAM BYTES")?;
+        for variant in &self.variants {
+            let ram = variant.estimated_ram.map_or_else(|| "unknown".to_string(), |ram| ram.to_string());
+            writeln!(
+                f,
+                "  {:<width$}  {:>14} {:>6.2} {:>14}",
+                variant.label, variant.file_size, variant.bits_per_weight, ram
+            )?;
+        }
+
+        if self.differing_tensors.is_empty() {
+            return Ok(());
+        }
+        writeln!(f, "Tensors with differing types:")?;
+        let name_width = self.differing_tensors.iter().map(|diff| diff.name.len()).max().unwrap_or(0);
+        for diff in &self.differing_tensors {
+            write!(f, "  {:<name_width$}", diff.name)?;
+            for quantization_type in &diff.types {
+                match quantization_type {
+                    Some(quantization_type) => write!(f, " {:>8}", quantization_type.to_string())?,
+                    None => write!(f, " {:>8}", "-")?,
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
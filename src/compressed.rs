@@ -0,0 +1,63 @@
+/*!
+ * Compressed GGUF Files
+ *
+ * Some distributions ship `.gguf.gz` or `.gguf.zst`. Decoders only read
+ * forward while the parser seeks, so `from_compressed_reader` decompresses
+ * a growing prefix into memory and parses that. Decompression stops there:
+ * tensor data is never decompressed, reading it needs the whole file
+ * decompressed first.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::options::ParseOptions;
+use crate::prefix::{parse_growing_prefix, INITIAL_PREFIX_BYTES};
+use crate::GgufFile;
+use std::io::{ErrorKind, Read};
+
+/// Largest prefix decompressed before giving up on a parse
+const MAX_PREFIX_BYTES: u64 = 4 << 30;
+
+/// Compression format wrapping a GGUF file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Compression {
+    /// gzip, as in `.gguf.gz`, including files of several gzip members
+    #[cfg(feature = "gzip")]
+    Gzip,
+    /// Zstandard, as in `.gguf.zst`, a single frame
+    #[cfg(feature = "zstd")]
+    Zstd,
+}
+
+pub(crate) fn from_compressed_reader<R: Read>(
+    reader: &mut R,
+    compression: Compression,
+    options: &ParseOptions,
+) -> Result<GgufFile> {
+    let mut decoder: Box<dyn Read + '_> = match compression {
+        #[cfg(feature = "gzip")]
+        Compression::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+        #[cfg(feature = "zstd")]
+        Compression::Zstd => Box::new(
+            ruzstd::decoding::StreamingDecoder::new(reader)
+                .map_err(|error| GgufError::InvalidCompressedData(error.to_string()))?,
+        ),
+    };
+
+    parse_growing_prefix(INITIAL_PREFIX_BYTES, MAX_PREFIX_BYTES, options, |prefix, len| {
+        let wanted = len - prefix.len() as u64;
+        decoder.by_ref().take(wanted).read_to_end(prefix).map_err(decode_error)?;
+        Ok(())
+    })
+}
+
+/// Decoders report corrupt input as I/O errors of their own, which become
+/// `InvalidCompressedData`; errors from the underlying reader carry an OS
+/// error code and stay I/O errors
+fn decode_error(error: std::io::Error) -> GgufError {
+    let corrupt = matches!(error.kind(), ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::Other);
+    if corrupt && error.raw_os_error().is_none() {
+        GgufError::InvalidCompressedData(error.to_string())
+    } else {
+        error.into()
+    }
+}
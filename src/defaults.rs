@@ -0,0 +1,97 @@
+/*!
+ * Architecture Default Values
+ *
+ * Optional hyperparameters a converter left out still have well-known
+ * values: llama.cpp's rope base, KV head count and rotary dimensions, and
+ * the RMS norm epsilon the converters write for each architecture. The raw
+ * `ModelConfig` fields stay `None` in that case, the `resolved_*` methods
+ * and `with_defaults` fill them in.
+ */
+
+use crate::metadata::ModelConfig;
+use alloc::string::ToString;
+
+/// Defaults for an architecture's optional keys
+struct ArchDefaults {
+    rope_freq_base: f32,
+    rms_epsilon: f32,
+}
+
+/// Look up the defaults for an architecture, `None` for architectures
+/// without a known table
+///
+/// `rope_freq_base` is llama.cpp's fallback. `rms_epsilon` is the value the
+/// converters write from the upstream model configs, llama.cpp itself
+/// requires the key.
+fn arch_defaults(architecture: &str) -> Option<ArchDefaults> {
+    let rms_epsilon = match architecture {
+        "llama" | "mistral" | "mixtral" | "codellama" | "granite" | "granitemoe" | "deepseek" | "internlm2"
+        | "minicpm" | "xverse" | "phi3" => 1e-5,
+        "qwen" | "qwen2" | "qwen2moe" | "qwen3" | "gemma" | "gemma2" | "gemma3" | "deepseek2" | "baichuan"
+        | "olmo2" => 1e-6,
+        _ => return None,
+    };
+    Some(ArchDefaults { rope_freq_base: 10_000.0, rms_epsilon })
+}
+
+impl ModelConfig {
+    /// Rope base frequency, falling back to the architecture default
+    pub fn resolved_rope_freq_base(&self) -> Option<f32> {
+        self.rope_freq_base
+            .or_else(|| arch_defaults(&self.architecture).map(|defaults| defaults.rope_freq_base))
+    }
+
+    /// RMS norm epsilon, falling back to the architecture default
+    pub fn resolved_rms_epsilon(&self) -> Option<f32> {
+        self.attention_layer_norm_rms_epsilon
+            .or_else(|| arch_defaults(&self.architecture).map(|defaults| defaults.rms_epsilon))
+    }
+
+    /// KV head count, defaulting to `attention_head_count` (no grouped-query
+    /// attention) for architectures with a default table
+    pub fn resolved_head_count_kv(&self) -> Option<u32> {
+        self.attention_head_count_kv
+            .or_else(|| arch_defaults(&self.architecture).map(|_| self.attention_head_count))
+    }
+
+    /// Rotary dimensions, defaulting to `head_dim()` (rope over the whole
+    /// head) for architectures with a default table
+    pub fn resolved_rope_dimension_count(&self) -> Option<u32> {
+        self.rope_dimension_count
+            .or_else(|| arch_defaults(&self.architecture).map(|_| self.head_dim()))
+    }
+
+    /// Fill missing optional fields with the architecture defaults, listing
+    /// each filled field in `defaulted_fields`
+    ///
+    /// Architectures without a default table are returned unchanged.
+    pub fn with_defaults(mut self) -> Self {
+        const FIELDS: [&str; 4] = [
+            "rope_freq_base",
+            "attention_layer_norm_rms_epsilon",
+            "attention_head_count_kv",
+            "rope_dimension_count",
+        ];
+        let present = |config: &Self| {
+            [
+                config.rope_freq_base.is_some(),
+                config.attention_layer_norm_rms_epsilon.is_some(),
+                config.attention_head_count_kv.is_some(),
+                config.rope_dimension_count.is_some(),
+            ]
+        };
+
+        let before = present(&self);
+        self.rope_freq_base = self.resolved_rope_freq_base();
+        self.attention_layer_norm_rms_epsilon = self.resolved_rms_epsilon();
+        self.attention_head_count_kv = self.resolved_head_count_kv();
+        self.rope_dimension_count = self.resolved_rope_dimension_count();
+
+        for ((field, was_present), is_present) in FIELDS.into_iter().zip(before).zip(present(&self)) {
+            if !was_present && is_present {
+                self.defaulted_fields.push(field.to_string());
+            }
+        }
+        self
+    }
+}
@@ -2,18 +2,53 @@
  * GGUF Parser Error Types
  */
 
+use crate::kind::GgufKind;
+use crate::types::GgufValueType;
+use crate::warning::GgufWarning;
 use thiserror::Error;
 
-pub type Result<T> = std::result::Result<T, GgufError>;
+use alloc::boxed::Box;
+use alloc::string::{FromUtf8Error, String};
+
+pub type Result<T> = core::result::Result<T, GgufError>;
+
+/// Broad kind of a `GgufError`, see `GgufError::category`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Reading failed below the format: missing file, failed disk, dropped connection
+    Io,
+    /// The bytes aren't well-formed GGUF, including files that end early
+    Format,
+    /// Well-formed, but uses a version or type this crate can't handle
+    Unsupported,
+    /// A configured `ParseLimits` bound was exceeded
+    Limit,
+    /// The file parsed but doesn't hold what was asked of it, e.g. a model config
+    Content,
+    /// An argument passed by the caller is invalid
+    Usage,
+}
 
 #[derive(Error, Debug)]
 pub enum GgufError {
+    /// Genuine I/O failure, reads that end early become `UnexpectedEof`
+    #[cfg(feature = "std")]
     #[error("I/O error: {0}")]
-    Io(#[from] std::io::Error),
+    Io(std::io::Error),
+
+    #[cfg(feature = "std")]
+    #[error("I/O error while reading {stage}: {source}")]
+    IoAt {
+        stage: &'static str,
+        source: std::io::Error,
+    },
 
     #[error("Invalid GGUF magic number: expected 'GGUF', found {0:?}")]
     InvalidMagic([u8; 4]),
 
+    #[error("Legacy '{magic}' model file predates GGUF, convert it with llama.cpp's conversion scripts first")]
+    LegacyFormat { magic: String },
+
     #[error("Unsupported GGUF version: {0}")]
     UnsupportedVersion(u32),
 
@@ -23,6 +58,15 @@ pub enum GgufError {
     #[error("Invalid quantization type: {0}")]
     InvalidQuantizationType(u32),
 
+    #[error("Unknown quantization label: {0}")]
+    UnknownQuantizationLabel(String),
+
+    #[error("Size of quantization type {0} is unknown")]
+    UnknownQuantizationSize(u32),
+
+    #[error("Tensor layout of architecture '{0}' is unknown")]
+    UnknownTensorLayout(String),
+
     #[error("Metadata key not found: {0}")]
     MetadataKeyNotFound(String),
 
@@ -34,14 +78,327 @@ pub enum GgufError {
     },
 
     #[error("String is not valid UTF-8: {0}")]
-    InvalidUtf8(#[from] std::string::FromUtf8Error),
+    InvalidUtf8(#[from] FromUtf8Error),
 
     #[error("Unexpected end of file")]
     UnexpectedEof,
 
-    #[error("Invalid tensor dimensions")]
-    InvalidTensorDimensions,
+    #[error("Size of {0} overflows 64 bits")]
+    SizeOverflow(String),
+
+    #[error("Unexpected end of file while reading {stage}")]
+    UnexpectedEofAt { stage: &'static str },
+
+    #[error("Array of {length} {element_type:?} elements needs at least {min_bytes} bytes, exceeding the {available} bytes available")]
+    ArrayTooLarge {
+        element_type: GgufValueType,
+        length: u64,
+        min_bytes: u64,
+        available: u64,
+    },
+
+    #[error("Arrays nested deeper than {0} levels")]
+    ArrayNestingTooDeep(usize),
+
+    #[error("Tensor '{name}' has {n_dimensions} dimensions, more than the maximum of {max}")]
+    InvalidTensorDimensions {
+        name: String,
+        n_dimensions: u32,
+        max: usize,
+    },
+
+    #[error("Array elements must share one type: the first is {expected:?} but another is {found:?}")]
+    MixedArrayTypes {
+        expected: GgufValueType,
+        found: GgufValueType,
+    },
+
+    #[error("Renaming tensors '{first}' and '{second}' would both produce '{name}'")]
+    TensorNameCollision {
+        name: String,
+        first: String,
+        second: String,
+    },
+
+    #[error("File is truncated: tensor data needs {expected} bytes but the file has {actual}, {missing} bytes missing")]
+    TruncatedFile {
+        expected: u64,
+        actual: u64,
+        missing: u64,
+    },
+
+    #[error("File has trailing data: tensor data ends at {expected} bytes but the file has {actual}, {extra} bytes extra")]
+    TrailingData {
+        expected: u64,
+        actual: u64,
+        extra: u64,
+    },
+
+    #[cfg(feature = "json")]
+    #[error("Embedded JSON under '{key}' is invalid at line {line}, column {column}: {message}")]
+    InvalidEmbeddedJson {
+        key: String,
+        line: usize,
+        column: usize,
+        message: String,
+    },
+
+    /// `status` is the HTTP status code, `None` when no response arrived
+    #[cfg(feature = "http")]
+    #[error("HTTP request for {url} failed: {message}")]
+    Http {
+        url: String,
+        status: Option<u16>,
+        message: String,
+    },
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[error("Compressed stream is corrupt: {0}")]
+    InvalidCompressedData(String),
+
+    #[error("Expected a model, found {0}")]
+    NotAModel(GgufKind),
 
     #[error("Model configuration incomplete: missing {0}")]
     IncompleteModelConfig(String),
-}
\ No newline at end of file
+
+    #[error("Files are not variants of the same model: {field} is {expected} in the first file but {found} in another")]
+    ModelMismatch {
+        field: &'static str,
+        expected: String,
+        found: String,
+    },
+
+    #[error("Invalid alignment {0}: must be a power of two that fits in 32 bits")]
+    InvalidAlignment(u64),
+
+    #[cfg(feature = "std")]
+    #[error("Output {0} is the input file, rewriting it in place would destroy it")]
+    OutputIsInput(String),
+
+    #[error("Parse limit {limit} exceeded: {value}")]
+    LimitExceeded { limit: &'static str, value: u64 },
+
+    #[error("Warning treated as an error: {0}")]
+    PromotedWarning(GgufWarning),
+
+    #[error("At byte offset {offset}: {source}")]
+    AtOffset {
+        offset: u64,
+        source: Box<GgufError>,
+    },
+}
+
+/// Compares variants field by field, I/O errors compare by `ErrorKind` since
+/// `std::io::Error` has no equality of its own
+impl PartialEq for GgufError {
+    fn eq(&self, other: &Self) -> bool {
+        use GgufError::*;
+        match (self, other) {
+            #[cfg(feature = "std")]
+            (Io(a), Io(b)) => a.kind() == b.kind(),
+            #[cfg(feature = "std")]
+            (IoAt { stage: a_stage, source: a }, IoAt { stage: b_stage, source: b }) => {
+                a_stage == b_stage && a.kind() == b.kind()
+            }
+            (InvalidMagic(a), InvalidMagic(b)) => a == b,
+            (LegacyFormat { magic: a }, LegacyFormat { magic: b }) => a == b,
+            (UnsupportedVersion(a), UnsupportedVersion(b)) => a == b,
+            (InvalidValueType(a), InvalidValueType(b)) => a == b,
+            (InvalidQuantizationType(a), InvalidQuantizationType(b)) => a == b,
+            (UnknownQuantizationLabel(a), UnknownQuantizationLabel(b)) => a == b,
+            (UnknownQuantizationSize(a), UnknownQuantizationSize(b)) => a == b,
+            (UnknownTensorLayout(a), UnknownTensorLayout(b)) => a == b,
+            (MetadataKeyNotFound(a), MetadataKeyNotFound(b)) => a == b,
+            (
+                InvalidMetadataValueType { key: a_key, expected: a_expected, found: a_found },
+                InvalidMetadataValueType { key: b_key, expected: b_expected, found: b_found },
+            ) => a_key == b_key && a_expected == b_expected && a_found == b_found,
+            (InvalidUtf8(a), InvalidUtf8(b)) => a == b,
+            (UnexpectedEof, UnexpectedEof) => true,
+            (SizeOverflow(a), SizeOverflow(b)) => a == b,
+            (UnexpectedEofAt { stage: a }, UnexpectedEofAt { stage: b }) => a == b,
+            (
+                ArrayTooLarge { element_type: a_type, length: a_length, min_bytes: a_min, available: a_available },
+                ArrayTooLarge { element_type: b_type, length: b_length, min_bytes: b_min, available: b_available },
+            ) => a_type == b_type && a_length == b_length && a_min == b_min && a_available == b_available,
+            (ArrayNestingTooDeep(a), ArrayNestingTooDeep(b)) => a == b,
+            (
+                InvalidTensorDimensions { name: a_name, n_dimensions: a_dims, max: a_max },
+                InvalidTensorDimensions { name: b_name, n_dimensions: b_dims, max: b_max },
+            ) => a_name == b_name && a_dims == b_dims && a_max == b_max,
+            (
+                MixedArrayTypes { expected: a_expected, found: a_found },
+                MixedArrayTypes { expected: b_expected, found: b_found },
+            ) => a_expected == b_expected && a_found == b_found,
+            (
+                TensorNameCollision { name: a_name, first: a_first, second: a_second },
+                TensorNameCollision { name: b_name, first: b_first, second: b_second },
+            ) => a_name == b_name && a_first == b_first && a_second == b_second,
+            (
+                TruncatedFile { expected: a_expected, actual: a_actual, missing: a_missing },
+                TruncatedFile { expected: b_expected, actual: b_actual, missing: b_missing },
+            ) => a_expected == b_expected && a_actual == b_actual && a_missing == b_missing,
+            (
+                TrailingData { expected: a_expected, actual: a_actual, extra: a_extra },
+                TrailingData { expected: b_expected, actual: b_actual, extra: b_extra },
+            ) => a_expected == b_expected && a_actual == b_actual && a_extra == b_extra,
+            #[cfg(feature = "json")]
+            (
+                InvalidEmbeddedJson { key: a_key, line: a_line, column: a_column, message: a_message },
+                InvalidEmbeddedJson { key: b_key, line: b_line, column: b_column, message: b_message },
+            ) => a_key == b_key && a_line == b_line && a_column == b_column && a_message == b_message,
+            #[cfg(feature = "http")]
+            (
+                Http { url: a_url, status: a_status, message: a_message },
+                Http { url: b_url, status: b_status, message: b_message },
+            ) => a_url == b_url && a_status == b_status && a_message == b_message,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            (InvalidCompressedData(a), InvalidCompressedData(b)) => a == b,
+            (NotAModel(a), NotAModel(b)) => a == b,
+            (IncompleteModelConfig(a), IncompleteModelConfig(b)) => a == b,
+            (
+                ModelMismatch { field: a_field, expected: a_expected, found: a_found },
+                ModelMismatch { field: b_field, expected: b_expected, found: b_found },
+            ) => a_field == b_field && a_expected == b_expected && a_found == b_found,
+            (InvalidAlignment(a), InvalidAlignment(b)) => a == b,
+            #[cfg(feature = "std")]
+            (OutputIsInput(a), OutputIsInput(b)) => a == b,
+            (LimitExceeded { limit: a_limit, value: a_value }, LimitExceeded { limit: b_limit, value: b_value }) => {
+                a_limit == b_limit && a_value == b_value
+            }
+            (PromotedWarning(a), PromotedWarning(b)) => a == b,
+            (AtOffset { offset: a_offset, source: a }, AtOffset { offset: b_offset, source: b }) => {
+                a_offset == b_offset && a == b
+            }
+            _ => false,
+        }
+    }
+}
+
+impl GgufError {
+    /// Attach the parse stage to a bare I/O or end of file error
+    pub(crate) fn at_stage(self, stage: &'static str) -> Self {
+        match self {
+            #[cfg(feature = "std")]
+            GgufError::Io(source) => GgufError::IoAt { stage, source },
+            GgufError::UnexpectedEof => GgufError::UnexpectedEofAt { stage },
+            other => other,
+        }
+    }
+
+    /// Get the broad kind of this error, looking through `AtOffset`
+    pub fn category(&self) -> ErrorCategory {
+        use GgufError::*;
+        match self {
+            #[cfg(feature = "std")]
+            Io(_) | IoAt { .. } => ErrorCategory::Io,
+            #[cfg(feature = "http")]
+            Http { .. } => ErrorCategory::Io,
+            InvalidMagic(_)
+            | InvalidValueType(_)
+            | InvalidQuantizationType(_)
+            | InvalidUtf8(_)
+            | UnexpectedEof
+            | UnexpectedEofAt { .. }
+            | SizeOverflow(_)
+            | ArrayTooLarge { .. }
+            | ArrayNestingTooDeep(_)
+            | InvalidTensorDimensions { .. }
+            | TruncatedFile { .. }
+            | TrailingData { .. } => ErrorCategory::Format,
+            #[cfg(feature = "json")]
+            InvalidEmbeddedJson { .. } => ErrorCategory::Format,
+            #[cfg(any(feature = "gzip", feature = "zstd"))]
+            InvalidCompressedData(_) => ErrorCategory::Format,
+            LegacyFormat { .. }
+            | UnsupportedVersion(_)
+            | UnknownQuantizationSize(_)
+            | UnknownTensorLayout(_) => ErrorCategory::Unsupported,
+            LimitExceeded { .. } => ErrorCategory::Limit,
+            MetadataKeyNotFound(_)
+            | InvalidMetadataValueType { .. }
+            | NotAModel(_)
+            | IncompleteModelConfig(_)
+            | ModelMismatch { .. }
+            | PromotedWarning(_) => ErrorCategory::Content,
+            UnknownQuantizationLabel(_)
+            | MixedArrayTypes { .. }
+            | TensorNameCollision { .. }
+            | InvalidAlignment(_) => ErrorCategory::Usage,
+            #[cfg(feature = "std")]
+            OutputIsInput(_) => ErrorCategory::Usage,
+            AtOffset { source, .. } => source.category(),
+        }
+    }
+
+    /// Check whether the file is malformed, so reading it again won't help
+    pub fn is_format_error(&self) -> bool {
+        self.category() == ErrorCategory::Format
+    }
+
+    /// Check whether the same call may succeed if retried
+    ///
+    /// Only transient I/O failures qualify: interrupted or timed out reads,
+    /// dropped connections, and HTTP requests that got no response or a 429
+    /// or 5xx status. Missing files, denied permissions, other HTTP statuses
+    /// such as 404, and every non-I/O error won't go away on their own.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            #[cfg(feature = "std")]
+            GgufError::Io(source) | GgufError::IoAt { source, .. } => {
+                use std::io::ErrorKind::*;
+                matches!(
+                    source.kind(),
+                    Interrupted
+                        | WouldBlock
+                        | TimedOut
+                        | ConnectionReset
+                        | ConnectionAborted
+                        | ConnectionRefused
+                        | NotConnected
+                        | BrokenPipe
+                )
+            }
+            #[cfg(feature = "http")]
+            GgufError::Http { status, .. } => matches!(status, None | Some(429 | 500..=599)),
+            GgufError::AtOffset { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
+/// Reads that end early are a property of the file, not of the I/O, so they
+/// become `UnexpectedEof`
+#[cfg(feature = "std")]
+impl From<std::io::Error> for GgufError {
+    fn from(error: std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::UnexpectedEof => GgufError::UnexpectedEof,
+            _ => GgufError::Io(error),
+        }
+    }
+}
+
+/// For io-centric call sites: I/O errors are unwrapped, anything else keeps
+/// its message under a kind matching its category
+#[cfg(feature = "std")]
+impl From<GgufError> for std::io::Error {
+    fn from(error: GgufError) -> Self {
+        use std::io::ErrorKind;
+        if let GgufError::Io(source) = error {
+            return source;
+        }
+        let kind = match &error {
+            GgufError::IoAt { source, .. } => source.kind(),
+            GgufError::UnexpectedEof | GgufError::UnexpectedEofAt { .. } => ErrorKind::UnexpectedEof,
+            other => match other.category() {
+                ErrorCategory::Io => ErrorKind::Other,
+                ErrorCategory::Unsupported => ErrorKind::Unsupported,
+                ErrorCategory::Usage => ErrorKind::InvalidInput,
+                ErrorCategory::Format | ErrorCategory::Limit | ErrorCategory::Content => ErrorKind::InvalidData,
+            },
+        };
+        std::io::Error::new(kind, error)
+    }
+}
@@ -0,0 +1,256 @@
+/*!
+ * Whole-File Quantization Labels
+ *
+ * `general.file_type` records the llama.cpp `llama_ftype` a file was
+ * quantized with, e.g. `Q4_K_M`, which individual tensor types can't express.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::tensor::{normalize_label, QuantizationType};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::str::FromStr;
+use serde::{Deserialize, Serialize};
+
+/// Quantization label of a whole model file, as stored in `general.file_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[allow(non_camel_case_types)]
+pub enum FileType {
+    F32,
+    F16,
+    Q4_0,
+    Q4_1,
+    Q8_0,
+    Q5_0,
+    Q5_1,
+    Q2_K,
+    Q3_K_S,
+    Q3_K_M,
+    Q3_K_L,
+    Q4_K_S,
+    Q4_K_M,
+    Q5_K_S,
+    Q5_K_M,
+    Q6_K,
+    IQ2_XXS,
+    IQ2_XS,
+    Q2_K_S,
+    IQ3_XS,
+    IQ3_XXS,
+    IQ1_S,
+    IQ4_NL,
+    IQ3_S,
+    IQ3_M,
+    IQ2_S,
+    IQ2_M,
+    IQ4_XS,
+    IQ1_M,
+    BF16,
+    TQ1_0,
+    TQ2_0,
+    /// An id this crate doesn't know yet
+    Unknown(u32),
+}
+
+impl FileType {
+    /// Get the numeric `llama_ftype` id
+    pub fn id(&self) -> u32 {
+        match self {
+            FileType::F32 => 0,
+            FileType::F16 => 1,
+            FileType::Q4_0 => 2,
+            FileType::Q4_1 => 3,
+            FileType::Q8_0 => 7,
+            FileType::Q5_0 => 8,
+            FileType::Q5_1 => 9,
+            FileType::Q2_K => 10,
+            FileType::Q3_K_S => 11,
+            FileType::Q3_K_M => 12,
+            FileType::Q3_K_L => 13,
+            FileType::Q4_K_S => 14,
+            FileType::Q4_K_M => 15,
+            FileType::Q5_K_S => 16,
+            FileType::Q5_K_M => 17,
+            FileType::Q6_K => 18,
+            FileType::IQ2_XXS => 19,
+            FileType::IQ2_XS => 20,
+            FileType::Q2_K_S => 21,
+            FileType::IQ3_XS => 22,
+            FileType::IQ3_XXS => 23,
+            FileType::IQ1_S => 24,
+            FileType::IQ4_NL => 25,
+            FileType::IQ3_S => 26,
+            FileType::IQ3_M => 27,
+            FileType::IQ2_S => 28,
+            FileType::IQ2_M => 29,
+            FileType::IQ4_XS => 30,
+            FileType::IQ1_M => 31,
+            FileType::BF16 => 32,
+            FileType::TQ1_0 => 36,
+            FileType::TQ2_0 => 37,
+            FileType::Unknown(id) => *id,
+        }
+    }
+
+    /// Get the label llama.cpp uses for this file type, e.g. `"Q4_K_M"`
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileType::F32 => "F32",
+            FileType::F16 => "F16",
+            FileType::Q4_0 => "Q4_0",
+            FileType::Q4_1 => "Q4_1",
+            FileType::Q8_0 => "Q8_0",
+            FileType::Q5_0 => "Q5_0",
+            FileType::Q5_1 => "Q5_1",
+            FileType::Q2_K => "Q2_K",
+            FileType::Q3_K_S => "Q3_K_S",
+            FileType::Q3_K_M => "Q3_K_M",
+            FileType::Q3_K_L => "Q3_K_L",
+            FileType::Q4_K_S => "Q4_K_S",
+            FileType::Q4_K_M => "Q4_K_M",
+            FileType::Q5_K_S => "Q5_K_S",
+            FileType::Q5_K_M => "Q5_K_M",
+            FileType::Q6_K => "Q6_K",
+            FileType::IQ2_XXS => "IQ2_XXS",
+            FileType::IQ2_XS => "IQ2_XS",
+            FileType::Q2_K_S => "Q2_K_S",
+            FileType::IQ3_XS => "IQ3_XS",
+            FileType::IQ3_XXS => "IQ3_XXS",
+            FileType::IQ1_S => "IQ1_S",
+            FileType::IQ4_NL => "IQ4_NL",
+            FileType::IQ3_S => "IQ3_S",
+            FileType::IQ3_M => "IQ3_M",
+            FileType::IQ2_S => "IQ2_S",
+            FileType::IQ2_M => "IQ2_M",
+            FileType::IQ4_XS => "IQ4_XS",
+            FileType::IQ1_M => "IQ1_M",
+            FileType::BF16 => "BF16",
+            FileType::TQ1_0 => "TQ1_0",
+            FileType::TQ2_0 => "TQ2_0",
+            FileType::Unknown(_) => "unknown",
+        }
+    }
+
+    /// Get the tensor type llama.cpp's recipe for this file type uses for
+    /// most weight matrices, e.g. `Q4_K` for `Q4_K_M`
+    pub fn base_quantization_type(&self) -> Option<QuantizationType> {
+        let base = match self {
+            FileType::F32 => QuantizationType::F32,
+            FileType::F16 => QuantizationType::F16,
+            FileType::Q4_0 => QuantizationType::Q4_0,
+            FileType::Q4_1 => QuantizationType::Q4_1,
+            FileType::Q8_0 => QuantizationType::Q8_0,
+            FileType::Q5_0 => QuantizationType::Q5_0,
+            FileType::Q5_1 => QuantizationType::Q5_1,
+            FileType::Q2_K | FileType::Q2_K_S => QuantizationType::Q2_K,
+            FileType::Q3_K_S | FileType::Q3_K_M | FileType::Q3_K_L => QuantizationType::Q3_K,
+            FileType::Q4_K_S | FileType::Q4_K_M => QuantizationType::Q4_K,
+            FileType::Q5_K_S | FileType::Q5_K_M => QuantizationType::Q5_K,
+            FileType::Q6_K => QuantizationType::Q6_K,
+            FileType::IQ2_XXS => QuantizationType::IQ2_XXS,
+            FileType::IQ2_XS | FileType::IQ2_S => QuantizationType::IQ2_XS,
+            FileType::IQ2_M => QuantizationType::IQ2_S,
+            FileType::IQ3_XXS => QuantizationType::IQ3_XXS,
+            FileType::IQ3_XS | FileType::IQ3_S | FileType::IQ3_M => QuantizationType::IQ3_S,
+            FileType::IQ1_S => QuantizationType::IQ1_S,
+            FileType::IQ1_M => QuantizationType::IQ1_M,
+            FileType::IQ4_NL => QuantizationType::IQ4_NL,
+            FileType::IQ4_XS => QuantizationType::IQ4_XS,
+            FileType::BF16 => QuantizationType::BF16,
+            FileType::TQ1_0 => QuantizationType::TQ1_0,
+            FileType::TQ2_0 => QuantizationType::TQ2_0,
+            FileType::Unknown(_) => return None,
+        };
+        Some(base)
+    }
+}
+
+/// How well the declared `general.file_type` matches the tensors actually stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileTypeConsistency {
+    /// Label from `general.file_type`
+    pub declared: FileType,
+    /// Main tensor type of the declared recipe, `None` for unknown labels
+    pub expected: Option<QuantizationType>,
+    /// Most common tensor type in the file
+    pub dominant: Option<QuantizationType>,
+    /// Weight matrices stored with a type other than `expected`
+    ///
+    /// Mixed recipes legitimately promote some tensors, e.g. `attn_v` to
+    /// Q6_K in a Q4_K_M file, so a few entries here are normal.
+    pub deviating: Vec<String>,
+}
+
+impl FileTypeConsistency {
+    /// Whether the dominant tensor type is the one the label promises
+    ///
+    /// A file declared Q4_K_M whose tensors are mostly Q8_0 is the typical
+    /// re-upload mistake this catches. Unknown labels are never consistent.
+    pub fn is_consistent(&self) -> bool {
+        self.expected.is_some() && self.expected == self.dominant
+    }
+}
+
+impl From<u32> for FileType {
+    fn from(value: u32) -> Self {
+        match value {
+            0 => FileType::F32,
+            1 => FileType::F16,
+            2 => FileType::Q4_0,
+            3 => FileType::Q4_1,
+            7 => FileType::Q8_0,
+            8 => FileType::Q5_0,
+            9 => FileType::Q5_1,
+            10 => FileType::Q2_K,
+            11 => FileType::Q3_K_S,
+            12 => FileType::Q3_K_M,
+            13 => FileType::Q3_K_L,
+            14 => FileType::Q4_K_S,
+            15 => FileType::Q4_K_M,
+            16 => FileType::Q5_K_S,
+            17 => FileType::Q5_K_M,
+            18 => FileType::Q6_K,
+            19 => FileType::IQ2_XXS,
+            20 => FileType::IQ2_XS,
+            21 => FileType::Q2_K_S,
+            22 => FileType::IQ3_XS,
+            23 => FileType::IQ3_XXS,
+            24 => FileType::IQ1_S,
+            25 => FileType::IQ4_NL,
+            26 => FileType::IQ3_S,
+            27 => FileType::IQ3_M,
+            28 => FileType::IQ2_S,
+            29 => FileType::IQ2_M,
+            30 => FileType::IQ4_XS,
+            31 => FileType::IQ1_M,
+            32 => FileType::BF16,
+            36 => FileType::TQ1_0,
+            37 => FileType::TQ2_0,
+            other => FileType::Unknown(other),
+        }
+    }
+}
+
+impl fmt::Display for FileType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FileType::Unknown(id) => write!(f, "unknown({id})"),
+            known => f.write_str(known.label()),
+        }
+    }
+}
+
+impl FromStr for FileType {
+    type Err = GgufError;
+
+    /// Parse a label like `"Q4_K_M"`, ignoring case and separators
+    fn from_str(label: &str) -> Result<Self> {
+        let wanted = normalize_label(label);
+        (0..=u8::MAX as u32)
+            .map(FileType::from)
+            .filter(|t| !matches!(t, FileType::Unknown(_)))
+            .find(|t| normalize_label(t.label()) == wanted)
+            .ok_or_else(|| GgufError::UnknownQuantizationLabel(label.to_string()))
+    }
+}
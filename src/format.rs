@@ -0,0 +1,120 @@
+/*!
+ * File Format Detection
+ */
+
+use crate::header::GGUF_MAGIC;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{Read, Seek, SeekFrom};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// Pre-GGUF magics, as written on disk (little-endian u32) and as plain text
+const LEGACY_MAGICS: &[(&[u8; 4], &str)] = &[
+    (b"lmgg", "ggml"),
+    (b"fmgg", "ggmf"),
+    (b"tjgg", "ggjt"),
+    (b"algg", "ggla"),
+    (b"ggml", "ggml"),
+    (b"ggmf", "ggmf"),
+    (b"ggjt", "ggjt"),
+    (b"ggla", "ggla"),
+];
+
+const ZIP_MAGIC: [u8; 4] = *b"PK\x03\x04";
+
+/// Kind of file detected from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormatKind {
+    /// GGUF file with the given version
+    Gguf(u32),
+    /// Pre-GGUF GGML family file (ggml, ggmf, ggjt, ggla)
+    LegacyGgml(&'static str),
+    /// Zip archive (e.g. a PyTorch checkpoint)
+    Zip,
+    /// Safetensors file
+    Safetensors,
+    Unknown,
+}
+
+impl FormatKind {
+    /// Classify a file from its leading bytes
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let Some(magic) = bytes.get(..4) else {
+            return FormatKind::Unknown;
+        };
+
+        if magic == GGUF_MAGIC {
+            return match bytes.get(4..8) {
+                Some(version) => FormatKind::Gguf(u32::from_le_bytes(version.try_into().unwrap())),
+                None => FormatKind::Unknown,
+            };
+        }
+
+        if let Some(name) = legacy_magic_name(magic) {
+            return FormatKind::LegacyGgml(name);
+        }
+
+        if magic == ZIP_MAGIC {
+            return FormatKind::Zip;
+        }
+
+        // Safetensors: little-endian u64 header length followed by a JSON object
+        if let (Some(length), Some(b'{')) = (bytes.get(..8), bytes.get(8)) {
+            let length = u64::from_le_bytes(length.try_into().unwrap());
+            if length > 0 {
+                return FormatKind::Safetensors;
+            }
+        }
+
+        FormatKind::Unknown
+    }
+}
+
+/// Name of a legacy GGML-family magic, if the bytes match one
+pub(crate) fn legacy_magic_name(magic: &[u8]) -> Option<&'static str> {
+    LEGACY_MAGICS
+        .iter()
+        .find(|(bytes, _)| bytes.as_slice() == magic)
+        .map(|(_, name)| *name)
+}
+
+/// Detect the file format by reading at most 9 leading bytes
+#[cfg(feature = "std")]
+pub fn sniff<R: Read>(reader: &mut R) -> std::io::Result<FormatKind> {
+    let mut bytes = Vec::with_capacity(9);
+    reader.take(9).read_to_end(&mut bytes)?;
+    Ok(FormatKind::from_bytes(&bytes))
+}
+
+/// Check whether a file is a GGUF file, regardless of its extension
+#[cfg(feature = "std")]
+pub fn is_gguf<P: AsRef<Path>>(path: P) -> bool {
+    File::open(path)
+        .and_then(|mut file| sniff(&mut file))
+        .is_ok_and(|kind| matches!(kind, FormatKind::Gguf(_)))
+}
+
+/// Find where GGUF content starts within the first `scan_limit` bytes,
+/// for GGUFs prefixed by another container such as a self-extracting bundle
+///
+/// Scans from the start of the reader. When the magic is found the reader is
+/// left positioned at it.
+#[cfg(feature = "std")]
+pub fn find_gguf_offset<R: Read + Seek>(reader: &mut R, scan_limit: u64) -> std::io::Result<Option<u64>> {
+    reader.seek(SeekFrom::Start(0))?;
+    // Include enough extra bytes to see a magic starting at the last scanned offset
+    let window = scan_limit.saturating_add(GGUF_MAGIC.len() as u64 - 1);
+    let mut bytes = Vec::new();
+    reader.take(window).read_to_end(&mut bytes)?;
+
+    let offset = bytes
+        .windows(GGUF_MAGIC.len())
+        .position(|candidate| candidate == GGUF_MAGIC)
+        .map(|position| position as u64);
+    if let Some(offset) = offset {
+        reader.seek(SeekFrom::Start(offset))?;
+    }
+    Ok(offset)
+}
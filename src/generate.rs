@@ -0,0 +1,108 @@
+/*!
+ * Arbitrary Generators
+ *
+ * `Arbitrary` implementations for structured fuzzing and property tests.
+ * Generated values are ones the writer can encode and the parser reads back
+ * unchanged: headers carry the real magic and a supported version, arrays
+ * hold a single element type and respect `MAX_ARRAY_DEPTH`, and tensors have
+ * at most `DEFAULT_MAX_TENSOR_DIMS` dimensions.
+ */
+
+use crate::header::{GgufHeader, GGUF_MAGIC, SUPPORTED_VERSIONS};
+use crate::options::DEFAULT_MAX_TENSOR_DIMS;
+use crate::quant_info::QUANT_INFO;
+use crate::tensor::{QuantizationType, TensorInfo};
+use crate::types::{GgufValue, GgufValueType, MAX_ARRAY_DEPTH};
+use alloc::vec::Vec;
+use arbitrary::{Arbitrary, Result, Unstructured};
+
+/// Every value type, in id order
+const VALUE_TYPES: [GgufValueType; 13] = [
+    GgufValueType::Uint8,
+    GgufValueType::Int8,
+    GgufValueType::Uint16,
+    GgufValueType::Int16,
+    GgufValueType::Uint32,
+    GgufValueType::Int32,
+    GgufValueType::Float32,
+    GgufValueType::Bool,
+    GgufValueType::String,
+    GgufValueType::Array,
+    GgufValueType::Uint64,
+    GgufValueType::Int64,
+    GgufValueType::Float64,
+];
+
+impl<'a> Arbitrary<'a> for GgufHeader {
+    /// The counts are arbitrary, callers writing whole files set them to
+    /// match what follows
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        Ok(Self {
+            magic: GGUF_MAGIC,
+            version: *u.choose(SUPPORTED_VERSIONS)?,
+            tensor_count: u.arbitrary()?,
+            metadata_kv_count: u.arbitrary()?,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for GgufValue {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let value_type = *u.choose(&VALUE_TYPES)?;
+        arbitrary_value(u, value_type, 0)
+    }
+}
+
+/// Generate a value of `value_type`, where `depth` counts the arrays it is
+/// nested in
+fn arbitrary_value(u: &mut Unstructured<'_>, value_type: GgufValueType, depth: usize) -> Result<GgufValue> {
+    Ok(match value_type {
+        GgufValueType::Uint8 => GgufValue::Uint8(u.arbitrary()?),
+        GgufValueType::Int8 => GgufValue::Int8(u.arbitrary()?),
+        GgufValueType::Uint16 => GgufValue::Uint16(u.arbitrary()?),
+        GgufValueType::Int16 => GgufValue::Int16(u.arbitrary()?),
+        GgufValueType::Uint32 => GgufValue::Uint32(u.arbitrary()?),
+        GgufValueType::Int32 => GgufValue::Int32(u.arbitrary()?),
+        GgufValueType::Float32 => GgufValue::Float32(u.arbitrary()?),
+        GgufValueType::Bool => GgufValue::Bool(u.arbitrary()?),
+        GgufValueType::String => GgufValue::String(u.arbitrary()?),
+        GgufValueType::Array => {
+            // The innermost allowed array can't hold arrays itself
+            let element_types: Vec<GgufValueType> = VALUE_TYPES
+                .into_iter()
+                .filter(|&element_type| element_type != GgufValueType::Array || depth + 1 < MAX_ARRAY_DEPTH)
+                .collect();
+            let element_type = *u.choose(&element_types)?;
+            // At most one element per remaining input byte
+            let length = u.arbitrary_len::<u8>()?;
+            let elements = (0..length).map(|_| arbitrary_value(u, element_type, depth + 1)).collect::<Result<_>>()?;
+            GgufValue::Array(elements)
+        }
+        GgufValueType::Uint64 => GgufValue::Uint64(u.arbitrary()?),
+        GgufValueType::Int64 => GgufValue::Int64(u.arbitrary()?),
+        GgufValueType::Float64 => GgufValue::Float64(u.arbitrary()?),
+    })
+}
+
+impl<'a> Arbitrary<'a> for QuantizationType {
+    /// Mostly known types, sometimes an arbitrary id, which may be unknown
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.ratio(1u8, 8u8)? {
+            return Ok(QuantizationType::from_id(u.arbitrary()?));
+        }
+        Ok(u.choose(QUANT_INFO)?.quantization_type)
+    }
+}
+
+impl<'a> Arbitrary<'a> for TensorInfo {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let n_dimensions = u.int_in_range(0..=DEFAULT_MAX_TENSOR_DIMS)?;
+        let dimensions = (0..n_dimensions).map(|_| u.arbitrary()).collect::<Result<_>>()?;
+        Ok(Self {
+            name: u.arbitrary()?,
+            dimensions,
+            quantization_type: u.arbitrary()?,
+            offset: u.arbitrary()?,
+        })
+    }
+}
@@ -3,13 +3,18 @@
  */
 
 use crate::error::{GgufError, Result};
-use std::io::{Read, Seek};
+use crate::format::legacy_magic_name;
+use crate::io::{Read, Seek};
+use crate::read::{read_bytes, read_u32, read_u64};
+use alloc::string::ToString;
+use serde::{Deserialize, Serialize};
 
-const GGUF_MAGIC: [u8; 4] = *b"GGUF";
-const SUPPORTED_VERSION: u32 = 3;
+pub(crate) const GGUF_MAGIC: [u8; 4] = *b"GGUF";
+/// Versions accepted by `GgufHeader::read`
+pub const SUPPORTED_VERSIONS: &[u32] = &[3];
 
 /// GGUF file header
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct GgufHeader {
     pub magic: [u8; 4],
     pub version: u32,
@@ -18,34 +23,46 @@ pub struct GgufHeader {
 }
 
 impl GgufHeader {
+    /// Header size in bytes: magic + version + tensor_count + metadata_kv_count
+    pub const SIZE: usize = 4 + 4 + 8 + 8;
+
     /// Read GGUF header from a reader
     pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        Self::read_with_versions(reader, SUPPORTED_VERSIONS)
+    }
+
+    /// Read GGUF header accepting only the given format versions
+    ///
+    /// Lets experimental tooling knowingly accept versions this crate
+    /// doesn't officially support; the rest of the file is still parsed
+    /// with the v3 layout.
+    pub fn read_with_versions<R: Read + Seek>(reader: &mut R, allowed: &[u32]) -> Result<Self> {
+        Self::read_fields(reader, allowed).map_err(|e| e.at_stage("header"))
+    }
+
+    fn read_fields<R: Read + Seek>(reader: &mut R, allowed: &[u32]) -> Result<Self> {
         // Read magic number
-        let mut magic = [0u8; 4];
-        reader.read_exact(&mut magic)?;
+        let magic: [u8; 4] = read_bytes(reader)?;
         
         if magic != GGUF_MAGIC {
+            if let Some(name) = legacy_magic_name(&magic) {
+                return Err(GgufError::LegacyFormat { magic: name.to_string() });
+            }
             return Err(GgufError::InvalidMagic(magic));
         }
 
         // Read version
-        let mut version_buf = [0u8; 4];
-        reader.read_exact(&mut version_buf)?;
-        let version = u32::from_le_bytes(version_buf);
+        let version = read_u32(reader)?;
 
-        if version != SUPPORTED_VERSION {
+        if !allowed.contains(&version) {
             return Err(GgufError::UnsupportedVersion(version));
         }
 
         // Read tensor count
-        let mut tensor_count_buf = [0u8; 8];
-        reader.read_exact(&mut tensor_count_buf)?;
-        let tensor_count = u64::from_le_bytes(tensor_count_buf);
+        let tensor_count = read_u64(reader)?;
 
         // Read metadata key-value count
-        let mut metadata_kv_count_buf = [0u8; 8];
-        reader.read_exact(&mut metadata_kv_count_buf)?;
-        let metadata_kv_count = u64::from_le_bytes(metadata_kv_count_buf);
+        let metadata_kv_count = read_u64(reader)?;
 
         Ok(Self {
             magic,
@@ -56,12 +73,13 @@ impl GgufHeader {
     }
 
     /// Get header size in bytes
+    #[deprecated(note = "the header size does not depend on the instance, use `GgufHeader::SIZE`")]
     pub fn size(&self) -> usize {
-        4 + 4 + 8 + 8 // magic + version + tensor_count + metadata_kv_count
+        Self::SIZE
     }
 
     /// Check if this is a valid GGUF file
     pub fn is_valid(&self) -> bool {
-        self.magic == GGUF_MAGIC && self.version == SUPPORTED_VERSION
+        self.magic == GGUF_MAGIC && SUPPORTED_VERSIONS.contains(&self.version)
     }
 }
\ No newline at end of file
@@ -0,0 +1,46 @@
+/*!
+ * Remote GGUF Headers over HTTP
+ *
+ * Everything `GgufFile` holds sits at the start of the file, before the
+ * tensor data. `from_url` fetches only that prefix with range requests,
+ * each one asking for the bytes past what earlier requests returned.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::limits::ParseLimits;
+use crate::options::ParseOptions;
+use crate::prefix::parse_growing_prefix;
+use crate::GgufFile;
+use std::io::Read;
+
+pub(crate) fn from_url(url: &str, initial_len: u64) -> Result<GgufFile> {
+    // Metadata larger than this fails the parse limits anyway
+    let max_len = ParseLimits::default().max_total_bytes;
+    parse_growing_prefix(initial_len, max_len, &ParseOptions::default(), |prefix, len| {
+        fetch_range(url, prefix, len)
+    })
+}
+
+/// Append bytes `prefix.len()..len` of `url` to `prefix`
+fn fetch_range(url: &str, prefix: &mut Vec<u8>, len: u64) -> Result<()> {
+    let start = prefix.len() as u64;
+    let response = ureq::get(url)
+        .set("Range", &format!("bytes={start}-{}", len - 1))
+        .call()
+        .map_err(|error| GgufError::Http {
+            url: url.to_string(),
+            status: match &error {
+                ureq::Error::Status(status, _) => Some(*status),
+                ureq::Error::Transport(_) => None,
+            },
+            message: error.to_string(),
+        })?;
+
+    // Servers without range support send the whole file, skip what we have
+    // and read only as far as needed
+    let skip = if response.status() == 206 { 0 } else { start };
+    let mut body = response.into_reader();
+    std::io::copy(&mut body.by_ref().take(skip), &mut std::io::sink())?;
+    body.take(len - start).read_to_end(prefix)?;
+    Ok(())
+}
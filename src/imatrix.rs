@@ -0,0 +1,67 @@
+/*!
+ * Importance Matrix Provenance
+ *
+ * `llama-quantize` records which importance matrix guided quantization in
+ * `quantize.imatrix.*` keys. Most IQ types are practically unusable without
+ * one, so a file using them but lacking these keys is worth flagging.
+ */
+
+use crate::keys;
+use crate::metadata::GgufMetadata;
+use crate::tensor::{QuantizationType, TensorInfo};
+use alloc::string::{String, ToString};
+use serde::{Deserialize, Serialize};
+
+/// Importance matrix details recorded in a quantized file
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ImatrixInfo {
+    /// Path of the imatrix file used, as given to `llama-quantize`
+    pub file: Option<String>,
+    /// Calibration dataset the imatrix was computed from
+    pub dataset: Option<String>,
+    /// Number of tensors covered by the imatrix
+    pub entries_count: Option<u64>,
+    /// Number of calibration chunks the imatrix was computed over
+    pub chunks_count: Option<u64>,
+    /// Whether any tensor uses an IQ type that practically requires an
+    /// imatrix, see `needs_imatrix`
+    pub uses_iq_types: bool,
+}
+
+impl ImatrixInfo {
+    /// Collect imatrix details, `None` when there are no imatrix keys and no IQ tensors
+    pub(crate) fn from_parts(metadata: &GgufMetadata, tensors: &[TensorInfo]) -> Option<Self> {
+        let count = |key: &str| {
+            metadata
+                .get(key)
+                .and_then(|value| value.integer_value())
+                .and_then(|value| u64::try_from(value).ok())
+        };
+        let info = Self {
+            file: metadata.get_string_opt(keys::QUANTIZE_IMATRIX_FILE).map(ToString::to_string),
+            dataset: metadata.get_string_opt(keys::QUANTIZE_IMATRIX_DATASET).map(ToString::to_string),
+            entries_count: count(keys::QUANTIZE_IMATRIX_ENTRIES_COUNT),
+            chunks_count: count(keys::QUANTIZE_IMATRIX_CHUNKS_COUNT),
+            uses_iq_types: tensors.iter().any(|t| needs_imatrix(t.quantization_type)),
+        };
+        (info.has_metadata() || info.uses_iq_types).then_some(info)
+    }
+
+    /// Whether any `quantize.imatrix.*` key is present
+    pub fn has_metadata(&self) -> bool {
+        self.file.is_some() || self.dataset.is_some() || self.entries_count.is_some() || self.chunks_count.is_some()
+    }
+
+    /// IQ types without imatrix metadata, e.g. an IQ2_XS file of unknown provenance
+    pub fn is_suspicious(&self) -> bool {
+        self.uses_iq_types && !self.has_metadata()
+    }
+}
+
+/// Check whether a type is an IQ type that quantizes badly without an imatrix
+///
+/// IQ4_NL and IQ4_XS are non-linear 4-bit types that hold up without one.
+fn needs_imatrix(quantization_type: QuantizationType) -> bool {
+    !matches!(quantization_type, QuantizationType::IQ4_NL | QuantizationType::IQ4_XS)
+        && quantization_type.info().is_some_and(|info| info.is_imatrix)
+}
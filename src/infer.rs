@@ -0,0 +1,117 @@
+/*!
+ * Model Configuration Inference from Tensor Shapes
+ */
+
+use crate::error::{GgufError, Result};
+use crate::keys::{self, arch_key};
+use crate::metadata::{GgufMetadata, ModelConfig};
+use crate::GgufFile;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+/// Context length assumed when neither metadata nor tensors provide one
+pub const DEFAULT_CONTEXT_LENGTH: u64 = 2048;
+
+impl ModelConfig {
+    /// Extract model configuration, deducing missing fields from the tensor list
+    ///
+    /// Metadata always wins. Gaps are filled from tensor shapes (ggml order,
+    /// innermost dimension first):
+    /// - `architecture` from the tensor families present (`ssm_*` vs `attn_*`)
+    /// - `block_count` from the highest `blk.N` index
+    /// - `embedding_length` and `vocab_size` from `token_embd.weight`
+    /// - `feed_forward_length` from `blk.0.ffn_up.weight`
+    /// - `attention.head_count` from the embedding width and the rope dimension
+    ///   count, assuming 128-wide heads (64 for narrow models) when absent
+    /// - `attention.head_count_kv` from `blk.0.attn_k.weight`
+    /// - `context_length` from `position_embd.weight`, else `DEFAULT_CONTEXT_LENGTH`
+    ///
+    /// Every deduced field is listed in `inferred_fields`.
+    pub fn infer(gguf_file: &GgufFile) -> Result<Self> {
+        let mut metadata = gguf_file.metadata.clone();
+        let mut inferred = Vec::new();
+
+        let find = |name: &str| gguf_file.tensors.iter().find(|t| t.name == name);
+        let has_tensor = |part: &str| gguf_file.tensors.iter().any(|t| t.name.contains(part));
+
+        let architecture = match metadata.get_string_opt(keys::GENERAL_ARCHITECTURE) {
+            Some(architecture) => architecture.to_string(),
+            None => {
+                let architecture = if has_tensor(".ssm_") {
+                    "mamba"
+                } else if has_tensor(".attn_q.") || has_tensor(".attn_qkv.") {
+                    "llama"
+                } else {
+                    return Err(GgufError::IncompleteModelConfig("architecture".to_string()));
+                };
+                metadata.insert(keys::GENERAL_ARCHITECTURE, architecture);
+                inferred.push("architecture".to_string());
+                architecture.to_string()
+            }
+        };
+        let arch = architecture.as_str();
+
+        let embedding = find("token_embd.weight");
+        let embedding_length = metadata
+            .get_u64_opt(&arch_key(arch, keys::EMBEDDING_LENGTH))
+            .or_else(|| embedding.and_then(|t| t.dimensions.first().copied()));
+
+        let block_count = gguf_file
+            .tensors
+            .iter()
+            .filter_map(|t| t.name.strip_prefix("blk."))
+            .filter_map(|rest| rest.split('.').next()?.parse::<u64>().ok())
+            .max()
+            .and_then(|max| max.checked_add(1));
+        fill(&mut metadata, &mut inferred, "block_count", arch_key(arch, keys::BLOCK_COUNT), block_count);
+
+        fill(&mut metadata, &mut inferred, "embedding_length", arch_key(arch, keys::EMBEDDING_LENGTH), embedding_length);
+
+        if metadata.get(keys::TOKENIZER_GGML_TOKENS).is_none() && !metadata.contains(keys::GENERAL_VOCAB_SIZE) {
+            let vocab_size = embedding.and_then(|t| t.dimensions.get(1).copied());
+            fill(&mut metadata, &mut inferred, "vocab_size", arch_key(arch, keys::VOCAB_SIZE), vocab_size);
+        }
+
+        let feed_forward_length = find("blk.0.ffn_up.weight").and_then(|t| t.dimensions.get(1).copied());
+        fill(&mut metadata, &mut inferred, "feed_forward_length", arch_key(arch, keys::FEED_FORWARD_LENGTH), feed_forward_length);
+
+        // Head width is not recoverable from shapes alone
+        let head_dim = metadata
+            .get_u64_opt(&arch_key(arch, keys::ROPE_DIMENSION_COUNT))
+            .or_else(|| embedding_length.map(|n| if n % 128 == 0 { 128 } else { 64 }));
+        let head_count = embedding_length
+            .zip(head_dim)
+            .filter(|&(n, d)| d > 0 && n % d == 0)
+            .map(|(n, d)| n / d);
+        fill(&mut metadata, &mut inferred, "attention.head_count", arch_key(arch, keys::ATTENTION_HEAD_COUNT), head_count);
+
+        let head_count_kv = find("blk.0.attn_k.weight")
+            .and_then(|t| t.dimensions.get(1).copied())
+            .zip(head_dim)
+            .filter(|&(k, d)| d > 0 && k % d == 0)
+            .map(|(k, d)| k / d);
+        fill(&mut metadata, &mut inferred, "attention.head_count_kv", arch_key(arch, keys::ATTENTION_HEAD_COUNT_KV), head_count_kv);
+
+        if !metadata.contains(keys::GENERAL_CONTEXT_LENGTH) {
+            let context_length = find("position_embd.weight")
+                .and_then(|t| t.dimensions.get(1).copied())
+                .unwrap_or(DEFAULT_CONTEXT_LENGTH);
+            fill(&mut metadata, &mut inferred, "context_length", arch_key(arch, keys::CONTEXT_LENGTH), Some(context_length));
+        }
+
+        let mut config = ModelConfig::from_metadata(&metadata)?;
+        config.inferred_fields = inferred;
+        Ok(config)
+    }
+}
+
+/// Insert a deduced value unless the key is already present
+fn fill(metadata: &mut GgufMetadata, inferred: &mut Vec<String>, field: &str, key: String, value: Option<u64>) {
+    if metadata.contains(&key) {
+        return;
+    }
+    if let Some(value) = value {
+        metadata.insert(key, value);
+        inferred.push(field.to_string());
+    }
+}
@@ -0,0 +1,75 @@
+/*!
+ * One-Stop File Summary
+ *
+ * `GgufFile::inspect` gathers what a CLI typically prints about a model into
+ * one serializable report.
+ */
+
+use crate::header::GgufHeader;
+use crate::metadata::ModelConfig;
+use crate::tensor::QuantizationType;
+use crate::GgufFile;
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Number of tensors listed in `InspectReport::largest_tensors`
+pub const INSPECT_LARGEST_TENSORS: usize = 10;
+
+/// Structured summary of a GGUF file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InspectReport {
+    pub header: GgufHeader,
+    /// `None` when the metadata lacks required configuration keys
+    pub model_config: Option<ModelConfig>,
+    pub tensor_count: usize,
+    /// Tensors per quantization type, most used first
+    pub quant_histogram: Vec<(QuantizationType, usize)>,
+    /// Bytes of tensor data, `None` if a tensor's type has an unknown size
+    pub data_size: Option<u64>,
+    /// Header, metadata, tensor infos and tensor data, `None` like `data_size`
+    pub total_size: Option<u64>,
+    /// Largest tensors by data size, largest first
+    pub largest_tensors: Vec<TensorSummary>,
+}
+
+/// Name, shape and size of one tensor in an `InspectReport`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorSummary {
+    pub name: String,
+    pub dimensions: Vec<u64>,
+    pub quantization_type: QuantizationType,
+    pub size_bytes: u64,
+}
+
+impl InspectReport {
+    pub(crate) fn new(gguf_file: &GgufFile) -> Self {
+        let data_size = gguf_file.total_size().ok();
+
+        // Tensors whose size is unknown can't be ranked and are left out
+        let mut largest_tensors: Vec<TensorSummary> = gguf_file
+            .tensors
+            .iter()
+            .filter_map(|tensor| {
+                Some(TensorSummary {
+                    name: tensor.name.clone(),
+                    dimensions: tensor.dimensions.clone(),
+                    quantization_type: tensor.quantization_type,
+                    size_bytes: tensor.size_bytes().ok()?,
+                })
+            })
+            .collect();
+        largest_tensors.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then_with(|| a.name.cmp(&b.name)));
+        largest_tensors.truncate(INSPECT_LARGEST_TENSORS);
+
+        Self {
+            header: gguf_file.header.clone(),
+            model_config: gguf_file.model_config().ok(),
+            tensor_count: gguf_file.tensors.len(),
+            quant_histogram: gguf_file.quantization_types_by_frequency(),
+            data_size,
+            total_size: data_size.and_then(|size| gguf_file.data_offset().checked_add(size)),
+            largest_tensors,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+/*!
+ * Minimal I/O Traits for the Core Parser
+ *
+ * The parser only needs to read exact byte counts and reposition the stream.
+ * With the `std` feature these traits are implemented for every
+ * `std::io::Read`/`std::io::Seek` type, without it `SliceReader` provides an
+ * in-memory implementation.
+ */
+
+use crate::error::Result;
+#[cfg(not(feature = "std"))]
+use crate::error::GgufError;
+
+/// Position to seek to, mirroring `std::io::SeekFrom`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeekFrom {
+    Start(u64),
+    End(i64),
+    Current(i64),
+}
+
+/// Source of bytes for the parser
+pub trait Read {
+    /// Fill the whole buffer or fail
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()>;
+}
+
+/// Repositionable source of bytes for the parser
+pub trait Seek {
+    /// Seek to a position, returning the new offset from the start
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64>;
+
+    /// Current offset from the start
+    fn stream_position(&mut self) -> Result<u64> {
+        self.seek(SeekFrom::Current(0))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Read + ?Sized> Read for T {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        std::io::Read::read_exact(self, buf)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: std::io::Seek + ?Sized> Seek for T {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let pos = match pos {
+            SeekFrom::Start(offset) => std::io::SeekFrom::Start(offset),
+            SeekFrom::End(offset) => std::io::SeekFrom::End(offset),
+            SeekFrom::Current(offset) => std::io::SeekFrom::Current(offset),
+        };
+        Ok(std::io::Seek::seek(self, pos)?)
+    }
+
+    fn stream_position(&mut self) -> Result<u64> {
+        // Avoid discarding buffered data on readers like `BufReader`
+        Ok(std::io::Seek::stream_position(self)?)
+    }
+}
+
+/// In-memory reader over a byte slice
+#[derive(Debug, Clone)]
+pub struct SliceReader<'a> {
+    bytes: &'a [u8],
+    position: u64,
+}
+
+impl<'a> SliceReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, position: 0 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Read for SliceReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let start = usize::try_from(self.position).map_or(self.bytes.len(), |start| start.min(self.bytes.len()));
+        let count = buf.len().min(self.bytes.len() - start);
+        buf[..count].copy_from_slice(&self.bytes[start..start + count]);
+        self.position += count as u64;
+        Ok(count)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::io::Seek for SliceReader<'_> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        let target = match pos {
+            std::io::SeekFrom::Start(offset) => Some(offset),
+            std::io::SeekFrom::End(offset) => (self.bytes.len() as u64).checked_add_signed(offset),
+            std::io::SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = target.ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, "seek before start of slice")
+        })?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Read for SliceReader<'_> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let start = usize::try_from(self.position).map_err(|_| GgufError::UnexpectedEof)?;
+        let end = start.checked_add(buf.len()).ok_or(GgufError::UnexpectedEof)?;
+        let source = self.bytes.get(start..end).ok_or(GgufError::UnexpectedEof)?;
+        buf.copy_from_slice(source);
+        self.position = end as u64;
+        Ok(())
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl Seek for SliceReader<'_> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => (self.bytes.len() as u64).checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = target.ok_or(GgufError::UnexpectedEof)?;
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,353 @@
+/*!
+ * Well-Known GGUF Metadata Keys
+ */
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use KeyAlias::{Arch, Fixed};
+
+// General keys
+pub const GENERAL_ARCHITECTURE: &str = "general.architecture";
+pub const GENERAL_QUANTIZATION_VERSION: &str = "general.quantization_version";
+pub const GENERAL_ALIGNMENT: &str = "general.alignment";
+pub const GENERAL_NAME: &str = "general.name";
+pub const GENERAL_AUTHOR: &str = "general.author";
+pub const GENERAL_VERSION: &str = "general.version";
+pub const GENERAL_ORGANIZATION: &str = "general.organization";
+pub const GENERAL_BASENAME: &str = "general.basename";
+pub const GENERAL_FINETUNE: &str = "general.finetune";
+pub const GENERAL_DESCRIPTION: &str = "general.description";
+pub const GENERAL_QUANTIZED_BY: &str = "general.quantized_by";
+pub const GENERAL_SIZE_LABEL: &str = "general.size_label";
+pub const GENERAL_LICENSE: &str = "general.license";
+pub const GENERAL_LICENSE_NAME: &str = "general.license.name";
+pub const GENERAL_LICENSE_LINK: &str = "general.license.link";
+pub const GENERAL_URL: &str = "general.url";
+pub const GENERAL_DOI: &str = "general.doi";
+pub const GENERAL_UUID: &str = "general.uuid";
+pub const GENERAL_REPO_URL: &str = "general.repo_url";
+pub const GENERAL_TAGS: &str = "general.tags";
+pub const GENERAL_LANGUAGES: &str = "general.languages";
+pub const GENERAL_FILE_TYPE: &str = "general.file_type";
+pub const GENERAL_TYPE: &str = "general.type";
+pub const GENERAL_SOURCE_URL: &str = "general.source.url";
+pub const GENERAL_SOURCE_DOI: &str = "general.source.doi";
+pub const GENERAL_SOURCE_UUID: &str = "general.source.uuid";
+pub const GENERAL_SOURCE_REPO_URL: &str = "general.source.repo_url";
+pub const GENERAL_BASE_MODEL_COUNT: &str = "general.base_model.count";
+pub const GENERAL_DATASET_COUNT: &str = "general.dataset.count";
+pub const GENERAL_VOCAB_SIZE: &str = "general.vocab_size";
+pub const GENERAL_CONTEXT_LENGTH: &str = "general.context_length";
+
+// Tokenizer keys
+pub const TOKENIZER_GGML_MODEL: &str = "tokenizer.ggml.model";
+pub const TOKENIZER_GGML_PRE: &str = "tokenizer.ggml.pre";
+pub const TOKENIZER_GGML_TOKENS: &str = "tokenizer.ggml.tokens";
+pub const TOKENIZER_GGML_SCORES: &str = "tokenizer.ggml.scores";
+pub const TOKENIZER_GGML_TOKEN_TYPE: &str = "tokenizer.ggml.token_type";
+pub const TOKENIZER_GGML_MERGES: &str = "tokenizer.ggml.merges";
+pub const TOKENIZER_GGML_ADDED_TOKENS: &str = "tokenizer.ggml.added_tokens";
+pub const TOKENIZER_GGML_BOS_TOKEN_ID: &str = "tokenizer.ggml.bos_token_id";
+pub const TOKENIZER_GGML_EOS_TOKEN_ID: &str = "tokenizer.ggml.eos_token_id";
+pub const TOKENIZER_GGML_UNKNOWN_TOKEN_ID: &str = "tokenizer.ggml.unknown_token_id";
+pub const TOKENIZER_GGML_SEPARATOR_TOKEN_ID: &str = "tokenizer.ggml.separator_token_id";
+pub const TOKENIZER_GGML_PADDING_TOKEN_ID: &str = "tokenizer.ggml.padding_token_id";
+pub const TOKENIZER_GGML_ADD_BOS_TOKEN: &str = "tokenizer.ggml.add_bos_token";
+pub const TOKENIZER_GGML_ADD_EOS_TOKEN: &str = "tokenizer.ggml.add_eos_token";
+pub const TOKENIZER_HUGGINGFACE_JSON: &str = "tokenizer.huggingface.json";
+pub const TOKENIZER_RWKV_WORLD: &str = "tokenizer.rwkv.world";
+pub const TOKENIZER_CHAT_TEMPLATE: &str = "tokenizer.chat_template";
+
+// Split keys
+pub const SPLIT_NO: &str = "split.no";
+pub const SPLIT_COUNT: &str = "split.count";
+pub const SPLIT_TENSORS_COUNT: &str = "split.tensors.count";
+
+// Importance matrix provenance, written by llama-quantize
+pub const QUANTIZE_IMATRIX_FILE: &str = "quantize.imatrix.file";
+pub const QUANTIZE_IMATRIX_DATASET: &str = "quantize.imatrix.dataset";
+pub const QUANTIZE_IMATRIX_ENTRIES_COUNT: &str = "quantize.imatrix.entries_count";
+pub const QUANTIZE_IMATRIX_CHUNKS_COUNT: &str = "quantize.imatrix.chunks_count";
+
+// Keys written by this crate into metadata-only stub files
+pub const AIOGGUF_STUB: &str = "aiogguf.stub";
+/// Prefix of the `aiogguf.stub.dropped.<key>` length markers
+pub const AIOGGUF_STUB_DROPPED_PREFIX: &str = "aiogguf.stub.dropped.";
+
+// Adapter keys, present in LoRA adapter files
+pub const ADAPTER_TYPE: &str = "adapter.type";
+pub const ADAPTER_LORA_ALPHA: &str = "adapter.lora.alpha";
+
+// Architecture-prefixed key suffixes, combine with `arch_key`
+pub const CONTEXT_LENGTH: &str = "context_length";
+pub const EMBEDDING_LENGTH: &str = "embedding_length";
+pub const BLOCK_COUNT: &str = "block_count";
+pub const FEED_FORWARD_LENGTH: &str = "feed_forward_length";
+pub const VOCAB_SIZE: &str = "vocab_size";
+pub const USE_PARALLEL_RESIDUAL: &str = "use_parallel_residual";
+pub const TENSOR_DATA_LAYOUT: &str = "tensor_data_layout";
+pub const EXPERT_COUNT: &str = "expert_count";
+pub const EXPERT_USED_COUNT: &str = "expert_used_count";
+pub const ATTENTION_HEAD_COUNT: &str = "attention.head_count";
+pub const ATTENTION_HEAD_COUNT_KV: &str = "attention.head_count_kv";
+pub const ATTENTION_MAX_ALIBI_BIAS: &str = "attention.max_alibi_bias";
+pub const ATTENTION_CLAMP_KQV: &str = "attention.clamp_kqv";
+pub const ATTENTION_LAYER_NORM_EPSILON: &str = "attention.layer_norm_epsilon";
+pub const ATTENTION_LAYER_NORM_RMS_EPSILON: &str = "attention.layer_norm_rms_epsilon";
+pub const ATTENTION_KEY_LENGTH: &str = "attention.key_length";
+pub const ATTENTION_VALUE_LENGTH: &str = "attention.value_length";
+pub const ATTENTION_SLIDING_WINDOW: &str = "attention.sliding_window";
+pub const ATTENTION_SLIDING_WINDOW_PATTERN: &str = "attention.sliding_window_pattern";
+pub const ROPE_DIMENSION_COUNT: &str = "rope.dimension_count";
+pub const ROPE_FREQ_BASE: &str = "rope.freq_base";
+/// Older name for `ROPE_FREQ_BASE`
+pub const ROPE_THETA: &str = "rope.theta";
+pub const ROPE_TYPE: &str = "rope.type";
+pub const ROPE_SCALING_TYPE: &str = "rope.scaling.type";
+pub const ROPE_SCALING_FACTOR: &str = "rope.scaling.factor";
+pub const ROPE_SCALING_ORIGINAL_CONTEXT_LENGTH: &str = "rope.scaling.original_context_length";
+pub const ROPE_SCALING_FINETUNED: &str = "rope.scaling.finetuned";
+pub const SSM_CONV_KERNEL: &str = "ssm.conv_kernel";
+pub const SSM_INNER_SIZE: &str = "ssm.inner_size";
+pub const SSM_STATE_SIZE: &str = "ssm.state_size";
+pub const SSM_TIME_STEP_RANK: &str = "ssm.time_step_rank";
+
+// Per-entry fields of the indexed `general.base_model.N.*` and `general.dataset.N.*` keys
+pub const ENTRY_NAME: &str = "name";
+pub const ENTRY_AUTHOR: &str = "author";
+pub const ENTRY_VERSION: &str = "version";
+pub const ENTRY_ORGANIZATION: &str = "organization";
+pub const ENTRY_URL: &str = "url";
+pub const ENTRY_DOI: &str = "doi";
+pub const ENTRY_UUID: &str = "uuid";
+pub const ENTRY_REPO_URL: &str = "repo_url";
+
+/// All fixed (non-prefixed, non-indexed) well-known keys
+pub const WELL_KNOWN_KEYS: &[&str] = &[
+    GENERAL_ARCHITECTURE,
+    GENERAL_QUANTIZATION_VERSION,
+    GENERAL_ALIGNMENT,
+    GENERAL_NAME,
+    GENERAL_AUTHOR,
+    GENERAL_VERSION,
+    GENERAL_ORGANIZATION,
+    GENERAL_BASENAME,
+    GENERAL_FINETUNE,
+    GENERAL_DESCRIPTION,
+    GENERAL_QUANTIZED_BY,
+    GENERAL_SIZE_LABEL,
+    GENERAL_LICENSE,
+    GENERAL_LICENSE_NAME,
+    GENERAL_LICENSE_LINK,
+    GENERAL_URL,
+    GENERAL_DOI,
+    GENERAL_UUID,
+    GENERAL_REPO_URL,
+    GENERAL_TAGS,
+    GENERAL_LANGUAGES,
+    GENERAL_FILE_TYPE,
+    GENERAL_TYPE,
+    GENERAL_SOURCE_URL,
+    GENERAL_SOURCE_DOI,
+    GENERAL_SOURCE_UUID,
+    GENERAL_SOURCE_REPO_URL,
+    GENERAL_BASE_MODEL_COUNT,
+    GENERAL_DATASET_COUNT,
+    GENERAL_VOCAB_SIZE,
+    GENERAL_CONTEXT_LENGTH,
+    TOKENIZER_GGML_MODEL,
+    TOKENIZER_GGML_PRE,
+    TOKENIZER_GGML_TOKENS,
+    TOKENIZER_GGML_SCORES,
+    TOKENIZER_GGML_TOKEN_TYPE,
+    TOKENIZER_GGML_MERGES,
+    TOKENIZER_GGML_ADDED_TOKENS,
+    TOKENIZER_GGML_BOS_TOKEN_ID,
+    TOKENIZER_GGML_EOS_TOKEN_ID,
+    TOKENIZER_GGML_UNKNOWN_TOKEN_ID,
+    TOKENIZER_GGML_SEPARATOR_TOKEN_ID,
+    TOKENIZER_GGML_PADDING_TOKEN_ID,
+    TOKENIZER_GGML_ADD_BOS_TOKEN,
+    TOKENIZER_GGML_ADD_EOS_TOKEN,
+    TOKENIZER_HUGGINGFACE_JSON,
+    TOKENIZER_RWKV_WORLD,
+    TOKENIZER_CHAT_TEMPLATE,
+    SPLIT_NO,
+    SPLIT_COUNT,
+    SPLIT_TENSORS_COUNT,
+    ADAPTER_TYPE,
+    ADAPTER_LORA_ALPHA,
+    AIOGGUF_STUB,
+];
+
+/// All well-known architecture-prefixed key suffixes
+pub const ARCH_KEY_SUFFIXES: &[&str] = &[
+    CONTEXT_LENGTH,
+    EMBEDDING_LENGTH,
+    BLOCK_COUNT,
+    FEED_FORWARD_LENGTH,
+    VOCAB_SIZE,
+    USE_PARALLEL_RESIDUAL,
+    TENSOR_DATA_LAYOUT,
+    EXPERT_COUNT,
+    EXPERT_USED_COUNT,
+    ATTENTION_HEAD_COUNT,
+    ATTENTION_HEAD_COUNT_KV,
+    ATTENTION_MAX_ALIBI_BIAS,
+    ATTENTION_CLAMP_KQV,
+    ATTENTION_LAYER_NORM_EPSILON,
+    ATTENTION_LAYER_NORM_RMS_EPSILON,
+    ATTENTION_KEY_LENGTH,
+    ATTENTION_VALUE_LENGTH,
+    ATTENTION_SLIDING_WINDOW,
+    ATTENTION_SLIDING_WINDOW_PATTERN,
+    ROPE_DIMENSION_COUNT,
+    ROPE_FREQ_BASE,
+    ROPE_THETA,
+    ROPE_TYPE,
+    ROPE_SCALING_TYPE,
+    ROPE_SCALING_FACTOR,
+    ROPE_SCALING_ORIGINAL_CONTEXT_LENGTH,
+    ROPE_SCALING_FINETUNED,
+    SSM_CONV_KERNEL,
+    SSM_INNER_SIZE,
+    SSM_STATE_SIZE,
+    SSM_TIME_STEP_RANK,
+];
+
+/// All well-known fields of indexed base model and dataset entries
+pub const ENTRY_FIELDS: &[&str] = &[
+    ENTRY_NAME,
+    ENTRY_AUTHOR,
+    ENTRY_VERSION,
+    ENTRY_ORGANIZATION,
+    ENTRY_URL,
+    ENTRY_DOI,
+    ENTRY_UUID,
+    ENTRY_REPO_URL,
+];
+
+/// A key a `ModelConfig` field may be read from, see `FIELD_ALIASES`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAlias {
+    /// A key read as-is, e.g. `GENERAL_NAME`
+    Fixed(&'static str),
+    /// An architecture-prefixed key suffix, e.g. `BLOCK_COUNT` for
+    /// `llama.block_count`
+    Arch(&'static str),
+}
+
+impl KeyAlias {
+    /// Build the full key for an architecture
+    pub fn key(&self, arch: &str) -> String {
+        match self {
+            KeyAlias::Fixed(key) => key.to_string(),
+            KeyAlias::Arch(suffix) => arch_key(arch, suffix),
+        }
+    }
+
+    /// Check whether `key` is this alias for `arch`, without building the key
+    pub fn matches(&self, arch: &str, key: &str) -> bool {
+        match self {
+            KeyAlias::Fixed(fixed) => *fixed == key,
+            KeyAlias::Arch(suffix) => key
+                .strip_prefix(arch)
+                .and_then(|rest| rest.strip_prefix('.'))
+                .is_some_and(|rest| rest == *suffix),
+        }
+    }
+}
+
+/// Keys each `ModelConfig` field is read from, most preferred first
+///
+/// Converter versions don't always agree on key names, so a field may list
+/// several. `Arch` keys are prefixed with `general.architecture`.
+pub const FIELD_ALIASES: &[(&str, &[KeyAlias])] = &[
+    ("architecture", &[Fixed(GENERAL_ARCHITECTURE)]),
+    ("vocab_size", &[Fixed(GENERAL_VOCAB_SIZE), Arch(VOCAB_SIZE)]),
+    ("context_length", &[Fixed(GENERAL_CONTEXT_LENGTH), Arch(CONTEXT_LENGTH)]),
+    ("block_count", &[Arch(BLOCK_COUNT)]),
+    ("embedding_length", &[Arch(EMBEDDING_LENGTH)]),
+    ("feed_forward_length", &[Arch(FEED_FORWARD_LENGTH)]),
+    ("attention_head_count", &[Arch(ATTENTION_HEAD_COUNT)]),
+    ("attention_head_count_kv", &[Arch(ATTENTION_HEAD_COUNT_KV)]),
+    ("attention_layer_norm_rms_epsilon", &[Arch(ATTENTION_LAYER_NORM_RMS_EPSILON), Arch(ATTENTION_LAYER_NORM_EPSILON)]),
+    ("attention_key_length", &[Arch(ATTENTION_KEY_LENGTH)]),
+    ("attention_value_length", &[Arch(ATTENTION_VALUE_LENGTH)]),
+    ("attention_sliding_window", &[Arch(ATTENTION_SLIDING_WINDOW)]),
+    ("attention_sliding_window_pattern", &[Arch(ATTENTION_SLIDING_WINDOW_PATTERN)]),
+    ("rope_dimension_count", &[Arch(ROPE_DIMENSION_COUNT)]),
+    ("rope_freq_base", &[Arch(ROPE_FREQ_BASE), Arch(ROPE_THETA)]),
+    ("rope_scaling_type", &[Arch(ROPE_SCALING_TYPE)]),
+    ("rope_scaling_factor", &[Arch(ROPE_SCALING_FACTOR)]),
+    ("rope_scaling_original_context_length", &[Arch(ROPE_SCALING_ORIGINAL_CONTEXT_LENGTH)]),
+    ("rope_scaling_finetuned", &[Arch(ROPE_SCALING_FINETUNED)]),
+    ("rope_type", &[Arch(ROPE_TYPE)]),
+    ("expert_count", &[Arch(EXPERT_COUNT)]),
+    ("expert_used_count", &[Arch(EXPERT_USED_COUNT)]),
+    ("tokenizer_ggml_model", &[Fixed(TOKENIZER_GGML_MODEL)]),
+    ("tokenizer_ggml_scores", &[Fixed(TOKENIZER_GGML_SCORES)]),
+    ("tokenizer_ggml_token_type", &[Fixed(TOKENIZER_GGML_TOKEN_TYPE)]),
+    ("tokenizer_chat_template", &[Fixed(TOKENIZER_CHAT_TEMPLATE)]),
+    ("general_name", &[Fixed(GENERAL_NAME)]),
+    ("general_description", &[Fixed(GENERAL_DESCRIPTION)]),
+    ("general_license", &[Fixed(GENERAL_LICENSE)]),
+    ("general_author", &[Fixed(GENERAL_AUTHOR)]),
+    ("general_url", &[Fixed(GENERAL_URL)]),
+    ("general_source_url", &[Fixed(GENERAL_SOURCE_URL)]),
+    ("general_organization", &[Fixed(GENERAL_ORGANIZATION)]),
+    ("general_size_label", &[Fixed(GENERAL_SIZE_LABEL)]),
+    ("base_model_count", &[Fixed(GENERAL_BASE_MODEL_COUNT)]),
+    ("dataset_count", &[Fixed(GENERAL_DATASET_COUNT)]),
+];
+
+/// Full keys a `ModelConfig` field may be read from for an architecture,
+/// empty for fields not in `FIELD_ALIASES`
+pub fn field_aliases(arch: &str, field: &str) -> Vec<String> {
+    FIELD_ALIASES
+        .iter()
+        .find(|(name, _)| *name == field)
+        .map(|(_, aliases)| aliases.iter().map(|alias| alias.key(arch)).collect())
+        .unwrap_or_default()
+}
+
+/// Build an architecture-prefixed key, e.g. `arch_key("llama", BLOCK_COUNT)`
+pub fn arch_key(arch: &str, suffix: &str) -> String {
+    format!("{arch}.{suffix}")
+}
+
+/// Build an indexed base model key, e.g. `general.base_model.0.name`
+pub fn base_model_key(index: u32, field: &str) -> String {
+    format!("general.base_model.{index}.{field}")
+}
+
+/// Build an indexed dataset key, e.g. `general.dataset.0.name`
+pub fn dataset_key(index: u32, field: &str) -> String {
+    format!("general.dataset.{index}.{field}")
+}
+
+/// Check whether a key is covered by the well-known key tables
+pub fn is_well_known(key: &str) -> bool {
+    if WELL_KNOWN_KEYS.contains(&key) {
+        return true;
+    }
+
+    // Indexed base model and dataset entries
+    for prefix in ["general.base_model.", "general.dataset."] {
+        if let Some(rest) = key.strip_prefix(prefix)
+            && let Some((index, field)) = rest.split_once('.')
+            && index.parse::<u32>().is_ok()
+        {
+            return ENTRY_FIELDS.contains(&field);
+        }
+    }
+
+    // Architecture-prefixed keys, the architecture itself is open-ended
+    match key.split_once('.') {
+        Some((arch, suffix)) if !matches!(arch, "general" | "tokenizer" | "split") => {
+            ARCH_KEY_SUFFIXES.contains(&suffix)
+        }
+        _ => false,
+    }
+}
@@ -0,0 +1,76 @@
+/*!
+ * GGUF File Kinds
+ *
+ * GGUF also carries LoRA adapters, vocab-only tokenizer files, vision
+ * projectors and importance matrices. `general.type` names the kind in
+ * recent files, older ones are recognized from their architecture and keys.
+ */
+
+use crate::keys;
+use crate::metadata::{detect_key_prefix, GgufMetadata};
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// What a GGUF file contains
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GgufKind {
+    /// Full model with hyperparameters and weights
+    Model,
+    /// LoRA or control vector adapter, `general.type = "adapter"`
+    Adapter,
+    /// Tokenizer without weights, `general.type = "vocab"`
+    Vocab,
+    /// Multimodal projector, `general.type = "mmproj"`, the `clip`
+    /// architecture or `clip.*` keys
+    VisionProjector,
+    /// Unrecognized `general.type`, empty when neither it nor
+    /// `general.architecture` is present
+    Unknown(String),
+}
+
+impl GgufKind {
+    /// Determine the kind from `general.type`, falling back to the
+    /// architecture and which keys are present for files without it
+    ///
+    /// A file without `general.type` that has `adapter.type` or
+    /// `adapter.lora.alpha` is an adapter, and one with `clip.*` keys a
+    /// vision projector. One whose only hyperparameters are
+    /// tokenizer keys, with no `<arch>.block_count`, is vocab-only.
+    pub fn from_metadata(metadata: &GgufMetadata) -> Self {
+        if let Some(file_type) = metadata.get_string_opt(keys::GENERAL_TYPE) {
+            return match file_type {
+                "model" => GgufKind::Model,
+                "adapter" => GgufKind::Adapter,
+                "vocab" => GgufKind::Vocab,
+                "mmproj" => GgufKind::VisionProjector,
+                other => GgufKind::Unknown(other.to_string()),
+            };
+        }
+        if metadata.contains(keys::ADAPTER_TYPE) || metadata.contains(keys::ADAPTER_LORA_ALPHA) {
+            return GgufKind::Adapter;
+        }
+        match metadata.get_string_opt(keys::GENERAL_ARCHITECTURE) {
+            Some("clip") => GgufKind::VisionProjector,
+            Some(_) if metadata.keys().any(|key| key.starts_with("clip.")) => GgufKind::VisionProjector,
+            Some(_) if detect_key_prefix(metadata).is_none() && metadata.contains(keys::TOKENIZER_GGML_TOKENS) => {
+                GgufKind::Vocab
+            }
+            Some(_) => GgufKind::Model,
+            None => GgufKind::Unknown(String::new()),
+        }
+    }
+}
+
+impl fmt::Display for GgufKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GgufKind::Model => write!(f, "model"),
+            GgufKind::Adapter => write!(f, "adapter"),
+            GgufKind::Vocab => write!(f, "vocab-only file"),
+            GgufKind::VisionProjector => write!(f, "vision projector"),
+            GgufKind::Unknown(file_type) if file_type.is_empty() => write!(f, "file of unknown type"),
+            GgufKind::Unknown(file_type) => write!(f, "file of unknown type '{file_type}'"),
+        }
+    }
+}
@@ -0,0 +1,122 @@
+/*!
+ * Tensor Data Layout
+ *
+ * Describes where each tensor's bytes live in the file, for loaders that
+ * map or upload tensor data without parsing GGUF themselves.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::header::GgufHeader;
+use crate::io::{Read, Seek};
+use crate::keys;
+use crate::metadata::GgufMetadata;
+use crate::tensor::QuantizationType;
+use crate::tensor_reader::TensorInfoReader;
+use crate::{alignment_of, GgufFile};
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Location and size of one tensor's data
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TensorExtent {
+    pub name: String,
+    /// Absolute position in the file, including `gguf_offset`
+    pub offset: u64,
+    /// Exact size of the tensor data in bytes
+    pub len: u64,
+    /// Size rounded up to the file's alignment
+    pub padded_len: u64,
+    pub quantization_type: QuantizationType,
+    /// Shape in ggml order, as stored
+    pub dimensions: Vec<u64>,
+}
+
+impl TensorExtent {
+    /// Absolute position just past the tensor data
+    pub fn end(&self) -> u64 {
+        self.offset + self.len
+    }
+}
+
+/// Why bytes between tensors hold no tensor data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GapKind {
+    /// Alignment padding after a tensor
+    Padding,
+    /// Bytes beyond any tensor's padding, which the file doesn't explain
+    Hole,
+}
+
+/// Range of the data section not covered by any tensor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DataGap {
+    /// Absolute position in the file
+    pub offset: u64,
+    pub len: u64,
+    pub kind: GapKind,
+}
+
+/// Get how many bytes from the reader's position cover the header, metadata
+/// and tensor infos, up to the aligned start of the tensor data
+///
+/// Metadata values are skipped rather than parsed, so this is cheaper than a
+/// full parse when only the length is needed, e.g. to size a range request.
+/// Equals `GgufFile::header_region_len` for the same file.
+pub fn estimate_metadata_prefix<R: Read + Seek>(reader: &mut R) -> Result<u64> {
+    let start = reader.stream_position()?;
+    let header = GgufHeader::read(reader)?;
+    let metadata = GgufMetadata::read_keys(reader, header.metadata_kv_count, &[keys::GENERAL_ALIGNMENT])?;
+    for tensor in TensorInfoReader::new(reader, header.tensor_count) {
+        tensor?;
+    }
+    let alignment = alignment_of(&metadata);
+    Ok((reader.stream_position()? - start).div_ceil(alignment) * alignment)
+}
+
+pub(crate) fn data_layout(gguf_file: &GgufFile) -> Result<Vec<TensorExtent>> {
+    let alignment = gguf_file.alignment();
+    let data_start = gguf_file.gguf_offset() + gguf_file.data_offset();
+    let mut extents = gguf_file
+        .tensors
+        .iter()
+        .map(|tensor| {
+            let len = tensor.size_bytes()?;
+            // Checked so `TensorExtent` arithmetic on the result can't overflow
+            let overflow = || GgufError::SizeOverflow(tensor.name.clone());
+            let padded_len = len.div_ceil(alignment).checked_mul(alignment).ok_or_else(overflow)?;
+            let offset = data_start.checked_add(tensor.offset).ok_or_else(overflow)?;
+            offset.checked_add(padded_len).ok_or_else(overflow)?;
+            Ok(TensorExtent {
+                name: tensor.name.clone(),
+                offset,
+                len,
+                padded_len,
+                quantization_type: tensor.quantization_type,
+                dimensions: tensor.dimensions.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    extents.sort_by_key(|extent| extent.offset);
+    Ok(extents)
+}
+
+pub(crate) fn data_gaps(gguf_file: &GgufFile) -> Result<Vec<DataGap>> {
+    let mut gaps = Vec::new();
+    let mut position = gguf_file.gguf_offset() + gguf_file.data_offset();
+    let mut padded_end = position;
+    for extent in data_layout(gguf_file)? {
+        if extent.offset > position {
+            let padding_end = padded_end.clamp(position, extent.offset);
+            if padding_end > position {
+                gaps.push(DataGap { offset: position, len: padding_end - position, kind: GapKind::Padding });
+            }
+            if extent.offset > padding_end {
+                gaps.push(DataGap { offset: padding_end, len: extent.offset - padding_end, kind: GapKind::Hole });
+            }
+        }
+        position = position.max(extent.end());
+        padded_end = padded_end.max(extent.offset + extent.padded_len);
+    }
+    Ok(gaps)
+}
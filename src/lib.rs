@@ -3,69 +3,602 @@
  * 
  * Pure Rust implementation for parsing GGUF (Generic Graph Universal Format) files.
  * Focused on extracting model metadata and configuration for AI model inference.
+ *
+ * The core parser only needs `alloc`; file access, format sniffing and lenient
+ * parsing require the default `std` feature.
  */
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod bundle;
+#[cfg(feature = "cache")]
+mod cache;
+mod compare;
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+mod compressed;
+mod defaults;
 mod error;
+mod file_type;
+mod format;
+#[cfg(feature = "arbitrary")]
+mod generate;
 mod header;
+#[cfg(feature = "http")]
+mod http;
+mod imatrix;
+mod infer;
+mod inspect;
+pub mod io;
+pub mod keys;
+mod kind;
+mod layout;
+mod limits;
 mod metadata;
+mod metadata_reader;
+mod metadata_stats;
+mod options;
+#[cfg(feature = "std")]
+mod output;
+#[cfg(feature = "std")]
+mod partial;
+#[cfg(any(feature = "gzip", feature = "zstd", feature = "http"))]
+mod prefix;
+mod quant_info;
+mod read;
+#[cfg(feature = "std")]
+mod realign;
+#[cfg(feature = "report")]
+mod report;
+mod rename;
+mod split;
+mod stats;
+#[cfg(feature = "std")]
+mod stub;
 mod tensor;
+mod tensor_names;
+mod tensor_reader;
 mod types;
+mod validate;
+mod vocab;
+mod warning;
+#[cfg(feature = "std")]
+mod writer;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod tests;
 
-pub use error::{GgufError, Result};
-pub use header::GgufHeader;
-pub use metadata::{GgufMetadata, ModelConfig};
-pub use tensor::{TensorInfo, QuantizationType};
-pub use types::{GgufValue, GgufValueType};
+#[cfg(feature = "std")]
+pub use bundle::{BundleWarning, ModelBundle};
+#[cfg(feature = "cache")]
+pub use cache::{CacheOptions, GgufCache, CACHE_EXTENSION};
+pub use compare::{QuantComparison, QuantVariant, TensorTypeDiff};
+#[cfg(any(feature = "gzip", feature = "zstd"))]
+pub use compressed::Compression;
+pub use error::{ErrorCategory, GgufError, Result};
+pub use file_type::{FileType, FileTypeConsistency};
+pub use format::FormatKind;
+#[cfg(feature = "std")]
+pub use format::{find_gguf_offset, is_gguf, sniff};
+pub use header::{GgufHeader, SUPPORTED_VERSIONS};
+pub use imatrix::ImatrixInfo;
+pub use infer::DEFAULT_CONTEXT_LENGTH;
+pub use inspect::{InspectReport, TensorSummary, INSPECT_LARGEST_TENSORS};
+pub use kind::GgufKind;
+pub use layout::{estimate_metadata_prefix, DataGap, GapKind, TensorExtent};
+pub use limits::ParseLimits;
+pub use metadata::{
+    detect_key_prefix, Activation, BaseModel, Dataset, EffectiveContext, GgufMetadata, ModelConfig, RopeType,
+    DEFAULT_FALLBACK_PREFIXES,
+};
+pub use metadata_reader::GgufMetadataReader;
+pub use metadata_stats::{EntrySummary, MetadataStats, NamespaceCounts, METADATA_STATS_LARGEST};
+pub use options::{ParseOptions, DEFAULT_MAX_TENSOR_DIMS};
+#[cfg(feature = "std")]
+pub use partial::PartialGgufFile;
+pub use quant_info::QuantInfo;
+pub use rename::{RenameRule, TensorRenamer, LAYER_PLACEHOLDER};
+pub use split::{SplitInfo, SplitWarning};
+pub use stats::ParseStats;
+#[cfg(feature = "std")]
+pub use stub::{StubOptions, DEFAULT_STUB_DROP_KEYS};
+pub use tensor::{DimOrder, QualityTier, QuantFamily, TensorInfo, QuantizationType};
+pub use tensor_names::LayerReport;
+pub use tensor_reader::TensorInfoReader;
+pub use types::{GgufValue, GgufValueType, CANONICAL_ARRAY_ELEMENTS, MAX_ARRAY_BYTES, MAX_ARRAY_DEPTH};
+pub use validate::{ConfigWarning, ShapeMismatch};
+pub use vocab::{ChatTemplates, TokenArena, TokenType, Vocab};
+pub use warning::{GgufWarning, ParseWarning, WarningKind};
 
+use alloc::collections::{BTreeMap, BTreeSet};
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use io::{Read, Seek};
+use limits::LimitedReader;
+use stats::Stopwatch;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
 use std::fs::File;
-use std::io::{BufReader, Read, Seek};
+#[cfg(feature = "std")]
+use std::io::BufReader;
+#[cfg(feature = "std")]
 use std::path::Path;
 
+/// Default alignment of the tensor data section when `general.alignment` is absent
+pub const DEFAULT_ALIGNMENT: u64 = 32;
+
+/// `general.quantization_version` written by current llama.cpp
+pub const EXPECTED_QUANTIZATION_VERSION: u32 = 2;
+
 /// Main GGUF file parser
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct GgufFile {
     pub header: GgufHeader,
     pub metadata: GgufMetadata,
     pub tensors: Vec<TensorInfo>,
+    header_bytes: u64,
+    metadata_bytes: u64,
+    tensor_info_bytes: u64,
+    data_offset: u64,
+    #[serde(default)]
+    gguf_offset: u64,
+    #[serde(default)]
+    warnings: Vec<GgufWarning>,
 }
 
+// Parsed files are shared across threads, keep that a compile error to break
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<GgufFile>();
+};
+
 impl GgufFile {
     /// Parse a GGUF file from a file path
+    #[cfg(feature = "std")]
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::from_file_with_options(path, &ParseOptions::default())
+    }
+
+    /// Parse the header, metadata and tensor infos of a remote GGUF file
+    ///
+    /// Only a prefix of the file is downloaded, using HTTP range requests
+    /// that start at 2 MiB and double until the metadata fits. Tensor data
+    /// is never fetched. Servers that ignore ranges are read only as far as
+    /// needed.
+    #[cfg(feature = "http")]
+    pub fn from_url(url: &str) -> Result<Self> {
+        http::from_url(url, prefix::INITIAL_PREFIX_BYTES)
+    }
+
+    /// Parse a GGUF file from a file path using custom parse options
+    #[cfg(feature = "std")]
+    pub fn from_file_with_options<P: AsRef<Path>>(path: P, options: &ParseOptions) -> Result<Self> {
         let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
         let mut reader = BufReader::new(file);
-        Self::from_reader(&mut reader)
+        if let Some(scan_limit) = options.scan_for_magic {
+            // Leaves the reader at the magic, or at the start to report the bad magic
+            if find_gguf_offset(&mut reader, scan_limit)?.is_none() {
+                std::io::Seek::rewind(&mut reader)?;
+            }
+        }
+        let gguf_file = Self::from_reader_with_options(&mut reader, options)?;
+        if options.verify_complete {
+            gguf_file.verify_complete(file_len)?;
+        }
+        if options.reject_trailing_data {
+            let (_, max_end) = gguf_file.expected_file_len()?;
+            if file_len > max_end {
+                return Err(GgufError::TrailingData {
+                    expected: max_end,
+                    actual: file_len,
+                    extra: file_len - max_end,
+                });
+            }
+        }
+        Ok(gguf_file)
     }
 
     /// Parse a GGUF file from a reader
     pub fn from_reader<R: Read + Seek>(reader: &mut R) -> Result<Self> {
-        // Parse header
+        Self::from_reader_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Parse a GGUF file from a reader using custom parse options
+    pub fn from_reader_with_options<R: Read + Seek>(reader: &mut R, options: &ParseOptions) -> Result<Self> {
+        Self::parse(reader, options, &mut ParseStats::default())
+    }
+
+    /// Parse a GGUF file from a reader, also returning how long the metadata
+    /// and tensor info sections took and how many bytes were read
+    ///
+    /// Durations are zero without the `std` feature.
+    pub fn from_reader_with_stats<R: Read + Seek>(reader: &mut R) -> Result<(Self, ParseStats)> {
+        let mut stats = ParseStats::default();
+        let gguf_file = Self::parse(reader, &ParseOptions::default(), &mut stats)?;
+        Ok((gguf_file, stats))
+    }
+
+    fn parse<R: Read + Seek>(reader: &mut R, options: &ParseOptions, stats: &mut ParseStats) -> Result<Self> {
+        let header_start = reader.stream_position()?;
+        let limits = &options.limits;
+        let reader = &mut LimitedReader::new(reader, limits.max_total_bytes)?;
+
+        // Parse header, rejecting counts over the limits before anything is allocated for them
         let header = GgufHeader::read(reader)?;
+        limits::check("max_metadata_kv", header.metadata_kv_count, limits.max_metadata_kv)?;
+        limits::check("max_tensors", header.tensor_count, limits.max_tensors)?;
+        let metadata_start = reader.stream_position()?;
         
         // Parse metadata
-        let metadata = GgufMetadata::read(reader, header.metadata_kv_count)?;
+        let stopwatch = Stopwatch::start();
+        let (metadata, mut warnings) = GgufMetadata::read_with_warnings(reader, header.metadata_kv_count, options)?;
+        stats.metadata_duration = stopwatch.elapsed();
+        let tensor_info_start = reader.stream_position()?;
         
         // Parse tensor information
-        let tensors = TensorInfo::read_all(reader, header.tensor_count)?;
+        let stopwatch = Stopwatch::start();
+        let tensors = TensorInfo::read_all_with_options(reader, header.tensor_count, options)?;
+        stats.tensor_duration = stopwatch.elapsed();
+        let tensor_info_end = reader.stream_position()?;
+        stats.bytes_read = tensor_info_end - header_start;
+        stats.kv_count = header.metadata_kv_count;
+        stats.tensor_count = header.tensor_count;
+
+        // Tensor data starts at the next multiple of the alignment
+        let alignment = alignment_of(&metadata);
+        let data_offset = (tensor_info_end - header_start).div_ceil(alignment) * alignment;
+
+        for tensor in &tensors {
+            if let QuantizationType::Unknown(id) = tensor.quantization_type {
+                let warning = ParseWarning::UnknownQuantType { tensor: tensor.name.clone(), id };
+                warnings.push(GgufWarning::new(warning, None));
+            }
+        }
+        warnings.extend(
+            metadata::hyperparameter_warnings(&metadata)
+                .into_iter()
+                .map(|warning| GgufWarning::new(warning, None)),
+        );
+        if let Some(version) = metadata.get_u32_opt(keys::GENERAL_QUANTIZATION_VERSION)
+            && version != EXPECTED_QUANTIZATION_VERSION
+        {
+            warnings.push(GgufWarning::new(ParseWarning::UnexpectedQuantizationVersion(version), None));
+        }
+        if let Some(promoted) = warnings.iter().find(|w| options.warnings_as_errors.contains(&w.kind())) {
+            return Err(GgufError::PromotedWarning(promoted.clone()));
+        }
         
         Ok(Self {
             header,
             metadata,
             tensors,
+            header_bytes: metadata_start - header_start,
+            metadata_bytes: tensor_info_start - metadata_start,
+            tensor_info_bytes: tensor_info_end - tensor_info_start,
+            data_offset,
+            gguf_offset: header_start,
+            warnings,
         })
     }
 
+    /// Parse a GGUF file from a gzip or zstd compressed stream
+    ///
+    /// Only the header, metadata and tensor infos are decompressed, into
+    /// memory. The returned offsets refer to the decompressed file, so tensor
+    /// data can only be read from a fully decompressed copy.
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    pub fn from_compressed_reader<R: std::io::Read>(reader: &mut R, compression: Compression) -> Result<Self> {
+        compressed::from_compressed_reader(reader, compression, &ParseOptions::default())
+    }
+
+    /// Parse a GGUF file leniently, returning whatever could be recovered
+    /// along with every problem encountered
+    #[cfg(feature = "std")]
+    pub fn from_reader_lenient<R: std::io::Read + std::io::Seek>(reader: &mut R) -> (PartialGgufFile, Vec<GgufError>) {
+        PartialGgufFile::read(reader)
+    }
+
+    /// Non-fatal oddities noticed while parsing, in the order they were found
+    pub fn warnings(&self) -> &[GgufWarning] {
+        &self.warnings
+    }
+
+    /// Number of bytes occupied by the header
+    pub fn header_bytes(&self) -> u64 {
+        self.header_bytes
+    }
+
+    /// Number of bytes occupied by the metadata key-value section
+    pub fn metadata_bytes(&self) -> u64 {
+        self.metadata_bytes
+    }
+
+    /// Number of bytes occupied by the tensor info section
+    pub fn tensor_info_bytes(&self) -> u64 {
+        self.tensor_info_bytes
+    }
+
+    /// Offset of the tensor data section from the start of the GGUF content,
+    /// which is the start of the file unless `gguf_offset` says otherwise
+    pub fn data_offset(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// Position in the file or reader where the GGUF content starts, non-zero
+    /// for GGUFs wrapped in a container, see `ParseOptions::scan_for_magic`
+    pub fn gguf_offset(&self) -> u64 {
+        self.gguf_offset
+    }
+
+    /// Alignment of the tensor data, from `general.alignment` or `DEFAULT_ALIGNMENT`
+    pub fn alignment(&self) -> u64 {
+        alignment_of(&self.metadata)
+    }
+
+    /// Check that a file of `file_len` bytes holds exactly the tensor data
+    /// its tensor infos describe
+    ///
+    /// The final tensor may or may not be followed by padding up to the
+    /// alignment, so any length between the unpadded and padded end is
+    /// accepted. Shorter files fail with `TruncatedFile`, longer ones with
+    /// `TrailingData`. Stubs (see `is_stub`) are expected to end after the
+    /// tensor infos. Bytes before `gguf_offset` count towards `file_len`.
+    pub fn verify_complete(&self, file_len: u64) -> Result<()> {
+        let (min_end, max_end) = self.expected_file_len()?;
+
+        if file_len < min_end {
+            return Err(GgufError::TruncatedFile {
+                expected: min_end,
+                actual: file_len,
+                missing: min_end - file_len,
+            });
+        }
+        if file_len > max_end {
+            return Err(GgufError::TrailingData {
+                expected: max_end,
+                actual: file_len,
+                extra: file_len - max_end,
+            });
+        }
+        Ok(())
+    }
+
+    /// Smallest and largest file length consistent with the tensor infos
+    fn expected_file_len(&self) -> Result<(u64, u64)> {
+        let alignment = self.alignment();
+        let overflow = || GgufError::SizeOverflow("tensor data".to_string());
+        let mut data_end = None;
+        if !self.is_stub() {
+            for tensor in &self.tensors {
+                let end = tensor.offset.checked_add(tensor.size_bytes()?).ok_or_else(overflow)?;
+                data_end = data_end.max(Some(end));
+            }
+        }
+        let (min_end, max_end) = match data_end {
+            Some(data_len) => (
+                self.data_offset.checked_add(data_len),
+                data_len
+                    .div_ceil(alignment)
+                    .checked_mul(alignment)
+                    .and_then(|padded| self.data_offset.checked_add(padded)),
+            ),
+            None => (Some(self.header_bytes + self.metadata_bytes + self.tensor_info_bytes), Some(self.data_offset)),
+        };
+        let absolute = |end: Option<u64>| end.and_then(|end| self.gguf_offset.checked_add(end)).ok_or_else(overflow);
+        Ok((absolute(min_end)?, absolute(max_end)?))
+    }
+
+    /// Number of bytes before the tensor data, including alignment padding
+    pub fn metadata_section_len(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// Bytes from the start of the GGUF data covering everything this type
+    /// parses, the prefix a remote fetch needs, see `estimate_metadata_prefix`
+    ///
+    /// Same value as `data_offset` and `metadata_section_len`, named for
+    /// callers sizing a prefix fetch.
+    pub fn header_region_len(&self) -> u64 {
+        self.data_offset
+    }
+
+    /// Get `general.architecture`
+    ///
+    /// Reads the key directly, so unlike `model_config` it works on files
+    /// missing the attention, feed-forward or other model keys. Fails only
+    /// if the key is missing or not a string.
+    pub fn architecture(&self) -> Result<&str> {
+        self.metadata.get_string(keys::GENERAL_ARCHITECTURE)
+    }
+
+    /// Check whether this is a metadata-only stub written by
+    /// `write_metadata_stub`, marked by `aiogguf.stub = true`
+    pub fn is_stub(&self) -> bool {
+        matches!(self.metadata.get(keys::AIOGGUF_STUB), Some(GgufValue::Bool(true)))
+    }
+
+    /// Write a stub GGUF with this file's header, metadata and tensor infos
+    /// but no tensor data
+    ///
+    /// Tensor count and offsets are kept as-is and the file ends after the
+    /// tensor info section. Keys in `options.drop_keys` are left out, and
+    /// `aiogguf.stub = true` is added so readers can recognize the stub.
+    #[cfg(feature = "std")]
+    pub fn write_metadata_stub<P: AsRef<Path>>(&self, path: P, options: &StubOptions) -> Result<()> {
+        stub::write_stub(self, path.as_ref(), options)
+    }
+
+    /// Copy a GGUF file with its tensor data realigned to `alignment`
+    ///
+    /// `general.alignment` is set to the new value and every tensor is moved
+    /// to the next aligned offset, keeping the order of the tensor data.
+    /// Offsets that broke the input's own alignment are normalized too. The
+    /// output is checked with `verify_complete` before returning, and
+    /// alignments that aren't a power of two fail with `InvalidAlignment`.
+    /// An output that is the input file fails with `OutputIsInput` before
+    /// anything is written.
+    #[cfg(feature = "std")]
+    pub fn rewrite_with_alignment<P: AsRef<Path>, Q: AsRef<Path>>(input: P, output: Q, alignment: u64) -> Result<()> {
+        realign::rewrite_with_alignment(input.as_ref(), output.as_ref(), alignment)
+    }
+
+    /// Get `general.name`, if present
+    pub fn name(&self) -> Option<&str> {
+        self.metadata.get_string_opt(keys::GENERAL_NAME)
+    }
+
+    /// Get the context length from `general.context_length` or `{arch}.context_length`
+    ///
+    /// Resolves the key prefix like `model_config`, so keys a converter
+    /// wrote under another architecture's prefix are still found.
+    pub fn context_length(&self) -> Option<u64> {
+        self.field_lookup().u64("context_length")
+    }
+
+    /// Get the vocabulary size from `general.vocab_size`, `{arch}.vocab_size`,
+    /// the length of `tokenizer.ggml.tokens` or the `token_embd.weight` rows
+    ///
+    /// Resolves the key prefix and falls back like `model_config`, but works
+    /// on files missing other model keys.
+    pub fn vocab_size(&self) -> Option<u64> {
+        metadata::resolve_vocab_size(&mut self.field_lookup(), &self.metadata, &self.tensors)
+    }
+
+    /// Look up `ModelConfig` fields under the resolved key prefix
+    fn field_lookup(&self) -> metadata::FieldLookup<'_> {
+        let key_prefix = self
+            .architecture()
+            .ok()
+            .map(|arch| metadata::resolve_key_prefix(&self.metadata, arch, DEFAULT_FALLBACK_PREFIXES))
+            .unwrap_or_default();
+        metadata::FieldLookup::new(&self.metadata, key_prefix)
+    }
+
+    /// Get the whole-file quantization label from `general.file_type`
+    pub fn quant_label(&self) -> Option<FileType> {
+        self.metadata.get_u32_opt(keys::GENERAL_FILE_TYPE).map(FileType::from)
+    }
+
+    /// Get the quantization format version from `general.quantization_version`
+    pub fn quantization_version(&self) -> Option<u32> {
+        self.metadata.get_u32_opt(keys::GENERAL_QUANTIZATION_VERSION)
+    }
+
+    /// Compare the declared `general.file_type` with the tensor types actually
+    /// used, `None` when the file declares no file type
+    pub fn file_type_consistency(&self) -> Option<FileTypeConsistency> {
+        let declared = self.quant_label()?;
+        let expected = declared.base_quantization_type();
+        let deviating = self
+            .tensors
+            .iter()
+            .filter(|t| t.is_matrix() && Some(t.quantization_type) != expected)
+            .map(|t| t.name.clone())
+            .collect();
+        Some(FileTypeConsistency {
+            declared,
+            expected,
+            dominant: self.dominant_quantization_type(),
+            deviating,
+        })
+    }
+
+    /// Render a markdown model card: title, properties table, tokenizer
+    /// section with the chat template, and a collapsible tensor summary
+    ///
+    /// Rows whose metadata is missing are left out.
+    #[cfg(feature = "report")]
+    pub fn model_card(&self) -> String {
+        report::model_card(self)
+    }
+
+    /// Get the Hugging Face `tokenizer.json` some conversions embed under
+    /// `tokenizer.huggingface.json`
+    pub fn embedded_tokenizer_json(&self) -> Option<&str> {
+        self.metadata.get_string_opt(keys::TOKENIZER_HUGGINGFACE_JSON)
+    }
+
+    /// Parse the embedded `tokenizer.json`, `Ok(None)` when there is none
+    #[cfg(feature = "json")]
+    pub fn parse_embedded_tokenizer_json(&self) -> Result<Option<serde_json::Value>> {
+        let Some(json) = self.embedded_tokenizer_json() else {
+            return Ok(None);
+        };
+        serde_json::from_str(json)
+            .map(Some)
+            .map_err(|e| GgufError::InvalidEmbeddedJson {
+                key: keys::TOKENIZER_HUGGINGFACE_JSON.to_string(),
+                line: e.line(),
+                column: e.column(),
+                message: e.to_string(),
+            })
+    }
+
+    /// Write the embedded `tokenizer.json` to a file
+    ///
+    /// With the `json` feature the blob is checked to be valid JSON first.
+    #[cfg(feature = "std")]
+    pub fn write_embedded_tokenizer_json<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let json = self
+            .embedded_tokenizer_json()
+            .ok_or_else(|| GgufError::MetadataKeyNotFound(keys::TOKENIZER_HUGGINGFACE_JSON.to_string()))?;
+        #[cfg(feature = "json")]
+        self.parse_embedded_tokenizer_json()?;
+        std::fs::write(path, json)?;
+        Ok(())
+    }
+
+    /// Determine whether this is a model, adapter, vocab-only file or
+    /// vision projector
+    pub fn kind(&self) -> GgufKind {
+        GgufKind::from_metadata(&self.metadata)
+    }
+
     /// Extract model configuration for inference
+    ///
+    /// Fails with `NotAModel` for adapters, vocab-only files, vision
+    /// projectors and files of unknown type.
     pub fn model_config(&self) -> Result<ModelConfig> {
-        ModelConfig::from_metadata(&self.metadata)
+        match self.kind() {
+            GgufKind::Model => ModelConfig::from_gguf(self),
+            other => Err(GgufError::NotAModel(other)),
+        }
+    }
+
+    /// Extract model configuration, falling back to tensor-shape inference
+    /// instead of failing when metadata is incomplete
+    pub fn model_config_inferred(&self) -> Result<ModelConfig> {
+        ModelConfig::infer(self)
+    }
+
+    /// Check whether the output projection reuses the token embedding, which
+    /// GGUF signals by storing `token_embd.weight` without `output.weight`
+    pub fn has_tied_embeddings(&self) -> bool {
+        let has_tensor = |name: &str| self.tensors.iter().any(|t| t.name == name);
+        has_tensor("token_embd.weight") && !has_tensor("output.weight")
+    }
+
+    /// Summarize the file for display: header, model configuration,
+    /// quantization histogram, sizes and the largest tensors
+    pub fn inspect(&self) -> InspectReport {
+        InspectReport::new(self)
     }
 
-    /// Get total file size in bytes
-    pub fn total_size(&self) -> u64 {
-        self.tensors.iter().map(|t| t.size_bytes()).sum()
+    /// Estimate the parameter count, counting tied embeddings once
+    pub fn estimated_param_count(&self) -> Result<u64> {
+        Ok(self.model_config()?.estimated_param_count(self.has_tied_embeddings()))
+    }
+
+    /// Get total tensor data size in bytes
+    ///
+    /// Fails if any tensor uses a quantization type of unknown size.
+    pub fn total_size(&self) -> Result<u64> {
+        self.tensors.iter().try_fold(0u64, |total, t| {
+            total.checked_add(t.size_bytes()?).ok_or_else(|| GgufError::SizeOverflow("tensor data".to_string()))
+        })
     }
 
     /// Check if this is a quantized model
@@ -83,4 +616,142 @@ impl GgufFile {
         types.dedup();
         types
     }
-}
\ No newline at end of file
+
+    /// Get each quantization type with the number of tensors using it, most
+    /// used first, ties in type order
+    pub fn quantization_types_by_frequency(&self) -> Vec<(QuantizationType, usize)> {
+        let mut counts: BTreeMap<QuantizationType, usize> = BTreeMap::new();
+        for tensor in &self.tensors {
+            *counts.entry(tensor.quantization_type).or_default() += 1;
+        }
+        let mut types: Vec<_> = counts.into_iter().collect();
+        types.sort_by(|(a_type, a_count), (b_type, b_count)| b_count.cmp(a_count).then(a_type.cmp(b_type)));
+        types
+    }
+
+    /// Get the most common quantization type across all tensors, the first
+    /// of `quantization_types_by_frequency`
+    pub fn dominant_quantization_type(&self) -> Option<QuantizationType> {
+        self.quantization_types_by_frequency().first().map(|&(quantization_type, _)| quantization_type)
+    }
+
+    /// Get a quality label from the dominant quantization type's bits per weight,
+    /// `None` when there are no tensors or the dominant type is unknown
+    pub fn quality_tier(&self) -> Option<QualityTier> {
+        let bits_per_weight = self.dominant_quantization_type()?.bits_per_weight()?;
+        Some(QualityTier::from_bits_per_weight(bits_per_weight))
+    }
+
+    /// Check that well-known tensors (`token_embd`, `output`, and the first
+    /// block's attention projections) have dimensions consistent with `config`
+    ///
+    /// All mismatches are returned rather than stopping at the first.
+    pub fn validate_against_config(&self, config: &ModelConfig) -> Vec<ShapeMismatch> {
+        validate::check_tensor_shapes(&self.tensors, config)
+    }
+
+    /// Get importance matrix provenance, `None` when the file has neither
+    /// `quantize.imatrix.*` keys nor IQ-quantized tensors
+    pub fn imatrix_info(&self) -> Option<ImatrixInfo> {
+        ImatrixInfo::from_parts(&self.metadata, &self.tensors)
+    }
+
+    /// Get tensors whose quantization type differs from the most common one,
+    /// e.g. `output.weight` kept at Q6_K in a Q4_K_M model
+    pub fn quantization_outliers(&self) -> Vec<&TensorInfo> {
+        let Some(dominant) = self.dominant_quantization_type() else {
+            return Vec::new();
+        };
+        self.tensors
+            .iter()
+            .filter(|t| t.quantization_type != dominant)
+            .collect()
+    }
+
+    /// Get the tokenizer vocabulary, borrowing the token strings
+    pub fn vocab(&self) -> Result<Vocab<'_>> {
+        Vocab::from_metadata(&self.metadata)
+    }
+
+    /// Describe where every tensor's data lives, sorted by offset
+    ///
+    /// Fails if any tensor uses a quantization type of unknown size.
+    pub fn data_layout(&self) -> Result<Vec<TensorExtent>> {
+        layout::data_layout(self)
+    }
+
+    /// Find the ranges between the start of the data section and the end of
+    /// the last tensor that hold no tensor data, classified as alignment
+    /// padding or unexplained holes
+    ///
+    /// Padding after the last tensor isn't reported since it depends on
+    /// the file length, see `verify_complete`.
+    pub fn data_gaps(&self) -> Result<Vec<DataGap>> {
+        layout::data_gaps(self)
+    }
+
+    /// Get the tensor names llama.cpp expects for this file's configuration,
+    /// without `output.weight` when the embeddings are tied
+    ///
+    /// Fails with `UnknownTensorLayout` for architectures whose layout isn't
+    /// known, see `ModelConfig::expected_tensor_names`.
+    pub fn expected_tensor_names(&self) -> Result<Vec<String>> {
+        let config = self.model_config()?;
+        config
+            .expected_tensor_names(self.has_tied_embeddings())
+            .ok_or(GgufError::UnknownTensorLayout(config.architecture))
+    }
+
+    /// List the expected tensor names this file doesn't have, in the order given
+    ///
+    /// Catches incomplete or corrupt downloads when checked against the
+    /// names an architecture requires.
+    pub fn missing_tensors(&self, expected_names: &[String]) -> Vec<String> {
+        let present: BTreeSet<&str> = self.tensors.iter().map(|t| t.name.as_str()).collect();
+        expected_names
+            .iter()
+            .filter(|name| !present.contains(name.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Get this shard's `split.*` keys, `None` for files that aren't split
+    pub fn split_info(&self) -> Option<SplitInfo> {
+        SplitInfo::from_metadata(&self.metadata)
+    }
+
+    /// Check the `split.*` keys against this shard: the shard number must be
+    /// below the shard count, only the first shard may carry model metadata,
+    /// and the shard can't hold more tensors than the whole model
+    ///
+    /// Empty for files that aren't split.
+    pub fn validate_split(&self) -> Vec<SplitWarning> {
+        match self.split_info() {
+            Some(split) => split.validate(&self.metadata, self.tensors.len() as u64),
+            None => Vec::new(),
+        }
+    }
+
+    /// Check each block `0..block_count` for the tensors `config` expects,
+    /// to pinpoint what a partial download lacks
+    ///
+    /// `None` when the architecture's layout isn't known.
+    pub fn layer_completeness(&self, config: &ModelConfig) -> Option<Vec<LayerReport>> {
+        tensor_names::layer_completeness(&self.tensors, config)
+    }
+
+    /// Get tensors in the order their data is stored, which may differ from
+    /// the order their infos are declared in
+    pub fn tensors_in_disk_order(&self) -> Vec<&TensorInfo> {
+        let mut tensors: Vec<_> = self.tensors.iter().collect();
+        tensors.sort_by_key(|t| t.offset);
+        tensors
+    }
+}
+
+fn alignment_of(metadata: &GgufMetadata) -> u64 {
+    metadata
+        .get_u64_opt(keys::GENERAL_ALIGNMENT)
+        .filter(|&a| a > 0)
+        .unwrap_or(DEFAULT_ALIGNMENT)
+}
@@ -0,0 +1,96 @@
+/*!
+ * Parse Work Limits
+ *
+ * Counts and lengths in a GGUF file are attacker-controlled. The parser
+ * already rejects arrays that can't fit in the input, `ParseLimits` also
+ * caps what a well-formed but hostile file can make it allocate and read.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::io::{Read, Seek, SeekFrom};
+use serde::{Deserialize, Serialize};
+
+/// Upper bounds on the work a single parse may do, see `ParseOptions::limits`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseLimits {
+    /// Maximum `metadata_kv_count` in the header
+    pub max_metadata_kv: u64,
+    /// Maximum `tensor_count` in the header
+    pub max_tensors: u64,
+    /// Maximum length in bytes of any key, string value or tensor name
+    pub max_string_len: u64,
+    /// Maximum number of elements in any metadata array
+    pub max_array_len: u64,
+    /// Maximum bytes read for the header, metadata and tensor infos together
+    pub max_total_bytes: u64,
+}
+
+impl ParseLimits {
+    /// No limits beyond the size of the input
+    pub const UNLIMITED: Self = Self {
+        max_metadata_kv: u64::MAX,
+        max_tensors: u64::MAX,
+        max_string_len: u64::MAX,
+        max_array_len: u64::MAX,
+        max_total_bytes: u64::MAX,
+    };
+}
+
+impl Default for ParseLimits {
+    /// Limits well above any published model, whose largest metadata
+    /// section is a few hundred thousand vocab entries
+    fn default() -> Self {
+        Self {
+            max_metadata_kv: 1 << 16,
+            max_tensors: 1 << 20,
+            max_string_len: 64 << 20,
+            max_array_len: 1 << 24,
+            max_total_bytes: 4 << 30,
+        }
+    }
+}
+
+/// Fail with `LimitExceeded` if `value` is above `max`
+pub(crate) fn check(limit: &'static str, value: u64, max: u64) -> Result<()> {
+    if value > max {
+        return Err(GgufError::LimitExceeded { limit, value });
+    }
+    Ok(())
+}
+
+/// Reader that fails once more than `max_total_bytes` past its starting
+/// position would be read
+pub(crate) struct LimitedReader<'r, R> {
+    reader: &'r mut R,
+    start: u64,
+    position: u64,
+    max_total_bytes: u64,
+}
+
+impl<'r, R: Seek> LimitedReader<'r, R> {
+    pub(crate) fn new(reader: &'r mut R, max_total_bytes: u64) -> Result<Self> {
+        let start = reader.stream_position()?;
+        Ok(Self { reader, start, position: start, max_total_bytes })
+    }
+}
+
+impl<R: Read> Read for LimitedReader<'_, R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        let read_end = self.position.saturating_add(buf.len() as u64);
+        check("max_total_bytes", read_end.saturating_sub(self.start), self.max_total_bytes)?;
+        self.reader.read_exact(buf)?;
+        self.position = read_end;
+        Ok(())
+    }
+}
+
+impl<R: Seek> Seek for LimitedReader<'_, R> {
+    fn seek(&mut self, pos: SeekFrom) -> Result<u64> {
+        self.position = self.reader.seek(pos)?;
+        Ok(self.position)
+    }
+
+    fn stream_position(&mut self) -> Result<u64> {
+        Ok(self.position)
+    }
+}
@@ -3,58 +3,257 @@
  */
 
 use crate::error::{GgufError, Result};
-use crate::types::{GgufValue, GgufValueType};
+use crate::keys::{self, KeyAlias};
+use crate::metadata_stats::MetadataStats;
+use crate::tensor::{QuantizationType, TensorInfo};
+use crate::types::GgufValue;
+use crate::GgufFile;
 use serde::{Deserialize, Serialize};
+use crate::io::{Read, Seek};
+use crate::limits::ParseLimits;
+use crate::options::ParseOptions;
+use crate::read::read_string_lossy;
+use crate::types::GgufValueType;
+use crate::warning::{GgufWarning, ParseWarning};
+use crate::metadata_reader::GgufMetadataReader;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::collections::BTreeMap;
+use alloc::vec;
+use alloc::vec::Vec;
+use alloc::sync::Arc;
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap as HashMap;
+#[cfg(feature = "std")]
 use std::collections::HashMap;
-use std::io::{Read, Seek};
 
 /// GGUF metadata container
-#[derive(Debug, Clone)]
+///
+/// Keys keep their file order, which the mutation methods (`insert`,
+/// `remove`, `retain`, `rename_key`) maintain. Lookups and inserts go
+/// through a key index, so building metadata is linear in the key count.
+/// Serializes as a map in key order.
+///
+/// Values are shared behind `Arc`, so cloning costs one reference count per
+/// key no matter how large the token arrays or chat templates are.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct GgufMetadata {
-    pub data: HashMap<String, GgufValue>,
+    /// Entries in file or insertion order
+    entries: Vec<(String, Arc<GgufValue>)>,
+    /// Position of each key in `entries`
+    index: HashMap<String, usize>,
 }
 
 impl GgufMetadata {
     /// Read metadata from a reader
     pub fn read<R: Read + Seek>(reader: &mut R, kv_count: u64) -> Result<Self> {
-        let mut data = HashMap::new();
+        Self::read_selected(reader, kv_count, |_| true)
+    }
+
+    /// Read only the listed keys, skipping every other value
+    pub fn read_keys<R: Read + Seek>(reader: &mut R, kv_count: u64, keys: &[&str]) -> Result<Self> {
+        Self::read_selected(reader, kv_count, |key| keys.contains(&key))
+    }
+
+    /// Read metadata, materializing only keys accepted by the predicate
+    ///
+    /// Rejected values are skipped without allocation, so the reader ends at
+    /// the same position as a full parse.
+    pub fn read_selected<R, F>(reader: &mut R, kv_count: u64, mut predicate: F) -> Result<Self>
+    where
+        R: Read + Seek,
+        F: FnMut(&str) -> bool,
+    {
+        Self::read_entries(reader, kv_count, &mut predicate, &ParseOptions { limits: ParseLimits::UNLIMITED, ..Default::default() }, &mut Vec::new())
+            .map_err(|e| e.at_stage("metadata"))
+    }
+
+    /// Read metadata under the given options, also returning warnings for
+    /// repeated keys and lossily decoded strings
+    pub(crate) fn read_with_warnings<R: Read + Seek>(
+        reader: &mut R,
+        kv_count: u64,
+        options: &ParseOptions,
+    ) -> Result<(Self, Vec<GgufWarning>)> {
+        let mut warnings = Vec::new();
+        let metadata = Self::read_entries(reader, kv_count, &mut |_| true, options, &mut warnings)
+            .map_err(|e| e.at_stage("metadata"))?;
+        Ok((metadata, warnings))
+    }
 
-        for _ in 0..kv_count {
-            // Read key
-            let key = {
-                let mut key_len_buf = [0u8; 8];
-                reader.read_exact(&mut key_len_buf)?;
-                let key_len = u64::from_le_bytes(key_len_buf);
+    fn read_entries<R, F>(
+        reader: &mut R,
+        kv_count: u64,
+        predicate: &mut F,
+        options: &ParseOptions,
+        warnings: &mut Vec<GgufWarning>,
+    ) -> Result<Self>
+    where
+        R: Read + Seek,
+        F: FnMut(&str) -> bool,
+    {
+        let mut metadata = Self::default();
+        let mut entries = GgufMetadataReader::with_limits(reader, kv_count, options.limits);
+        while let Some(entry) = entries.step(|reader, limits, key, value_type| {
+            if !predicate(key) {
+                return GgufValue::skip(reader, value_type).map(|_| None);
+            }
+            if options.lossy_utf8 && value_type == GgufValueType::String {
+                let (string, replaced) = read_string_lossy(reader, limits)?;
+                return Ok(Some((GgufValue::String(string), replaced)));
+            }
+            GgufValue::read_with_limits(reader, value_type, limits).map(|value| Some((value, false)))
+        }) {
+            // Rejected values were skipped without being read
+            if let (entry_start, key, Some((value, replaced))) = entry? {
+                if replaced {
+                    warnings.push(GgufWarning::new(ParseWarning::LossyUtf8(key.clone()), Some(entry_start)));
+                }
+                if metadata.contains(&key) {
+                    warnings.push(GgufWarning::new(ParseWarning::DuplicateKey(key.clone()), Some(entry_start)));
+                }
+                metadata.insert(key, value);
+            }
+        }
+        Ok(metadata)
+    }
+
+    /// Insert a value, returning the previous one
+    ///
+    /// New keys go last, replaced keys keep their position.
+    pub fn insert<K: Into<String>, V: Into<GgufValue>>(&mut self, key: K, value: V) -> Option<GgufValue> {
+        let key = key.into();
+        let value = Arc::new(value.into());
+        if let Some(&position) = self.index.get(&key) {
+            return Some(unshare(core::mem::replace(&mut self.entries[position].1, value)));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
 
-                let mut key_buf = vec![0u8; key_len as usize];
-                reader.read_exact(&mut key_buf)?;
-                String::from_utf8(key_buf)?
-            };
+    /// Remove a value, returning it if it was present
+    ///
+    /// Later entries move up one position, so this is linear in the number
+    /// of entries after the removed one.
+    pub fn remove(&mut self, key: &str) -> Option<GgufValue> {
+        let position = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for (key, _) in &self.entries[position..] {
+            if let Some(slot) = self.index.get_mut(key) {
+                *slot -= 1;
+            }
+        }
+        Some(unshare(value))
+    }
 
-            // Read value type
-            let value_type = {
-                let mut type_buf = [0u8; 4];
-                reader.read_exact(&mut type_buf)?;
-                GgufValueType::try_from(u32::from_le_bytes(type_buf))?
-            };
+    /// Keep only the entries the predicate accepts, preserving their order
+    pub fn retain<F: FnMut(&str, &GgufValue) -> bool>(&mut self, mut predicate: F) {
+        self.entries.retain(|(key, value)| predicate(key, value));
+        self.reindex();
+    }
 
-            // Read value
-            let value = GgufValue::read(reader, value_type)?;
-            data.insert(key, value);
+    /// Rename a key in place, replacing any existing entry under `new`
+    ///
+    /// Returns `false` if `old` is not present.
+    pub fn rename_key<K: Into<String>>(&mut self, old: &str, new: K) -> bool {
+        let new = new.into();
+        if !self.index.contains_key(old) {
+            return false;
         }
+        if old == new {
+            return true;
+        }
+        // Drop any entry already under the new name, then rename in place
+        self.entries.retain(|(key, _)| *key != new);
+        if let Some((key, _)) = self.entries.iter_mut().find(|(key, _)| key == old) {
+            *key = new;
+        }
+        self.reindex();
+        true
+    }
+
+    /// Rebuild `index` after entries moved
+    fn reindex(&mut self) {
+        self.index = self.entries.iter().enumerate().map(|(position, (key, _))| (key.clone(), position)).collect();
+    }
+
+    /// Iterate over all entries in file order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &GgufValue)> {
+        self.entries.iter().map(|(key, value)| (key.as_str(), &**value))
+    }
+
+    /// Iterate over all keys in file order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(key, _)| key.as_str())
+    }
 
-        Ok(Self { data })
+    /// Number of entries
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check whether there are no entries
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
     }
 
     /// Get a metadata value by key
     pub fn get(&self, key: &str) -> Option<&GgufValue> {
-        self.data.get(key)
+        self.index.get(key).map(|&position| &*self.entries[position].1)
+    }
+
+    /// Iterate over all keys starting with the given prefix, in file order
+    pub fn keys_with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.keys().filter(move |key| key.starts_with(prefix))
+    }
+
+    /// Serialized size of a value's payload in bytes, excluding key and type tag
+    pub fn value_size(&self, key: &str) -> Option<u64> {
+        self.get(key).map(|v| v.serialized_size())
+    }
+
+    /// List the `n` keys with the largest serialized values, largest first,
+    /// to find what's bloating the metadata section
+    pub fn largest_values(&self, n: usize) -> Vec<(&str, u64)> {
+        let mut sizes: Vec<_> = self
+            .iter()
+            .map(|(key, value)| (key, value.serialized_size()))
+            .collect();
+        sizes.sort_unstable_by(|(a_key, a_size), (b_key, b_size)| b_size.cmp(a_size).then(a_key.cmp(b_key)));
+        sizes.truncate(n);
+        sizes
+    }
+
+    /// Break the metadata down by value type, namespace and largest entries
+    pub fn stats(&self) -> MetadataStats {
+        MetadataStats::new(self)
+    }
+
+    /// Check whether a key is present
+    pub fn contains(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    /// List keys in this file not covered by the well-known key tables, sorted
+    pub fn known_unknown_keys(&self) -> Vec<&str> {
+        let mut unknown: Vec<_> = self
+            .keys()
+            .filter(|key| !keys::is_well_known(key))
+            .collect();
+        unknown.sort_unstable();
+        unknown
+    }
+
+    /// Get the first present key among aliases for the same concept,
+    /// returning which alias matched along with its value
+    pub fn get_with_aliases<'k>(&self, keys: &[&'k str]) -> Option<(&'k str, &GgufValue)> {
+        keys.iter().find_map(|&key| self.get(key).map(|value| (key, value)))
     }
 
     /// Get a required metadata value by key
     pub fn get_required(&self, key: &str) -> Result<&GgufValue> {
-        self.data
-            .get(key)
+        self.get(key)
             .ok_or_else(|| GgufError::MetadataKeyNotFound(key.to_string()))
     }
 
@@ -93,12 +292,209 @@ impl GgufMetadata {
         self.get_required(key)?.as_f32()
     }
 
+    /// Get an array of strings, such as `tokenizer.ggml.tokens`
+    ///
+    /// Fails if the key is missing, isn't an array, or holds a non-string
+    /// element, in which case the error key names the element as `key[index]`.
+    pub fn get_string_array(&self, key: &str) -> Result<Vec<String>> {
+        let items = match self.get_required(key)? {
+            GgufValue::Array(items) => items,
+            other => {
+                return Err(GgufError::InvalidMetadataValueType {
+                    key: key.to_string(),
+                    expected: "string array".to_string(),
+                    found: format!("{:?}", other.value_type()),
+                });
+            }
+        };
+        items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                GgufValue::String(value) => Ok(value.clone()),
+                other => Err(GgufError::InvalidMetadataValueType {
+                    key: format!("{key}[{index}]"),
+                    expected: "string".to_string(),
+                    found: format!("{:?}", other.value_type()),
+                }),
+            })
+            .collect()
+    }
+
     /// Get an optional f32 value
     pub fn get_f32_opt(&self, key: &str) -> Option<f32> {
         self.get(key).and_then(|v| v.as_f32().ok())
     }
 }
 
+impl<K: Into<String>, V: Into<GgufValue>> FromIterator<(K, V)> for GgufMetadata {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        let mut metadata = Self::default();
+        for (key, value) in entries {
+            metadata.insert(key, value);
+        }
+        metadata
+    }
+}
+
+impl Serialize for GgufMetadata {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> core::result::Result<S::Ok, S::Error> {
+        use serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(self.entries.len()))?;
+        for (key, value) in &self.entries {
+            map.serialize_entry(key, &**value)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for GgufMetadata {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> core::result::Result<Self, D::Error> {
+        struct MetadataVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for MetadataVisitor {
+            type Value = GgufMetadata;
+
+            fn expecting(&self, formatter: &mut core::fmt::Formatter) -> core::fmt::Result {
+                formatter.write_str("a map of metadata keys to values")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, mut access: A) -> core::result::Result<Self::Value, A::Error> {
+                let mut metadata = GgufMetadata::default();
+                while let Some((key, value)) = access.next_entry::<String, GgufValue>()? {
+                    metadata.insert(key, value);
+                }
+                Ok(metadata)
+            }
+        }
+
+        deserializer.deserialize_map(MetadataVisitor)
+    }
+}
+
+/// Take a value out of its `Arc`, copying only if a clone still shares it
+fn unshare(value: Arc<GgufValue>) -> GgufValue {
+    Arc::try_unwrap(value).unwrap_or_else(|shared| (*shared).clone())
+}
+
+/// Base model a GGUF was derived from (`general.base_model.N.*`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaseModel {
+    pub name: Option<String>,
+    pub organization: Option<String>,
+    pub repo_url: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Dataset a model was trained on (`general.dataset.N.*`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dataset {
+    pub name: Option<String>,
+    pub organization: Option<String>,
+    pub repo_url: Option<String>,
+    pub version: Option<String>,
+}
+
+/// Rotary position embedding variant
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RopeType {
+    /// Llama style, rotating adjacent pairs
+    Norm,
+    /// GPT-NeoX style, rotating the two halves of each head
+    Neox,
+    /// ChatGLM style
+    GlmRoPE,
+    /// Any other variant, as named or numbered in the file
+    Other(String),
+}
+
+impl RopeType {
+    /// Parse a rope type name as stored in `{arch}.rope.type`
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "norm" | "normal" => RopeType::Norm,
+            "neox" => RopeType::Neox,
+            "glm" => RopeType::GlmRoPE,
+            _ => RopeType::Other(name.to_string()),
+        }
+    }
+
+    /// Map a llama.cpp `llama_rope_type` code
+    pub fn from_code(code: i64) -> Self {
+        match code {
+            0 => RopeType::Norm,
+            2 => RopeType::Neox,
+            4 => RopeType::GlmRoPE,
+            other => RopeType::Other(other.to_string()),
+        }
+    }
+
+    /// Rope variant implied by an architecture, following llama.cpp
+    pub fn for_architecture(architecture: &str) -> Option<Self> {
+        match architecture {
+            "llama" | "mistral" | "mixtral" | "codellama" | "baichuan" | "starcoder" | "internlm2"
+            | "minicpm" | "xverse" | "command-r" | "olmo" | "arctic" | "deepseek" | "deepseek2"
+            | "granite" | "granitemoe" => Some(RopeType::Norm),
+            "falcon" | "grok" | "dbrx" | "bert" | "nomic-bert" | "stablelm" | "bitnet" | "qwen"
+            | "qwen2" | "qwen2moe" | "qwen3" | "phi2" | "phi3" | "gemma" | "gemma2" | "gemma3"
+            | "starcoder2" | "openelm" | "gptneox" | "codeshell" | "exaone" | "nemotron"
+            | "olmo2" | "olmoe" => Some(RopeType::Neox),
+            "chatglm" => Some(RopeType::GlmRoPE),
+            _ => None,
+        }
+    }
+}
+
+/// Feed-forward activation, which decides the FFN kernel an engine needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Activation {
+    /// SiLU-gated FFN with gate, up and down projections
+    SwiGLU,
+    /// GELU-gated FFN with gate, up and down projections
+    GeGLU,
+    /// Plain GELU between up and down projections
+    Gelu,
+}
+
+impl Activation {
+    /// Activation implied by an architecture, following llama.cpp
+    pub fn for_architecture(architecture: &str) -> Option<Self> {
+        match architecture {
+            "llama" | "mistral" | "mixtral" | "codellama" | "qwen" | "qwen2" | "qwen2moe" | "qwen3"
+            | "phi3" | "baichuan" | "internlm2" | "minicpm" | "xverse" | "command-r" | "olmo"
+            | "olmo2" | "olmoe" | "deepseek" | "deepseek2" | "granite" | "granitemoe" | "exaone"
+            | "stablelm" | "chatglm" | "arctic" | "nomic-bert" => Some(Activation::SwiGLU),
+            "gemma" | "gemma2" | "gemma3" => Some(Activation::GeGLU),
+            "gpt2" | "gptj" | "gptneox" | "falcon" | "starcoder" | "starcoder2" | "bert" | "phi2"
+            | "bloom" | "mpt" => Some(Activation::Gelu),
+            _ => None,
+        }
+    }
+
+    /// Whether the FFN has a separate gate projection
+    pub fn is_gated(&self) -> bool {
+        matches!(self, Activation::SwiGLU | Activation::GeGLU)
+    }
+}
+
+/// Context lengths a model supports, see `ModelConfig::effective_context`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EffectiveContext {
+    /// Context the model was trained at
+    pub native: u64,
+    /// Longer context reachable through the configured rope scaling
+    pub extended: Option<u64>,
+    /// Sliding attention window, how far back each token attends
+    pub attention_window: Option<u64>,
+}
+
+impl EffectiveContext {
+    /// Longest usable context, extended if scaling is configured
+    pub fn max(&self) -> u64 {
+        self.extended.unwrap_or(self.native)
+    }
+}
+
 /// Model configuration extracted from GGUF metadata
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelConfig {
@@ -116,86 +512,189 @@ pub struct ModelConfig {
     pub attention_head_count: u32,
     pub attention_head_count_kv: Option<u32>,
     pub attention_layer_norm_rms_epsilon: Option<f32>,
+    #[serde(default)]
+    pub attention_key_length: Option<u32>,
+    #[serde(default)]
+    pub attention_value_length: Option<u32>,
+    #[serde(default)]
+    pub attention_sliding_window: Option<u32>,
+    #[serde(default)]
+    pub attention_sliding_window_pattern: Option<u32>,
     
     // Rope configuration
     pub rope_dimension_count: Option<u32>,
     pub rope_freq_base: Option<f32>,
     pub rope_scaling_type: Option<String>,
+    #[serde(default)]
+    pub rope_scaling_factor: Option<f32>,
+    #[serde(default)]
+    pub rope_scaling_original_context_length: Option<u64>,
+    /// Whether the model was finetuned at the scaled context
+    #[serde(default)]
+    pub rope_scaling_finetuned: Option<bool>,
+    #[serde(default)]
+    pub rope_type: Option<RopeType>,
+
+    // Mixture of experts configuration
+    #[serde(default)]
+    pub expert_count: Option<u32>,
+    #[serde(default)]
+    pub expert_used_count: Option<u32>,
     
     // Tokenizer info
     pub tokenizer_ggml_model: Option<String>,
-    pub tokenizer_ggml_tokens: Option<Vec<String>>,
-    pub tokenizer_ggml_scores: Option<Vec<f32>>,
-    pub tokenizer_ggml_token_type: Option<Vec<u32>>,
+    pub tokenizer_ggml_tokens: Option<Arc<[String]>>,
+    pub tokenizer_ggml_scores: Option<Arc<[f32]>>,
+    pub tokenizer_ggml_token_type: Option<Arc<[u32]>>,
+    // Length of `tokenizer.ggml.tokens`, known without materializing the array
+    #[serde(default)]
+    pub tokenizer_token_count: Option<u64>,
     
     // Chat template
-    pub tokenizer_chat_template: Option<String>,
+    pub tokenizer_chat_template: Option<Arc<str>>,
     
     // Additional metadata
     pub general_name: Option<String>,
     pub general_description: Option<String>,
     pub general_license: Option<String>,
+    pub general_author: Option<String>,
+    pub general_url: Option<String>,
+    pub general_source_url: Option<String>,
+    pub general_organization: Option<String>,
+    #[serde(default)]
+    pub general_size_label: Option<String>,
+    pub base_models: Vec<BaseModel>,
+    pub datasets: Vec<Dataset>,
+
+    // Fields deduced from tensor shapes rather than read from metadata
+    #[serde(default)]
+    pub inferred_fields: Vec<String>,
+
+    // Fields filled from architecture defaults by `with_defaults`
+    #[serde(default)]
+    pub defaulted_fields: Vec<String>,
+
+    // Metadata key each field was read from, showing which alias matched
+    #[serde(default)]
+    pub source_keys: BTreeMap<String, String>,
+
+    // Prefix the architecture-specific keys were read under, normally the
+    // architecture itself
+    #[serde(default)]
+    pub key_prefix: String,
 }
 
 impl ModelConfig {
     /// Extract model configuration from GGUF metadata
     pub fn from_metadata(metadata: &GgufMetadata) -> Result<Self> {
-        // Architecture is required
-        let architecture = metadata.get_string("general.architecture")?.to_string();
-        
-        // Use architecture-specific prefixes for parameter names
-        let arch_prefix = format!("{architecture}.");
+        Self::from_parts(metadata, &[], DEFAULT_FALLBACK_PREFIXES)
+    }
+
+    /// Extract model configuration, reading architecture-specific keys from
+    /// the first of these prefixes that has a `block_count` when the
+    /// declared architecture's prefix doesn't
+    ///
+    /// Converter bugs sometimes write e.g. `llama.*` keys into a file whose
+    /// `general.architecture` is `qwen2`. The prefix found by
+    /// `detect_key_prefix` is tried after `fallbacks`.
+    pub fn from_metadata_with_fallbacks(metadata: &GgufMetadata, fallbacks: &[&str]) -> Result<Self> {
+        Self::from_parts(metadata, &[], fallbacks)
+    }
+
+    /// Extract model configuration from a parsed GGUF file
+    ///
+    /// Unlike `from_metadata`, this can fall back to the `token_embd.weight`
+    /// shape when neither a vocab size key nor a token array is present.
+    pub fn from_gguf(gguf_file: &GgufFile) -> Result<Self> {
+        Self::from_parts(&gguf_file.metadata, &gguf_file.tensors, DEFAULT_FALLBACK_PREFIXES)
+    }
+
+    fn from_parts(metadata: &GgufMetadata, tensors: &[TensorInfo], fallbacks: &[&str]) -> Result<Self> {
+        // Architecture is required, and resolves the `{arch}` prefixed keys
+        let arch = metadata
+            .get_string_opt(keys::GENERAL_ARCHITECTURE)
+            .ok_or_else(|| GgufError::MetadataKeyNotFound(keys::GENERAL_ARCHITECTURE.to_string()))?;
+        let key_prefix = resolve_key_prefix(metadata, arch, fallbacks);
+        let mut lookup = FieldLookup::new(metadata, key_prefix);
+        let architecture = lookup.string("architecture").unwrap_or_default();
         
-        // Required parameters - vocab_size can be inferred from tokenizer tokens
-        let vocab_size = metadata.get_u64("general.vocab_size")
-            .or_else(|_| metadata.get_u64(&format!("{arch_prefix}vocab_size")))
-            .or_else(|_| {
-                // Infer vocab_size from tokenizer tokens array length
-                if let Some(GgufValue::Array(tokens)) = metadata.get("tokenizer.ggml.tokens") {
-                    Ok(tokens.len() as u64)
-                } else {
-                    Err(GgufError::IncompleteModelConfig("vocab_size".to_string()))
-                }
-            })?;
+        // Required parameters
+        let vocab_size = resolve_vocab_size(&mut lookup, metadata, tensors)
+            .ok_or_else(|| GgufError::IncompleteModelConfig("vocab_size".to_string()))?;
 
-        let context_length = metadata.get_u64("general.context_length")
-            .or_else(|_| metadata.get_u64(&format!("{arch_prefix}context_length")))
-            .map_err(|_| GgufError::IncompleteModelConfig("context_length".to_string()))?;
+        let context_length = lookup.u64("context_length")
+            .ok_or_else(|| GgufError::IncompleteModelConfig("context_length".to_string()))?;
 
-        let block_count = metadata.get_u32(&format!("{arch_prefix}block_count"))
-            .map_err(|_| GgufError::IncompleteModelConfig("block_count".to_string()))?;
+        let block_count = lookup.u32("block_count")
+            .ok_or_else(|| GgufError::IncompleteModelConfig("block_count".to_string()))?;
 
-        let embedding_length = metadata.get_u32(&format!("{arch_prefix}embedding_length"))
-            .map_err(|_| GgufError::IncompleteModelConfig("embedding_length".to_string()))?;
+        let embedding_length = lookup.u32("embedding_length")
+            .ok_or_else(|| GgufError::IncompleteModelConfig("embedding_length".to_string()))?;
 
-        let feed_forward_length = metadata.get_u32(&format!("{arch_prefix}feed_forward_length"))
-            .map_err(|_| GgufError::IncompleteModelConfig("feed_forward_length".to_string()))?;
+        let feed_forward_length = lookup.u32("feed_forward_length")
+            .ok_or_else(|| GgufError::IncompleteModelConfig("feed_forward_length".to_string()))?;
 
-        let attention_head_count = metadata.get_u32(&format!("{arch_prefix}attention.head_count"))
-            .map_err(|_| GgufError::IncompleteModelConfig("attention.head_count".to_string()))?;
+        let attention_head_count = lookup.u32("attention_head_count")
+            .ok_or_else(|| GgufError::IncompleteModelConfig("attention.head_count".to_string()))?;
 
         // Optional parameters
-        let attention_head_count_kv = metadata.get_u32_opt(&format!("{arch_prefix}attention.head_count_kv"));
-        let attention_layer_norm_rms_epsilon = metadata.get_f32_opt(&format!("{arch_prefix}attention.layer_norm_rms_epsilon"));
+        let attention_head_count_kv = lookup.u32("attention_head_count_kv");
+        let attention_layer_norm_rms_epsilon = lookup.f32("attention_layer_norm_rms_epsilon");
+        let attention_key_length = lookup.u32("attention_key_length");
+        let attention_value_length = lookup.u32("attention_value_length");
+        let attention_sliding_window = lookup.u32("attention_sliding_window");
+        let attention_sliding_window_pattern = lookup.u32("attention_sliding_window_pattern");
         
-        let rope_dimension_count = metadata.get_u32_opt(&format!("{arch_prefix}rope.dimension_count"));
-        let rope_freq_base = metadata.get_f32_opt(&format!("{arch_prefix}rope.freq_base"));
-        let rope_scaling_type = metadata.get_string_opt(&format!("{arch_prefix}rope.scaling.type")).map(|s| s.to_string());
+        let rope_dimension_count = lookup.u32("rope_dimension_count");
+        let rope_freq_base = lookup.f32("rope_freq_base");
+        let rope_scaling_type = lookup.string("rope_scaling_type");
+        let rope_scaling_factor = lookup.f32("rope_scaling_factor");
+        let rope_scaling_original_context_length = lookup.u64("rope_scaling_original_context_length");
+        let rope_scaling_finetuned = lookup.bool("rope_scaling_finetuned");
+        let rope_type = match lookup.value("rope_type") {
+            Some(GgufValue::String(name)) => Some(RopeType::from_name(name)),
+            Some(value) => value.integer_value().map(|code| RopeType::from_code(code as i64)),
+            None => RopeType::for_architecture(arch),
+        };
+
+        let expert_count = lookup.u32("expert_count");
+        let expert_used_count = lookup.u32("expert_used_count");
         
         // Tokenizer information
-        let tokenizer_ggml_model = metadata.get_string_opt("tokenizer.ggml.model").map(|s| s.to_string());
+        let tokenizer_ggml_model = lookup.string("tokenizer_ggml_model");
         
-        // TODO: Parse tokenizer arrays (tokens, scores, token_type)
+        // The arrays stay in the metadata, `GgufFile::vocab` decodes them.
+        // Only note whether the optional arrays exist, see `has_tokenizer_scores`
+        lookup.record_source("tokenizer_ggml_scores");
+        lookup.record_source("tokenizer_ggml_token_type");
         let tokenizer_ggml_tokens = None;
         let tokenizer_ggml_scores = None;
         let tokenizer_ggml_token_type = None;
         
-        let tokenizer_chat_template = metadata.get_string_opt("tokenizer.chat_template").map(|s| s.to_string());
+        let tokenizer_chat_template = lookup.string("tokenizer_chat_template").map(Arc::from);
         
         // General metadata
-        let general_name = metadata.get_string_opt("general.name").map(|s| s.to_string());
-        let general_description = metadata.get_string_opt("general.description").map(|s| s.to_string());
-        let general_license = metadata.get_string_opt("general.license").map(|s| s.to_string());
+        let general_name = lookup.string("general_name");
+        let general_description = lookup.string("general_description");
+        let general_license = lookup.string("general_license");
+        let general_author = lookup.string("general_author");
+        let general_url = lookup.string("general_url");
+        let general_source_url = lookup.string("general_source_url");
+        let general_organization = lookup.string("general_organization");
+        let general_size_label = lookup.string("general_size_label");
+
+        // Base models and datasets are stored as a count plus indexed keys
+        let base_model_count = lookup.u32("base_model_count").unwrap_or(0);
+        let base_models = indexed_entries(metadata, "general.base_model.", base_model_count)
+            .into_iter()
+            .map(|[name, organization, repo_url, version]| BaseModel { name, organization, repo_url, version })
+            .collect();
+
+        let dataset_count = lookup.u32("dataset_count").unwrap_or(0);
+        let datasets = indexed_entries(metadata, "general.dataset.", dataset_count)
+            .into_iter()
+            .map(|[name, organization, repo_url, version]| Dataset { name, organization, repo_url, version })
+            .collect();
 
         Ok(ModelConfig {
             architecture,
@@ -207,35 +706,302 @@ impl ModelConfig {
             attention_head_count,
             attention_head_count_kv,
             attention_layer_norm_rms_epsilon,
+            attention_key_length,
+            attention_value_length,
+            attention_sliding_window,
+            attention_sliding_window_pattern,
             rope_dimension_count,
             rope_freq_base,
             rope_scaling_type,
+            rope_scaling_factor,
+            rope_scaling_original_context_length,
+            rope_scaling_finetuned,
+            rope_type,
+            expert_count,
+            expert_used_count,
             tokenizer_ggml_model,
             tokenizer_ggml_tokens,
+            tokenizer_token_count: tokenizer_token_count(metadata),
             tokenizer_ggml_scores,
             tokenizer_ggml_token_type,
             tokenizer_chat_template,
             general_name,
             general_description,
             general_license,
+            general_author,
+            general_url,
+            general_source_url,
+            general_organization,
+            general_size_label,
+            base_models,
+            datasets,
+            inferred_fields: Vec::new(),
+            defaulted_fields: Vec::new(),
+            source_keys: lookup.into_source_keys(),
+            key_prefix: key_prefix.to_string(),
         })
     }
 
+    /// Per-head dimension of the attention keys
+    ///
+    /// Uses `attention.key_length` when present, since some models size their
+    /// heads independently of the embedding width, otherwise
+    /// `embedding_length / attention_head_count`.
+    pub fn head_dim(&self) -> u32 {
+        self.attention_key_length
+            .unwrap_or(self.embedding_length / self.attention_head_count.max(1))
+    }
+
+    /// Per-head dimension of the attention values, defaulting to `head_dim()`
+    fn value_head_dim(&self) -> u32 {
+        self.attention_value_length.unwrap_or_else(|| self.head_dim())
+    }
+
+    /// Whether the file has `tokenizer.ggml.scores`, known from
+    /// `source_keys` without materializing the array
+    pub fn has_tokenizer_scores(&self) -> bool {
+        self.source_keys.contains_key("tokenizer_ggml_scores")
+    }
+
+    /// Whether the file has `tokenizer.ggml.token_type`, known from
+    /// `source_keys` without materializing the array
+    pub fn has_tokenizer_token_types(&self) -> bool {
+        self.source_keys.contains_key("tokenizer_ggml_token_type")
+    }
+
+    /// FFN activation implied by the architecture, `None` for architectures
+    /// this crate doesn't know
+    ///
+    /// GGUF has no standard key recording the activation, so this is
+    /// inferred like `RopeType::for_architecture`.
+    pub fn ffn_activation(&self) -> Option<Activation> {
+        Activation::for_architecture(&self.architecture)
+    }
+
+    /// Context length to run with, given the one a user asked for
+    ///
+    /// The request is clamped to the trained `context_length`, and without a
+    /// request the trained length is used. A sliding attention window sets a
+    /// floor, since shorter contexts can't exercise the window. A zero
+    /// `context_length` is treated as unknown and doesn't clamp.
+    pub fn effective_context_length(&self, requested: Option<u64>) -> u64 {
+        let trained = self.context_length;
+        let context = match requested {
+            Some(requested) if trained > 0 => requested.min(trained),
+            Some(requested) => requested,
+            None => trained,
+        };
+        match self.attention_sliding_window {
+            Some(window) if trained > 0 => context.max((window as u64).min(trained)),
+            Some(window) => context.max(window as u64),
+            None => context,
+        }
+    }
+
+    /// Combine the trained context, rope scaling and sliding window into the
+    /// contexts a model can actually run at
+    ///
+    /// The native context is `native_context_length`. GGUF's
+    /// `context_length` is already the scaled length, so it is the extended
+    /// context, except that linear and YaRN scaling with a known
+    /// `rope_scaling_original_context_length` extend that by
+    /// `rope_scaling_factor`, a missing factor counting as 1.0. Without the
+    /// original length nothing is scaled twice.
+    pub fn effective_context(&self) -> EffectiveContext {
+        let attention_window = self.attention_sliding_window.filter(|&w| w > 0).map(u64::from);
+        let scaling = self.rope_scaling_type.as_deref().map(str::to_ascii_lowercase);
+
+        let native = self.native_context_length();
+        let extended = match (scaling.as_deref(), self.rope_scaling_original_context_length) {
+            (Some("linear" | "yarn"), Some(original)) => {
+                let factor = self.rope_scaling_factor.filter(|f| f.is_finite() && *f > 0.0).unwrap_or(1.0);
+                (original as f64 * factor as f64) as u64
+            }
+            _ => self.context_length,
+        };
+
+        EffectiveContext {
+            native,
+            extended: (extended > native).then_some(extended),
+            attention_window,
+        }
+    }
+
+    /// Context length the file declares, `general.context_length` or
+    /// `{arch}.context_length`
+    pub fn advertised_context_length(&self) -> u64 {
+        self.context_length
+    }
+
+    /// Context length the model was trained at before any rope scaling
+    ///
+    /// When a scaling type other than `none` is configured and the model
+    /// wasn't finetuned at the scaled length, this is
+    /// `rope_scaling_original_context_length` (or `context_length` if that
+    /// is missing). Otherwise the advertised length is native. A native
+    /// length below the advertised one marks an extended-context model.
+    pub fn native_context_length(&self) -> u64 {
+        let scaled = self
+            .rope_scaling_type
+            .as_deref()
+            .is_some_and(|scaling| !scaling.eq_ignore_ascii_case("none"));
+        if scaled && self.rope_scaling_finetuned != Some(true) {
+            self.rope_scaling_original_context_length.unwrap_or(self.context_length)
+        } else {
+            self.context_length
+        }
+    }
+
+    /// Longest context whose KV cache fits in `budget_bytes` for one sequence,
+    /// with the cache stored as `cache_type` (F16, Q8_0, ...)
+    ///
+    /// Capped at `effective_context().max()`. Sliding window layers stop
+    /// growing at the window, so once it fits only the global layers limit
+    /// the context, and a model without global layers fits any context.
+    /// Returns `None` if the head size or the size of `cache_type` is unknown.
+    pub fn max_context_for_memory(&self, budget_bytes: u64, cache_type: QuantizationType) -> Option<u64> {
+        if self.attention_key_length.is_none() && self.attention_head_count == 0 {
+            return None;
+        }
+        let (block_size, type_size) = (cache_type.block_size()?, cache_type.type_size()?);
+        let kv_heads = self.attention_head_count_kv.unwrap_or(self.attention_head_count) as u64;
+        let bytes_per_layer = kv_heads
+            .checked_mul(self.head_dim() as u64 + self.value_head_dim() as u64)?
+            .checked_mul(type_size)?;
+        if bytes_per_layer == 0 || self.block_count == 0 {
+            return None;
+        }
+
+        // Cached tokens summed over layers that fit in the budget
+        let slots = budget_bytes as u128 * block_size as u128 / bytes_per_layer as u128;
+        let layers = self.block_count as u128;
+        let fitting = match self.attention_sliding_window.filter(|&w| w > 0).map(u128::from) {
+            Some(window) if slots / layers >= window => {
+                let swa_layers = self.sliding_window_layer_count() as u128;
+                match layers - swa_layers {
+                    0 => u128::MAX,
+                    global_layers => (slots - swa_layers * window) / global_layers,
+                }
+            }
+            _ => slots / layers,
+        };
+        Some(fitting.min(self.effective_context().max() as u128) as u64)
+    }
+
+    /// Bytes needed for the K and V caches of `batch` sequences of
+    /// `context_length` tokens, with `cache_dtype_bytes` per element
+    /// (2 for an F16 cache)
+    ///
+    /// A sliding attention window caps the cached context of the layers
+    /// that use it, see `sliding_window_layer_count`. Returns `None` if the
+    /// head size can't be derived or the size overflows 64 bits.
+    pub fn kv_cache_bytes(&self, context_length: u64, batch: u64, cache_dtype_bytes: u64) -> Option<u64> {
+        if self.attention_key_length.is_none() && self.attention_head_count == 0 {
+            return None;
+        }
+        let swa_layers = self.sliding_window_layer_count() as u64;
+        let global_layers = self.block_count as u64 - swa_layers;
+        let swa_context = match self.attention_sliding_window {
+            Some(window) if window > 0 => context_length.min(window as u64),
+            _ => context_length,
+        };
+        let cached_tokens = swa_layers
+            .checked_mul(swa_context)?
+            .checked_add(global_layers.checked_mul(context_length)?)?;
+        let kv_heads = self.attention_head_count_kv.unwrap_or(self.attention_head_count) as u64;
+        let head_dims = self.head_dim() as u64 + self.value_head_dim() as u64;
+
+        [batch, kv_heads, head_dims, cache_dtype_bytes]
+            .into_iter()
+            .try_fold(cached_tokens, u64::checked_mul)
+    }
+
+    /// Number of layers that attend through the sliding window, 0 without one
+    ///
+    /// Follows llama.cpp's layer pattern `n`: all but the last layer of each
+    /// group of `n` use the window, and `n = 0` puts every layer on it. The
+    /// pattern is `attention_sliding_window_pattern`, or for models without
+    /// the key 2 for Gemma 2, 6 for Gemma 3 and 4 for Command R7B, which
+    /// interleave global layers. Other models use the window on every layer.
+    pub fn sliding_window_layer_count(&self) -> u32 {
+        if self.attention_sliding_window.is_none_or(|window| window == 0) {
+            return 0;
+        }
+        let pattern = self.attention_sliding_window_pattern.unwrap_or(match self.architecture.as_str() {
+            "gemma2" => 2,
+            "gemma3" => 6,
+            "cohere2" => 4,
+            _ => 0,
+        });
+        match pattern {
+            0 | 1 => self.block_count,
+            n => self.block_count / n * (n - 1) + (self.block_count % n).min(n - 1),
+        }
+    }
+
     /// Get model parameter count estimate
-    pub fn estimated_param_count(&self) -> u64 {
-        // Rough estimate based on transformer architecture
-        let vocab_embedding = self.vocab_size * self.embedding_length as u64;
-        let transformer_blocks = self.block_count as u64 * (
-            // Self-attention weights
-            4 * self.embedding_length as u64 * self.embedding_length as u64 +
-            // Feed-forward weights  
-            2 * self.embedding_length as u64 * self.feed_forward_length as u64 +
+    ///
+    /// Counts the embedding, per-block attention (with K/V sized by the KV
+    /// head count for GQA), feed-forward (three matrices for gated
+    /// architectures), norm weights, and the output projection unless
+    /// `tied_embeddings` is set. Biases, MoE experts, and architecture-specific
+    /// extras (e.g. SSM or vision tensors) are not included; count the actual
+    /// tensors when an exact figure is needed.
+    pub fn estimated_param_count(&self, tied_embeddings: bool) -> u64 {
+        let d = self.embedding_length as u64;
+        let heads = self.attention_head_count.max(1) as u64;
+        let kv_heads = self.attention_head_count_kv.map_or(heads, |kv| kv as u64);
+        let key_dim = self.head_dim() as u64;
+        let value_dim = self.value_head_dim() as u64;
+
+        // Saturating, since corrupt metadata can hold any sizes
+        let vocab_embedding = self.vocab_size.saturating_mul(d);
+        let block = [
+            // Q and O projections
+            d.saturating_mul(heads).saturating_mul(key_dim + value_dim),
+            // K and V projections
+            d.saturating_mul(kv_heads).saturating_mul(key_dim + value_dim),
+            // Feed-forward weights (gate, up, down when gated)
+            (self.ffn_matrix_count() * d).saturating_mul(self.feed_forward_length as u64),
             // Layer norms
-            2 * self.embedding_length as u64
-        );
-        let output_projection = self.vocab_size * self.embedding_length as u64;
-        
-        vocab_embedding + transformer_blocks + output_projection
+            2 * d,
+        ]
+        .into_iter()
+        .fold(0, u64::saturating_add);
+        let transformer_blocks = (self.block_count as u64).saturating_mul(block);
+        let output_norm = d;
+        let output_projection = if tied_embeddings { 0 } else { vocab_embedding };
+
+        [vocab_embedding, transformer_blocks, output_norm, output_projection]
+            .into_iter()
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Format `estimated_param_count` the way llama.cpp writes
+    /// `general.size_label`, e.g. "7B", "13B" or "1.1B"
+    ///
+    /// Scaled to K, M, B or T with at least two significant digits, and the
+    /// output projection counted as untied. Prefer `general_size_label` when
+    /// the file has one.
+    pub fn derived_size_label(&self) -> String {
+        let count = self.estimated_param_count(false) as f64;
+        let (scaled, suffix) = [(1e12, "T"), (1e9, "B"), (1e6, "M")]
+            .into_iter()
+            .find(|(scale, _)| count > *scale)
+            .map_or((count * 1e-3, "K"), |(scale, suffix)| (count / scale, suffix));
+        let integer_digits = format!("{scaled:.0}").len();
+        let label = format!("{scaled:.*}", 2usize.saturating_sub(integer_digits));
+        let label = label.strip_suffix(".0").unwrap_or(&label);
+        format!("{label}{suffix}")
+    }
+
+    /// Number of feed-forward weight matrices per block
+    fn ffn_matrix_count(&self) -> u64 {
+        // Unknown architectures are assumed to be gated like most current models
+        match self.ffn_activation() {
+            Some(activation) if !activation.is_gated() => 2,
+            _ => 3,
+        }
     }
 
     /// Check if this is a supported architecture
@@ -244,4 +1010,210 @@ impl ModelConfig {
             "llama" | "mistral" | "qwen" | "qwen2" | "phi3" | "gemma" | "mixtral" | "codellama"
         )
     }
-}
\ No newline at end of file
+}
+
+/// Prefixes `ModelConfig::from_metadata` falls back to when the declared
+/// architecture has no keys of its own
+pub const DEFAULT_FALLBACK_PREFIXES: &[&str] = &["llama"];
+
+const BLOCK_COUNT_ALIAS: KeyAlias = KeyAlias::Arch(keys::BLOCK_COUNT);
+
+/// Find the prefix architecture-specific keys are stored under, from the
+/// `<prefix>.block_count` key
+///
+/// Returns the alphabetically first prefix when several are present, e.g. in
+/// multimodal files.
+pub fn detect_key_prefix(metadata: &GgufMetadata) -> Option<&str> {
+    metadata
+        .keys()
+        .filter_map(|key| key.strip_suffix(".block_count"))
+        .filter(|prefix| !prefix.is_empty())
+        .min()
+}
+
+/// Find the prefix the `{arch}` keys of an `arch` model are stored under
+///
+/// The first of `arch`, `fallbacks` and `detect_key_prefix` that has a
+/// block count, or `arch` itself when none does.
+pub(crate) fn resolve_key_prefix<'a>(metadata: &'a GgufMetadata, arch: &'a str, fallbacks: &[&'a str]) -> &'a str {
+    let has_block_count =
+        |prefix: &str| metadata.keys().any(|key| BLOCK_COUNT_ALIAS.matches(prefix, key));
+    core::iter::once(arch)
+        .chain(fallbacks.iter().copied())
+        .chain(detect_key_prefix(metadata))
+        .find(|prefix| has_block_count(prefix))
+        .unwrap_or(arch)
+}
+
+/// Get the vocabulary size from the `vocab_size` field, falling back to the
+/// length of `tokenizer.ggml.tokens` and then to the `token_embd.weight` rows
+pub(crate) fn resolve_vocab_size(lookup: &mut FieldLookup, metadata: &GgufMetadata, tensors: &[TensorInfo]) -> Option<u64> {
+    lookup.u64("vocab_size").or_else(|| {
+        let embedding_rows = tensors
+            .iter()
+            .find(|t| t.name == "token_embd.weight")
+            .and_then(|t| t.dimensions.get(1).copied());
+        tokenizer_token_count(metadata).or(embedding_rows)
+    })
+}
+
+/// Largest plausible value of each hyperparameter, well above any released
+/// model, so larger values point at corrupt or misconverted metadata
+const SUSPICIOUS_LIMITS: &[(&str, u64)] = &[
+    ("vocab_size", 1 << 24),
+    ("context_length", 1 << 27),
+    ("block_count", 1 << 16),
+    ("embedding_length", 1 << 20),
+    ("feed_forward_length", 1 << 22),
+    ("attention_head_count", 1 << 16),
+    ("attention_head_count_kv", 1 << 16),
+    ("expert_count", 1 << 16),
+];
+
+/// Check the hyperparameters for inconsistencies that don't stop
+/// `ModelConfig` extraction: keys under another architecture's prefix, a
+/// vocab size missing or disagreeing with the token array, and implausibly
+/// large values
+pub(crate) fn hyperparameter_warnings(metadata: &GgufMetadata) -> Vec<ParseWarning> {
+    let mut warnings = Vec::new();
+    let Some(arch) = metadata.get_string_opt(keys::GENERAL_ARCHITECTURE) else {
+        return warnings;
+    };
+    let key_prefix = resolve_key_prefix(metadata, arch, DEFAULT_FALLBACK_PREFIXES);
+    if key_prefix != arch {
+        let warning = ParseWarning::KeyPrefixMismatch { architecture: arch.to_string(), prefix: key_prefix.to_string() };
+        warnings.push(warning);
+    }
+    let mut lookup = FieldLookup::new(metadata, key_prefix);
+    let token_count = tokenizer_token_count(metadata);
+    match (lookup.u64("vocab_size"), token_count) {
+        (None, Some(tokens)) => warnings.push(ParseWarning::InferredVocabSize { tokens }),
+        (Some(vocab_size), Some(tokens)) if vocab_size != tokens => {
+            warnings.push(ParseWarning::VocabSizeMismatch { vocab_size, tokens });
+        }
+        _ => {}
+    }
+    for &(field, limit) in SUSPICIOUS_LIMITS {
+        if let Some((key, value)) = lookup.entry(field)
+            && let Ok(value) = value.as_u64()
+            && value > limit
+        {
+            warnings.push(ParseWarning::SuspiciousValue { key: key.to_string(), value });
+        }
+    }
+    warnings
+}
+
+/// Length of the `tokenizer.ggml.tokens` array, if present
+fn tokenizer_token_count(metadata: &GgufMetadata) -> Option<u64> {
+    match metadata.get(keys::TOKENIZER_GGML_TOKENS) {
+        Some(GgufValue::Array(tokens)) => Some(tokens.len() as u64),
+        _ => None,
+    }
+}
+
+/// Reads `ModelConfig` fields through `keys::FIELD_ALIASES`, remembering
+/// which key each one came from
+///
+/// All fields are resolved in one pass over the metadata, matching keys
+/// against the alias patterns in place, so no key strings are built.
+pub(crate) struct FieldLookup<'a> {
+    /// Best match for each `FIELD_ALIASES` entry: alias rank, key and value
+    matches: [Option<(usize, &'a str, &'a GgufValue)>; FIELD_COUNT],
+    /// Fields read so far, reported by `into_source_keys`
+    used: [bool; FIELD_COUNT],
+}
+
+const FIELD_COUNT: usize = keys::FIELD_ALIASES.len();
+
+impl<'a> FieldLookup<'a> {
+    pub(crate) fn new(metadata: &'a GgufMetadata, arch: &str) -> Self {
+        let mut matches = [None; FIELD_COUNT];
+        for (key, value) in metadata.iter() {
+            for (slot, (_, aliases)) in matches.iter_mut().zip(keys::FIELD_ALIASES) {
+                if let Some(rank) = aliases.iter().position(|alias| alias.matches(arch, key))
+                    && slot.is_none_or(|(best, _, _)| rank < best)
+                {
+                    *slot = Some((rank, key, value));
+                }
+            }
+        }
+        Self { matches, used: [false; FIELD_COUNT] }
+    }
+
+    pub(crate) fn value(&mut self, field: &str) -> Option<&'a GgufValue> {
+        self.entry(field).map(|(_, value)| value)
+    }
+
+    /// Get the key a field was found under along with its value
+    fn entry(&mut self, field: &str) -> Option<(&'a str, &'a GgufValue)> {
+        let index = keys::FIELD_ALIASES.iter().position(|(name, _)| *name == field)?;
+        let (_, key, value) = self.matches[index]?;
+        self.used[index] = true;
+        Some((key, value))
+    }
+
+    /// Add a field to the source keys without reading its value
+    fn record_source(&mut self, field: &str) {
+        if let Some(index) = keys::FIELD_ALIASES.iter().position(|(name, _)| *name == field) {
+            self.used[index] |= self.matches[index].is_some();
+        }
+    }
+
+    fn string(&mut self, field: &str) -> Option<String> {
+        self.value(field).and_then(|v| v.as_string().ok()).map(|s| s.to_string())
+    }
+
+    fn u32(&mut self, field: &str) -> Option<u32> {
+        self.value(field).and_then(|v| v.as_u32().ok())
+    }
+
+    pub(crate) fn u64(&mut self, field: &str) -> Option<u64> {
+        self.value(field).and_then(|v| v.as_u64().ok())
+    }
+
+    fn f32(&mut self, field: &str) -> Option<f32> {
+        self.value(field).and_then(|v| v.as_f32().ok())
+    }
+
+    fn bool(&mut self, field: &str) -> Option<bool> {
+        self.value(field).and_then(|v| v.as_bool().ok())
+    }
+
+    /// Metadata key each field read so far came from
+    fn into_source_keys(self) -> BTreeMap<String, String> {
+        keys::FIELD_ALIASES
+            .iter()
+            .zip(self.matches)
+            .zip(self.used)
+            .filter_map(|(((field, _), found), used)| {
+                let (_, key, _) = found.filter(|_| used)?;
+                Some((field.to_string(), key.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Collect the string fields of `<prefix>N.*` entries for `N < count` in one
+/// pass, in `BaseModel`/`Dataset` field order
+///
+/// Every entry takes at least one key, so a count above the number of
+/// metadata entries is corrupt and capped there rather than allocated.
+fn indexed_entries(metadata: &GgufMetadata, prefix: &str, count: u32) -> Vec<[Option<String>; 4]> {
+    const FIELDS: [&str; 4] = [keys::ENTRY_NAME, keys::ENTRY_ORGANIZATION, keys::ENTRY_REPO_URL, keys::ENTRY_VERSION];
+    let mut entries = vec![[None, None, None, None]; (count as usize).min(metadata.len())];
+    for (key, value) in metadata.iter() {
+        // Only canonical indices, so `01` doesn't stand in for `1`
+        if let Some((index, field)) = key.strip_prefix(prefix).and_then(|rest| rest.split_once('.'))
+            && (index == "0" || !index.starts_with('0'))
+            && index.bytes().all(|b| b.is_ascii_digit())
+            && let Ok(index) = index.parse::<usize>()
+            && let Some(entry) = entries.get_mut(index)
+            && let Some(slot) = FIELDS.iter().position(|f| *f == field)
+            && let GgufValue::String(s) = value
+        {
+            entry[slot] = Some(s.clone());
+        }
+    }
+    entries
+}
@@ -0,0 +1,103 @@
+/*!
+ * Streaming Metadata Reader
+ *
+ * `GgufMetadata` holds every entry at once. `GgufMetadataReader` yields
+ * entries one at a time as they are parsed, so a caller can move a token
+ * array straight into its own structures or stop once it has the keys it
+ * needs.
+ */
+
+use crate::error::Result;
+use crate::io::{Read, Seek};
+use crate::limits::ParseLimits;
+use crate::read::{read_string, read_u32};
+use crate::types::{GgufValue, GgufValueType};
+use alloc::string::String;
+
+/// Iterator over the metadata entries of a reader positioned just after the
+/// GGUF header
+///
+/// Each item is a whole entry, so after any successful item the reader sits
+/// at the start of the next entry and `into_inner` can hand it back to
+/// resume parsing. After an error the iterator is poisoned: it yields
+/// nothing more and the reader's position is unspecified.
+#[derive(Debug)]
+pub struct GgufMetadataReader<'r, R> {
+    reader: &'r mut R,
+    remaining: u64,
+    limits: ParseLimits,
+    poisoned: bool,
+}
+
+impl<'r, R: Read + Seek> GgufMetadataReader<'r, R> {
+    /// Read `kv_count` entries from `reader`
+    pub fn new(reader: &'r mut R, kv_count: u64) -> Self {
+        Self::with_limits(reader, kv_count, ParseLimits::UNLIMITED)
+    }
+
+    /// Read entries, failing with `LimitExceeded` on strings or arrays longer
+    /// than `limits` allow
+    pub fn with_limits(reader: &'r mut R, kv_count: u64, limits: ParseLimits) -> Self {
+        Self { reader, remaining: kv_count, limits, poisoned: false }
+    }
+
+    /// Number of entries not yet read
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Check whether an error stopped the iteration
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Get the reader back, positioned at the next unread entry unless poisoned
+    pub fn into_inner(self) -> &'r mut R {
+        self.reader
+    }
+
+    /// Read one entry's key and type, leaving the value to `read_value`,
+    /// and poison the iterator on error
+    pub(crate) fn step<T, F>(&mut self, read_value: F) -> Option<Result<(u64, String, T)>>
+    where
+        F: FnOnce(&mut R, &ParseLimits, &str, GgufValueType) -> Result<T>,
+    {
+        if self.poisoned || self.remaining == 0 {
+            return None;
+        }
+        let entry = self.read_entry(read_value).map_err(|e| e.at_stage("metadata"));
+        match entry {
+            Ok(_) => self.remaining -= 1,
+            Err(_) => self.poisoned = true,
+        }
+        Some(entry)
+    }
+
+    fn read_entry<T, F>(&mut self, read_value: F) -> Result<(u64, String, T)>
+    where
+        F: FnOnce(&mut R, &ParseLimits, &str, GgufValueType) -> Result<T>,
+    {
+        let entry_start = self.reader.stream_position()?;
+        let key = read_string(self.reader, &self.limits)?;
+        let value_type = GgufValueType::try_from(read_u32(self.reader)?)?;
+        let value = read_value(self.reader, &self.limits, &key, value_type)?;
+        Ok((entry_start, key, value))
+    }
+}
+
+impl<R: Read + Seek> Iterator for GgufMetadataReader<'_, R> {
+    type Item = Result<(String, GgufValue)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.step(|reader, limits, _, value_type| GgufValue::read_with_limits(reader, value_type, limits))?;
+        Some(entry.map(|(_, key, value)| (key, value)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.poisoned {
+            return (0, Some(0));
+        }
+        let remaining = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        (0, Some(remaining))
+    }
+}
@@ -0,0 +1,136 @@
+/*!
+ * Metadata Composition Statistics
+ *
+ * Metadata sections range from a few kilobytes to hundreds of megabytes,
+ * almost always because of a handful of tokenizer arrays. `MetadataStats`
+ * breaks the section down by value type, key namespace and largest entries.
+ */
+
+use crate::keys;
+use crate::metadata::GgufMetadata;
+use crate::types::{GgufValue, GgufValueType};
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Number of entries listed in `MetadataStats::largest_entries`
+pub const METADATA_STATS_LARGEST: usize = 10;
+
+/// Breakdown of a metadata section's entries and size
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataStats {
+    pub entry_count: usize,
+    /// Entries per value type, most common first
+    pub type_counts: Vec<(GgufValueType, usize)>,
+    /// Bytes the entries take in the file, including keys and type tags
+    pub total_bytes: u64,
+    /// Largest entries, largest first and in file order for equal sizes
+    pub largest_entries: Vec<EntrySummary>,
+    pub namespaces: NamespaceCounts,
+}
+
+/// Size of one metadata entry in `MetadataStats`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntrySummary {
+    pub key: String,
+    pub value_type: GgufValueType,
+    /// Array length, 1 for scalars and strings
+    pub element_count: u64,
+    /// Serialized size including the key and type tag
+    pub bytes: u64,
+}
+
+/// Number of keys in each namespace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NamespaceCounts {
+    /// `general.*`
+    pub general: usize,
+    /// `tokenizer.*`
+    pub tokenizer: usize,
+    /// Keys under the `general.architecture` prefix, e.g. `llama.*`
+    pub architecture: usize,
+    pub other: usize,
+}
+
+impl MetadataStats {
+    pub(crate) fn new(metadata: &GgufMetadata) -> Self {
+        let arch_prefix = metadata
+            .get_string_opt(keys::GENERAL_ARCHITECTURE)
+            .map(|arch| arch.to_string() + ".");
+
+        let mut types: BTreeMap<GgufValueType, usize> = BTreeMap::new();
+        let mut namespaces = NamespaceCounts::default();
+        let mut entries = Vec::with_capacity(metadata.len());
+        for (key, value) in metadata.iter() {
+            *types.entry(value.value_type()).or_default() += 1;
+
+            if key.starts_with("general.") {
+                namespaces.general += 1;
+            } else if key.starts_with("tokenizer.") {
+                namespaces.tokenizer += 1;
+            } else if arch_prefix.as_deref().is_some_and(|prefix| key.starts_with(prefix)) {
+                namespaces.architecture += 1;
+            } else {
+                namespaces.other += 1;
+            }
+
+            entries.push(EntrySummary {
+                key: key.to_string(),
+                value_type: value.value_type(),
+                element_count: match value {
+                    GgufValue::Array(items) => items.len() as u64,
+                    _ => 1,
+                },
+                // key length + key + type tag + payload
+                bytes: 8 + key.len() as u64 + 4 + value.serialized_size(),
+            });
+        }
+
+        let mut type_counts: Vec<_> = types.into_iter().collect();
+        type_counts.sort_by(|(a_type, a_count), (b_type, b_count)| b_count.cmp(a_count).then(a_type.cmp(b_type)));
+
+        let total_bytes = entries.iter().map(|entry| entry.bytes).sum();
+        // Stable sort keeps file order among equally sized entries
+        entries.sort_by_key(|entry| core::cmp::Reverse(entry.bytes));
+        entries.truncate(METADATA_STATS_LARGEST);
+
+        Self {
+            entry_count: metadata.len(),
+            type_counts,
+            total_bytes,
+            largest_entries: entries,
+            namespaces,
+        }
+    }
+}
+
+impl fmt::Display for MetadataStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Metadata: {} entries, {} bytes", self.entry_count, self.total_bytes)?;
+        let NamespaceCounts { general, tokenizer, architecture, other } = self.namespaces;
+        writeln!(f, "Namespaces: general {general}, tokenizer {tokenizer}, architecture {architecture}, other {other}")?;
+
+        writeln!(f, "Types:")?;
+        for (value_type, count) in &self.type_counts {
+            writeln!(f, "  {:<8} {count:>8}", format!("{value_type:?}"))?;
+        }
+
+        let width = self.largest_entries.iter().map(|entry| entry.key.len()).max().unwrap_or(0).max(3);
+        writeln!(f, "Largest entries:")?;
+        writeln!(f, "  {:<width$}  {:<8} {:>10} {:>12}", "KEY", "TYPE", "ELEMENTS", "BYTES")?;
+        for entry in &self.largest_entries {
+            writeln!(
+                f,
+                "  {:<width$}  {:<8} {:>10} {:>12}",
+                entry.key,
+                format!("{:?}", entry.value_type),
+                entry.element_count,
+                entry.bytes
+            )?;
+        }
+        Ok(())
+    }
+}
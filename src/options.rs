@@ -0,0 +1,50 @@
+/*!
+ * GGUF Parse Options
+ */
+
+use crate::limits::ParseLimits;
+use crate::warning::WarningKind;
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Maximum tensor rank accepted by default, matching ggml's `GGML_MAX_DIMS`
+pub const DEFAULT_MAX_TENSOR_DIMS: usize = 4;
+
+/// Options controlling how strictly a GGUF file is parsed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseOptions {
+    /// Maximum number of dimensions a tensor may declare
+    pub max_tensor_dims: usize,
+    /// Check that the file is exactly as long as its tensor data requires
+    /// when opening it by path, see `GgufFile::verify_complete`
+    pub verify_complete: bool,
+    /// When opening by path, look for the GGUF magic within this many leading
+    /// bytes and parse from there, for GGUFs wrapped in another container
+    pub scan_for_magic: Option<u64>,
+    /// When opening by path, fail with `TrailingData` if bytes follow the end
+    /// of the tensor data, without checking for truncation
+    pub reject_trailing_data: bool,
+    /// Warning kinds that fail the parse with `PromotedWarning` instead of
+    /// being collected into `GgufFile::warnings`
+    pub warnings_as_errors: Vec<WarningKind>,
+    /// Bounds on counts, lengths and bytes read, failing with `LimitExceeded`
+    pub limits: ParseLimits,
+    /// Decode metadata string values that aren't valid UTF-8 with
+    /// replacement characters and a `LossyUtf8` warning, instead of failing
+    /// with `InvalidUtf8`
+    pub lossy_utf8: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_tensor_dims: DEFAULT_MAX_TENSOR_DIMS,
+            verify_complete: false,
+            scan_for_magic: None,
+            reject_trailing_data: false,
+            warnings_as_errors: Vec::new(),
+            limits: ParseLimits::default(),
+            lossy_utf8: false,
+        }
+    }
+}
@@ -0,0 +1,25 @@
+/*!
+ * Output Files for Rewrites
+ *
+ * Rewriting copies tensor data from the input after the output is created,
+ * so an output naming the input would be truncated before it is read.
+ */
+
+use crate::error::{GgufError, Result};
+use alloc::string::ToString;
+use std::fs::File;
+use std::path::Path;
+
+/// Create `output` for a rewrite of `input`, refusing to overwrite the input
+///
+/// Paths are compared after resolving links and `..`, so two spellings of
+/// the same file are caught. An output that doesn't exist yet can't be the
+/// input.
+pub(crate) fn create_output(input: &Path, output: &Path) -> Result<File> {
+    if let Ok(resolved) = output.canonicalize()
+        && resolved == input.canonicalize()?
+    {
+        return Err(GgufError::OutputIsInput(output.display().to_string()));
+    }
+    Ok(File::create(output)?)
+}
@@ -0,0 +1,333 @@
+/*!
+ * Lenient GGUF Parsing with Partial Results
+ */
+
+use crate::error::GgufError;
+use crate::header::GgufHeader;
+use crate::limits::{self, ParseLimits};
+use crate::metadata::GgufMetadata;
+use crate::options::{ParseOptions, DEFAULT_MAX_TENSOR_DIMS};
+use crate::tensor::{QuantizationType, TensorInfo};
+use crate::read::{read_u32, read_u64};
+use crate::types::{GgufValue, GgufValueType};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Longest key or tensor name considered plausible while resynchronizing
+const MAX_PLAUSIBLE_NAME_LEN: u64 = 1024;
+
+/// Maximum number of bytes scanned when looking for the next intact entry
+const RESYNC_WINDOW: u64 = 16 * 1024 * 1024;
+
+/// Whatever could be recovered from a damaged GGUF file
+#[derive(Debug, Clone)]
+pub struct PartialGgufFile {
+    pub header: Option<GgufHeader>,
+    pub metadata: GgufMetadata,
+    pub tensors: Vec<TensorInfo>,
+    /// Keys whose values were malformed, held in `metadata` as placeholders:
+    /// the zero of the value's type, or an empty array when the type itself
+    /// is unknown
+    pub malformed_keys: Vec<String>,
+    pub header_complete: bool,
+    pub metadata_complete: bool,
+    pub tensors_complete: bool,
+}
+
+impl PartialGgufFile {
+    fn empty() -> Self {
+        Self {
+            header: None,
+            metadata: GgufMetadata::default(),
+            tensors: Vec::new(),
+            malformed_keys: Vec::new(),
+            header_complete: false,
+            metadata_complete: false,
+            tensors_complete: false,
+        }
+    }
+
+    /// Check whether every section was recovered in full
+    pub fn is_complete(&self) -> bool {
+        self.header_complete && self.metadata_complete && self.tensors_complete
+    }
+
+    /// Parse a GGUF file, recovering as much as framing allows
+    ///
+    /// Malformed values are skipped when their length can still be determined,
+    /// otherwise the parser scans ahead for the next plausible entry. Every
+    /// problem is reported as `GgufError::AtOffset`.
+    pub fn read<R: Read + Seek>(reader: &mut R) -> (Self, Vec<GgufError>) {
+        Self::read_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Parse a GGUF file leniently using custom parse options
+    ///
+    /// A value or tensor info over `options.limits` is malformed like any
+    /// other, counts over the limits end the parse after the header.
+    pub fn read_with_options<R: Read + Seek>(reader: &mut R, options: &ParseOptions) -> (Self, Vec<GgufError>) {
+        let mut partial = Self::empty();
+        let mut errors = Vec::new();
+
+        // A broken header leaves nothing to frame the rest of the file
+        let header_start = reader.stream_position().unwrap_or(0);
+        let header = match GgufHeader::read(reader) {
+            Ok(header) => header,
+            Err(e) => {
+                errors.push(at(header_start, e));
+                return (partial, errors);
+            }
+        };
+        partial.header_complete = true;
+        partial.header = Some(header.clone());
+        let limits = &options.limits;
+        if let Err(e) = limits::check("max_metadata_kv", header.metadata_kv_count, limits.max_metadata_kv)
+            .and_then(|_| limits::check("max_tensors", header.tensor_count, limits.max_tensors))
+        {
+            errors.push(at(header_start, e));
+            return (partial, errors);
+        }
+
+        // Tensor infos can only be located if the metadata framing held up
+        let metadata_framed = read_metadata(reader, &header, limits, &mut partial, &mut errors);
+        partial.metadata_complete = metadata_framed && partial.malformed_keys.is_empty();
+        if !metadata_framed {
+            return (partial, errors);
+        }
+
+        let tensors_framed = read_tensors(reader, header.tensor_count, options, &mut partial, &mut errors);
+        partial.tensors_complete = tensors_framed && partial.tensors.len() as u64 == header.tensor_count;
+        (partial, errors)
+    }
+}
+
+/// Read metadata entries, returns whether framing stayed intact throughout
+fn read_metadata<R: Read + Seek>(
+    reader: &mut R,
+    header: &GgufHeader,
+    limits: &ParseLimits,
+    partial: &mut PartialGgufFile,
+    errors: &mut Vec<GgufError>,
+) -> bool {
+    for index in 0..header.metadata_kv_count {
+        let entry_start = match reader.stream_position() {
+            Ok(position) => position,
+            Err(e) => {
+                errors.push(at(0, e.into()));
+                return false;
+            }
+        };
+
+        // Key framing is required to continue
+        let key = match read_lossy_string(reader, limits) {
+            Ok((key, None)) => key,
+            Ok((key, Some(e))) => {
+                errors.push(at(entry_start, e));
+                key
+            }
+            Err(e) => {
+                errors.push(at(entry_start, e));
+                return false;
+            }
+        };
+
+        let type_start = match reader.stream_position() {
+            Ok(position) => position,
+            Err(e) => {
+                errors.push(at(entry_start, e.into()));
+                return false;
+            }
+        };
+        let value_type = match read_u32(reader) {
+            Ok(raw) => GgufValueType::try_from(raw),
+            Err(e) => {
+                errors.push(at(type_start, e));
+                return false;
+            }
+        };
+
+        let value_start = type_start + 4;
+        let value = match value_type {
+            Ok(value_type) => GgufValue::read_with_limits(reader, value_type, limits).map_err(|e| {
+                // Retry as a skip so the next entry stays reachable
+                let skipped = reader.seek(SeekFrom::Start(value_start)).is_ok()
+                    && GgufValue::skip(reader, value_type).is_ok();
+                (value_start, e, skipped, Some(value_type))
+            }),
+            Err(e) => Err((type_start, e, false, None)),
+        };
+
+        match value {
+            Ok(value) => {
+                partial.metadata.insert(key, value);
+            }
+            Err((offset, e, skipped, value_type)) => {
+                errors.push(at(offset, e));
+                partial.metadata.insert(key.clone(), placeholder(value_type));
+                partial.malformed_keys.push(key);
+
+                if !skipped {
+                    let found = if index + 1 < header.metadata_kv_count {
+                        resync(reader, value_start, plausible_kv)
+                    } else {
+                        resync(reader, value_start, plausible_tensor_info)
+                    };
+                    if !found {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    true
+}
+
+/// Read tensor infos, returns whether framing stayed intact throughout
+fn read_tensors<R: Read + Seek>(
+    reader: &mut R,
+    tensor_count: u64,
+    options: &ParseOptions,
+    partial: &mut PartialGgufFile,
+    errors: &mut Vec<GgufError>,
+) -> bool {
+    for _ in 0..tensor_count {
+        let entry_start = match reader.stream_position() {
+            Ok(position) => position,
+            Err(e) => {
+                errors.push(at(0, e.into()));
+                return false;
+            }
+        };
+
+        match TensorInfo::read_with_options(reader, options) {
+            Ok(tensor) => partial.tensors.push(tensor),
+            Err(e) => {
+                errors.push(at(entry_start, e));
+                if reader.seek(SeekFrom::Start(entry_start)).is_err() || !skip_tensor_info(reader) {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+fn at(offset: u64, source: GgufError) -> GgufError {
+    GgufError::AtOffset {
+        offset,
+        source: Box::new(source),
+    }
+}
+
+/// Stand-in for a malformed value of the given type
+fn placeholder(value_type: Option<GgufValueType>) -> GgufValue {
+    match value_type {
+        Some(GgufValueType::Uint8) => GgufValue::Uint8(0),
+        Some(GgufValueType::Int8) => GgufValue::Int8(0),
+        Some(GgufValueType::Uint16) => GgufValue::Uint16(0),
+        Some(GgufValueType::Int16) => GgufValue::Int16(0),
+        Some(GgufValueType::Uint32) => GgufValue::Uint32(0),
+        Some(GgufValueType::Int32) => GgufValue::Int32(0),
+        Some(GgufValueType::Float32) => GgufValue::Float32(0.0),
+        Some(GgufValueType::Bool) => GgufValue::Bool(false),
+        Some(GgufValueType::String) => GgufValue::String(String::new()),
+        Some(GgufValueType::Uint64) => GgufValue::Uint64(0),
+        Some(GgufValueType::Int64) => GgufValue::Int64(0),
+        Some(GgufValueType::Float64) => GgufValue::Float64(0.0),
+        Some(GgufValueType::Array) | None => GgufValue::Array(Vec::new()),
+    }
+}
+
+/// Read a length-prefixed string, replacing invalid UTF-8 rather than failing
+fn read_lossy_string<R: Read>(reader: &mut R, limits: &ParseLimits) -> Result<(String, Option<GgufError>), GgufError> {
+    let length = read_u64(reader)?;
+    limits::check("max_string_len", length, limits.max_string_len)?;
+
+    // Don't trust the length for the allocation
+    let mut buf = Vec::new();
+    reader.take(length).read_to_end(&mut buf)?;
+    if (buf.len() as u64) < length {
+        return Err(GgufError::UnexpectedEof);
+    }
+
+    match String::from_utf8(buf) {
+        Ok(string) => Ok((string, None)),
+        Err(e) => {
+            let lossy = String::from_utf8_lossy(e.as_bytes()).into_owned();
+            Ok((lossy, Some(e.into())))
+        }
+    }
+}
+
+/// Skip a tensor info entry without validating its quantization type,
+/// returns whether the entry looked framed well enough to skip
+fn skip_tensor_info<R: Read + Seek>(reader: &mut R) -> bool {
+    let Ok(name_len) = read_u64(reader) else {
+        return false;
+    };
+    if name_len > MAX_PLAUSIBLE_NAME_LEN || reader.seek(SeekFrom::Current(name_len as i64)).is_err() {
+        return false;
+    }
+    let Ok(n_dimensions) = read_u32(reader) else {
+        return false;
+    };
+    if n_dimensions as usize > DEFAULT_MAX_TENSOR_DIMS {
+        return false;
+    }
+    // dimensions + quantization type + offset
+    reader.seek(SeekFrom::Current(8 * n_dimensions as i64 + 4 + 8)).is_ok()
+}
+
+/// Scan forward from `start` for an entry accepted by `plausible`, leaving the reader there
+fn resync<R: Read + Seek>(reader: &mut R, start: u64, plausible: fn(&[u8]) -> bool) -> bool {
+    if reader.seek(SeekFrom::Start(start)).is_err() {
+        return false;
+    }
+    let mut window = Vec::new();
+    if reader.take(RESYNC_WINDOW).read_to_end(&mut window).is_err() {
+        return false;
+    }
+
+    // The malformed value occupies at least one byte
+    match (1..window.len()).find(|&i| plausible(&window[i..])) {
+        Some(i) => reader.seek(SeekFrom::Start(start + i as u64)).is_ok(),
+        None => false,
+    }
+}
+
+fn le_u32(bytes: &[u8]) -> Option<u32> {
+    Some(u32::from_le_bytes(bytes.get(..4)?.try_into().ok()?))
+}
+
+/// Split off a plausible key or tensor name, returning the bytes after it
+fn plausible_name(bytes: &[u8]) -> Option<&[u8]> {
+    let length = u64::from_le_bytes(bytes.get(..8)?.try_into().ok()?);
+    if length == 0 || length > MAX_PLAUSIBLE_NAME_LEN {
+        return None;
+    }
+    let name = bytes.get(8..8 + length as usize)?;
+    if !name.iter().all(|b| b.is_ascii_graphic()) {
+        return None;
+    }
+    Some(&bytes[8 + length as usize..])
+}
+
+fn plausible_kv(bytes: &[u8]) -> bool {
+    plausible_name(bytes)
+        .and_then(le_u32)
+        .is_some_and(|raw| GgufValueType::try_from(raw).is_ok())
+}
+
+fn plausible_tensor_info(bytes: &[u8]) -> bool {
+    let Some(rest) = plausible_name(bytes) else {
+        return false;
+    };
+    let Some(n_dimensions) = le_u32(rest).filter(|&n| n as usize <= DEFAULT_MAX_TENSOR_DIMS) else {
+        return false;
+    };
+    let type_start = 4 + 8 * n_dimensions as usize;
+    rest.get(type_start..)
+        .and_then(le_u32)
+        .is_some_and(|raw| QuantizationType::try_from(raw).is_ok())
+}
@@ -0,0 +1,49 @@
+/*!
+ * Parsing from a Growing Prefix
+ *
+ * Compressed streams and remote files can't be seeked cheaply, but
+ * everything `GgufFile` holds sits before the tensor data. Both readers
+ * fetch a prefix into memory and parse that, doubling the prefix until the
+ * metadata and tensor infos fit.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::options::ParseOptions;
+use crate::GgufFile;
+use std::io::{Cursor, ErrorKind};
+
+/// Bytes fetched first, enough for most models' metadata
+pub(crate) const INITIAL_PREFIX_BYTES: u64 = 2 << 20;
+
+/// Parse a GGUF file from a prefix that `extend` grows on demand
+///
+/// `extend` appends bytes until the buffer holds the requested length or
+/// the source ends. A parse that runs off the end of the prefix doubles
+/// the length, up to `max_len`, and tries again.
+pub(crate) fn parse_growing_prefix<F>(initial_len: u64, max_len: u64, options: &ParseOptions, mut extend: F) -> Result<GgufFile>
+where
+    F: FnMut(&mut Vec<u8>, u64) -> Result<()>,
+{
+    let mut prefix = Vec::new();
+    let mut len = initial_len.max(1);
+    loop {
+        extend(&mut prefix, len)?;
+        let exhausted = (prefix.len() as u64) < len;
+        match GgufFile::from_reader_with_options(&mut Cursor::new(&prefix), options) {
+            Err(error) if !exhausted && len < max_len && needs_more_data(&error) => {
+                len = len.saturating_mul(2).min(max_len);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Check whether a parse failed because the prefix ended too early
+fn needs_more_data(error: &GgufError) -> bool {
+    match error {
+        GgufError::Io(source) | GgufError::IoAt { source, .. } => source.kind() == ErrorKind::UnexpectedEof,
+        GgufError::UnexpectedEof | GgufError::UnexpectedEofAt { .. } | GgufError::ArrayTooLarge { .. } => true,
+        GgufError::AtOffset { source, .. } => needs_more_data(source),
+        _ => false,
+    }
+}
@@ -0,0 +1,90 @@
+/*!
+ * Quantization Type Registry
+ *
+ * One table describing every quantization type this crate knows, which all
+ * `QuantizationType` methods read from.
+ */
+
+use crate::tensor::{QuantFamily, QuantizationType};
+
+/// Static description of a quantization type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct QuantInfo {
+    pub quantization_type: QuantizationType,
+    /// Numeric id stored in tensor infos
+    pub id: u32,
+    /// Canonical llama.cpp label, e.g. `"Q4_K"`
+    pub name: &'static str,
+    /// Number of weights per block
+    pub block_size: u64,
+    /// Bytes occupied by one block
+    pub type_size_bytes: u64,
+    /// Average storage cost, `type_size_bytes * 8 / block_size`
+    pub bits_per_weight: f32,
+    pub family: QuantFamily,
+    /// Neither a plain float nor a plain integer type
+    pub is_quantized: bool,
+    /// Importance-matrix (IQ*) type
+    pub is_imatrix: bool,
+    pub description: &'static str,
+}
+
+impl QuantInfo {
+    const fn new(
+        quantization_type: QuantizationType,
+        id: u32,
+        name: &'static str,
+        block_size: u64,
+        type_size_bytes: u64,
+        family: QuantFamily,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            quantization_type,
+            id,
+            name,
+            block_size,
+            type_size_bytes,
+            bits_per_weight: type_size_bytes as f32 * 8.0 / block_size as f32,
+            family,
+            is_quantized: !matches!(family, QuantFamily::Float | QuantFamily::Integer),
+            is_imatrix: matches!(family, QuantFamily::IQuant),
+            description,
+        }
+    }
+}
+
+/// Every known quantization type, ordered by id
+pub(crate) static QUANT_INFO: &[QuantInfo] = &[
+    QuantInfo::new(QuantizationType::F32, 0, "F32", 1, 4, QuantFamily::Float, "32-bit float"),
+    QuantInfo::new(QuantizationType::F16, 1, "F16", 1, 2, QuantFamily::Float, "16-bit float"),
+    QuantInfo::new(QuantizationType::Q4_0, 2, "Q4_0", 32, 18, QuantFamily::Legacy, "4-bit quantized (symmetric)"),
+    QuantInfo::new(QuantizationType::Q4_1, 3, "Q4_1", 32, 20, QuantFamily::Legacy, "4-bit quantized (asymmetric)"),
+    QuantInfo::new(QuantizationType::Q5_0, 6, "Q5_0", 32, 22, QuantFamily::Legacy, "5-bit quantized (symmetric)"),
+    QuantInfo::new(QuantizationType::Q5_1, 7, "Q5_1", 32, 24, QuantFamily::Legacy, "5-bit quantized (asymmetric)"),
+    QuantInfo::new(QuantizationType::Q8_0, 8, "Q8_0", 32, 34, QuantFamily::Legacy, "8-bit quantized (symmetric)"),
+    QuantInfo::new(QuantizationType::Q8_1, 9, "Q8_1", 32, 36, QuantFamily::Legacy, "8-bit quantized (asymmetric)"),
+    QuantInfo::new(QuantizationType::Q2_K, 10, "Q2_K", 256, 84, QuantFamily::KQuant, "2-bit K-quantized"),
+    QuantInfo::new(QuantizationType::Q3_K, 11, "Q3_K", 256, 110, QuantFamily::KQuant, "3-bit K-quantized"),
+    QuantInfo::new(QuantizationType::Q4_K, 12, "Q4_K", 256, 144, QuantFamily::KQuant, "4-bit K-quantized"),
+    QuantInfo::new(QuantizationType::Q5_K, 13, "Q5_K", 256, 176, QuantFamily::KQuant, "5-bit K-quantized"),
+    QuantInfo::new(QuantizationType::Q6_K, 14, "Q6_K", 256, 210, QuantFamily::KQuant, "6-bit K-quantized"),
+    QuantInfo::new(QuantizationType::Q8_K, 15, "Q8_K", 256, 292, QuantFamily::KQuant, "8-bit K-quantized"),
+    QuantInfo::new(QuantizationType::IQ2_XXS, 16, "IQ2_XXS", 256, 66, QuantFamily::IQuant, "2-bit IMatrix (extra small)"),
+    QuantInfo::new(QuantizationType::IQ2_XS, 17, "IQ2_XS", 256, 74, QuantFamily::IQuant, "2-bit IMatrix (small)"),
+    QuantInfo::new(QuantizationType::IQ3_XXS, 18, "IQ3_XXS", 256, 98, QuantFamily::IQuant, "3-bit IMatrix (extra small)"),
+    QuantInfo::new(QuantizationType::IQ1_S, 19, "IQ1_S", 256, 50, QuantFamily::IQuant, "1-bit IMatrix (small)"),
+    QuantInfo::new(QuantizationType::IQ4_NL, 20, "IQ4_NL", 32, 18, QuantFamily::IQuant, "4-bit IMatrix (non-linear)"),
+    QuantInfo::new(QuantizationType::IQ3_S, 21, "IQ3_S", 256, 110, QuantFamily::IQuant, "3-bit IMatrix (small)"),
+    QuantInfo::new(QuantizationType::IQ2_S, 22, "IQ2_S", 256, 82, QuantFamily::IQuant, "2-bit IMatrix (small)"),
+    QuantInfo::new(QuantizationType::IQ4_XS, 23, "IQ4_XS", 256, 136, QuantFamily::IQuant, "4-bit IMatrix (extra small)"),
+    QuantInfo::new(QuantizationType::I8, 24, "I8", 1, 1, QuantFamily::Integer, "8-bit integer"),
+    QuantInfo::new(QuantizationType::I16, 25, "I16", 1, 2, QuantFamily::Integer, "16-bit integer"),
+    QuantInfo::new(QuantizationType::I32, 26, "I32", 1, 4, QuantFamily::Integer, "32-bit integer"),
+    QuantInfo::new(QuantizationType::I64, 27, "I64", 1, 8, QuantFamily::Integer, "64-bit integer"),
+    QuantInfo::new(QuantizationType::F64, 28, "F64", 1, 8, QuantFamily::Float, "64-bit float"),
+    QuantInfo::new(QuantizationType::IQ1_M, 29, "IQ1_M", 256, 56, QuantFamily::IQuant, "1-bit IMatrix (medium)"),
+    QuantInfo::new(QuantizationType::BF16, 30, "BF16", 1, 2, QuantFamily::Float, "16-bit brain float"),
+    QuantInfo::new(QuantizationType::TQ1_0, 34, "TQ1_0", 256, 54, QuantFamily::Ternary, "1.69-bit ternary"),
+    QuantInfo::new(QuantizationType::TQ2_0, 35, "TQ2_0", 256, 66, QuantFamily::Ternary, "2.06-bit ternary"),
+];
@@ -0,0 +1,91 @@
+/*!
+ * Little-Endian Primitive Readers
+ *
+ * Every integer and float in a GGUF file is little-endian, and strings are
+ * a u64 length followed by UTF-8 bytes. The section readers decode fields
+ * through these helpers rather than repeating the buffer handling.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::io::Read;
+use crate::limits::{self, ParseLimits};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Read exactly `N` bytes
+pub(crate) fn read_bytes<R: Read, const N: usize>(reader: &mut R) -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+pub(crate) fn read_u8<R: Read>(reader: &mut R) -> Result<u8> {
+    Ok(u8::from_le_bytes(read_bytes(reader)?))
+}
+
+pub(crate) fn read_u16<R: Read>(reader: &mut R) -> Result<u16> {
+    Ok(u16::from_le_bytes(read_bytes(reader)?))
+}
+
+pub(crate) fn read_u32<R: Read>(reader: &mut R) -> Result<u32> {
+    Ok(u32::from_le_bytes(read_bytes(reader)?))
+}
+
+pub(crate) fn read_u64<R: Read>(reader: &mut R) -> Result<u64> {
+    Ok(u64::from_le_bytes(read_bytes(reader)?))
+}
+
+pub(crate) fn read_f32<R: Read>(reader: &mut R) -> Result<f32> {
+    Ok(f32::from_le_bytes(read_bytes(reader)?))
+}
+
+pub(crate) fn read_f64<R: Read>(reader: &mut R) -> Result<f64> {
+    Ok(f64::from_le_bytes(read_bytes(reader)?))
+}
+
+/// Bytes a string buffer grows by per read, so a corrupt length can't
+/// allocate much more than the input actually holds
+const STRING_READ_CHUNK: usize = 64 * 1024;
+
+/// Elements reserved up front for a count read from the input
+const MAX_PREALLOCATION: usize = 1 << 16;
+
+/// Capacity to reserve for `count` elements read from the input
+///
+/// Counts are checked against the input size before they get here, but the
+/// elements may be many times larger in memory than on disk, and a count
+/// can overflow `usize` on 32-bit targets. Longer collections grow as they
+/// are read instead.
+pub(crate) fn preallocation(count: u64) -> usize {
+    usize::try_from(count).map_or(MAX_PREALLOCATION, |count| count.min(MAX_PREALLOCATION))
+}
+
+/// Read a u64 length-prefixed UTF-8 string
+///
+/// Fails before allocating if the string is longer than `max_string_len`.
+pub(crate) fn read_string<R: Read>(reader: &mut R, limits: &ParseLimits) -> Result<String> {
+    Ok(String::from_utf8(read_string_bytes(reader, limits)?)?)
+}
+
+/// Read a string like `read_string`, replacing invalid UTF-8 instead of
+/// failing and reporting whether anything was replaced
+pub(crate) fn read_string_lossy<R: Read>(reader: &mut R, limits: &ParseLimits) -> Result<(String, bool)> {
+    match String::from_utf8(read_string_bytes(reader, limits)?) {
+        Ok(string) => Ok((string, false)),
+        Err(error) => Ok((String::from_utf8_lossy(error.as_bytes()).into_owned(), true)),
+    }
+}
+
+fn read_string_bytes<R: Read>(reader: &mut R, limits: &ParseLimits) -> Result<Vec<u8>> {
+    let length = read_u64(reader)?;
+    limits::check("max_string_len", length, limits.max_string_len)?;
+    // Longer than the address space means longer than any input
+    let length = usize::try_from(length).map_err(|_| GgufError::UnexpectedEof)?;
+    let mut buf = Vec::with_capacity(length.min(STRING_READ_CHUNK));
+    while buf.len() < length {
+        let start = buf.len();
+        buf.resize(start + (length - start).min(STRING_READ_CHUNK), 0);
+        reader.read_exact(&mut buf[start..])?;
+    }
+    Ok(buf)
+}
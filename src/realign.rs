@@ -0,0 +1,86 @@
+/*!
+ * Tensor Data Realignment
+ *
+ * Converters disagree on `general.alignment`: 32 is the ggml default while
+ * 4096 suits direct I/O. Realigning rewrites the file with new padding
+ * between tensors, which also repairs offsets that violate the alignment
+ * the file declares.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::header::GgufHeader;
+use crate::keys;
+use crate::options::ParseOptions;
+use crate::output::create_output;
+use crate::GgufFile;
+use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub(crate) fn rewrite_with_alignment(input: &Path, output: &Path, alignment: u64) -> Result<()> {
+    if !alignment.is_power_of_two() || alignment > u32::MAX as u64 {
+        return Err(GgufError::InvalidAlignment(alignment));
+    }
+
+    let gguf_file = GgufFile::from_file(input)?;
+    let mut metadata = gguf_file.metadata.clone();
+    metadata.insert(keys::GENERAL_ALIGNMENT, alignment as u32);
+    let header = GgufHeader {
+        metadata_kv_count: metadata.len() as u64,
+        ..gguf_file.header.clone()
+    };
+
+    // Lay tensors out in their existing disk order, each at the next aligned offset
+    let mut order: Vec<usize> = (0..gguf_file.tensors.len()).collect();
+    order.sort_by_key(|&i| gguf_file.tensors[i].offset);
+    let overflow = || GgufError::SizeOverflow("tensor data".to_string());
+    let mut tensors = gguf_file.tensors.clone();
+    let mut sizes = vec![0; tensors.len()];
+    let mut data_len: u64 = 0;
+    for &i in &order {
+        sizes[i] = tensors[i].size_bytes()?;
+        tensors[i].offset = data_len;
+        data_len = data_len
+            .checked_add(sizes[i])
+            .and_then(|end| end.div_ceil(alignment).checked_mul(alignment))
+            .ok_or_else(overflow)?;
+    }
+
+    let mut reader = BufReader::new(File::open(input)?);
+    let mut writer = BufWriter::new(create_output(input, output)?);
+    header.write(&mut writer)?;
+    metadata.write(&mut writer)?;
+    for tensor in &tensors {
+        tensor.write(&mut writer)?;
+    }
+    let info_end = writer.stream_position()?;
+    pad(&mut writer, info_end.div_ceil(alignment) * alignment - info_end)?;
+
+    let data_start = gguf_file.gguf_offset() + gguf_file.data_offset();
+    let mut written = 0;
+    for &i in &order {
+        pad(&mut writer, tensors[i].offset - written)?;
+        let source = data_start.checked_add(gguf_file.tensors[i].offset).ok_or_else(overflow)?;
+        reader.seek(SeekFrom::Start(source))?;
+        let copied = std::io::copy(&mut (&mut reader).take(sizes[i]), &mut writer)?;
+        if copied != sizes[i] {
+            return Err(GgufError::UnexpectedEof);
+        }
+        written = tensors[i].offset + sizes[i];
+    }
+    pad(&mut writer, data_len - written)?;
+    writer.flush()?;
+    drop(writer);
+
+    let options = ParseOptions { verify_complete: true, ..Default::default() };
+    GgufFile::from_file_with_options(output, &options)?;
+    Ok(())
+}
+
+fn pad<W: Write>(writer: &mut W, len: u64) -> Result<()> {
+    std::io::copy(&mut std::io::repeat(0).take(len), writer)?;
+    Ok(())
+}
@@ -0,0 +1,223 @@
+/*!
+ * Tensor Renaming Between Naming Conventions
+ *
+ * Converters and checkpoints disagree on tensor names, e.g. the original
+ * `layers.N.attention.wq.weight` or Hugging Face's
+ * `model.layers.N.self_attn.q_proj.weight` versus llama.cpp's
+ * `blk.N.attn_q.weight`. `TensorRenamer` maps between them, in memory or
+ * while rewriting a file.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::tensor::TensorInfo;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use crate::header::GgufHeader;
+#[cfg(feature = "std")]
+use crate::options::ParseOptions;
+#[cfg(feature = "std")]
+use crate::output::create_output;
+#[cfg(feature = "std")]
+use crate::GgufFile;
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// Placeholder for a layer number in `RenameRule::Pattern`
+pub const LAYER_PLACEHOLDER: &str = "{N}";
+
+/// Original Meta llama checkpoint names, without the `.weight`/`.bias` suffix
+const LEGACY_LLAMA_NAMES: &[(&str, &str)] = &[
+    ("tok_embeddings", "token_embd"),
+    ("norm", "output_norm"),
+    ("output", "output"),
+    ("rope.freqs", "rope_freqs"),
+    ("layers.{N}.attention.wq", "blk.{N}.attn_q"),
+    ("layers.{N}.attention.wk", "blk.{N}.attn_k"),
+    ("layers.{N}.attention.wv", "blk.{N}.attn_v"),
+    ("layers.{N}.attention.wo", "blk.{N}.attn_output"),
+    ("layers.{N}.attention_norm", "blk.{N}.attn_norm"),
+    ("layers.{N}.feed_forward.w1", "blk.{N}.ffn_gate"),
+    ("layers.{N}.feed_forward.w2", "blk.{N}.ffn_down"),
+    ("layers.{N}.feed_forward.w3", "blk.{N}.ffn_up"),
+    ("layers.{N}.ffn_norm", "blk.{N}.ffn_norm"),
+];
+
+/// Hugging Face transformers names for llama-style models
+const HF_LLAMA_NAMES: &[(&str, &str)] = &[
+    ("model.embed_tokens", "token_embd"),
+    ("model.norm", "output_norm"),
+    ("lm_head", "output"),
+    ("model.layers.{N}.self_attn.q_proj", "blk.{N}.attn_q"),
+    ("model.layers.{N}.self_attn.k_proj", "blk.{N}.attn_k"),
+    ("model.layers.{N}.self_attn.v_proj", "blk.{N}.attn_v"),
+    ("model.layers.{N}.self_attn.o_proj", "blk.{N}.attn_output"),
+    ("model.layers.{N}.input_layernorm", "blk.{N}.attn_norm"),
+    ("model.layers.{N}.post_attention_layernorm", "blk.{N}.ffn_norm"),
+    ("model.layers.{N}.mlp.gate_proj", "blk.{N}.ffn_gate"),
+    ("model.layers.{N}.mlp.up_proj", "blk.{N}.ffn_up"),
+    ("model.layers.{N}.mlp.down_proj", "blk.{N}.ffn_down"),
+];
+
+/// One way of renaming a tensor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameRule {
+    /// Replace a leading prefix, keeping the rest of the name
+    Prefix { from: String, to: String },
+    /// Match the whole name, with `{N}` matching a layer number that is
+    /// carried over to `to`
+    Pattern { from: String, to: String },
+}
+
+impl RenameRule {
+    /// Get the new name if this rule matches
+    pub fn apply(&self, name: &str) -> Option<String> {
+        match self {
+            RenameRule::Prefix { from, to } => name.strip_prefix(from.as_str()).map(|rest| format!("{to}{rest}")),
+            RenameRule::Pattern { from, to } => match from.split_once(LAYER_PLACEHOLDER) {
+                Some((head, tail)) => {
+                    let layer = name.strip_prefix(head)?.strip_suffix(tail)?;
+                    (!layer.is_empty() && layer.bytes().all(|b| b.is_ascii_digit()))
+                        .then(|| to.replace(LAYER_PLACEHOLDER, layer))
+                }
+                None => (name == from).then(|| to.clone()),
+            },
+        }
+    }
+}
+
+/// Ordered list of rename rules, the first matching rule wins
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TensorRenamer {
+    rules: Vec<RenameRule>,
+}
+
+impl TensorRenamer {
+    /// Create a renamer without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Original Meta llama checkpoint names to llama.cpp names
+    pub fn legacy_llama() -> Self {
+        Self::new().with_names(LEGACY_LLAMA_NAMES)
+    }
+
+    /// Hugging Face llama-style names to llama.cpp names
+    pub fn hf_llama() -> Self {
+        Self::new().with_names(HF_LLAMA_NAMES)
+    }
+
+    /// Add a prefix rule
+    pub fn prefix(mut self, from: &str, to: &str) -> Self {
+        self.rules.push(RenameRule::Prefix { from: from.to_string(), to: to.to_string() });
+        self
+    }
+
+    /// Add a whole-name rule, `{N}` matches a layer number
+    pub fn pattern(mut self, from: &str, to: &str) -> Self {
+        self.rules.push(RenameRule::Pattern { from: from.to_string(), to: to.to_string() });
+        self
+    }
+
+    /// Add a `.weight` and a `.bias` pattern for each pair of name stems
+    fn with_names(mut self, names: &[(&str, &str)]) -> Self {
+        for (from, to) in names {
+            for suffix in [".weight", ".bias"] {
+                self = self.pattern(&format!("{from}{suffix}"), &format!("{to}{suffix}"));
+            }
+        }
+        self
+    }
+
+    /// Get the rules in the order they are tried
+    pub fn rules(&self) -> &[RenameRule] {
+        &self.rules
+    }
+
+    /// Get the new name for a tensor, `None` when no rule matches
+    pub fn rename(&self, name: &str) -> Option<String> {
+        self.rules.iter().find_map(|rule| rule.apply(name))
+    }
+
+    /// Rename tensors in place, returning how many names changed
+    ///
+    /// Nothing is renamed if two tensors would end up with the same name,
+    /// the error names both source tensors.
+    pub fn apply(&self, tensors: &mut [TensorInfo]) -> Result<usize> {
+        let renamed = self.renamed_names(tensors)?;
+        let mut changed = 0;
+        for (tensor, name) in tensors.iter_mut().zip(renamed) {
+            if tensor.name != name {
+                tensor.name = name;
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
+    fn renamed_names(&self, tensors: &[TensorInfo]) -> Result<Vec<String>> {
+        let names: Vec<String> = tensors
+            .iter()
+            .map(|t| self.rename(&t.name).unwrap_or_else(|| t.name.clone()))
+            .collect();
+
+        let mut sources: BTreeMap<&str, &str> = BTreeMap::new();
+        for (tensor, name) in tensors.iter().zip(&names) {
+            if let Some(first) = sources.insert(name, &tensor.name) {
+                return Err(GgufError::TensorNameCollision {
+                    name: name.clone(),
+                    first: first.to_string(),
+                    second: tensor.name.clone(),
+                });
+            }
+        }
+        Ok(names)
+    }
+
+    /// Copy a GGUF file with renamed tensors, tensor data is copied untouched
+    ///
+    /// Tensor offsets are relative to the data section, so only the tensor
+    /// info section and the padding after it change. Duplicate metadata keys
+    /// are written once, and the output is checked with `verify_complete`
+    /// before returning.
+    #[cfg(feature = "std")]
+    pub fn rewrite_file<P: AsRef<Path>, Q: AsRef<Path>>(&self, input: P, output: Q) -> Result<()> {
+        let gguf_file = GgufFile::from_file(&input)?;
+        let mut tensors = gguf_file.tensors.clone();
+        self.apply(&mut tensors)?;
+
+        let input = input.as_ref();
+        let mut reader = BufReader::new(File::open(input)?);
+        let output = output.as_ref();
+        let mut writer = BufWriter::new(create_output(input, output)?);
+        let header = GgufHeader {
+            metadata_kv_count: gguf_file.metadata.len() as u64,
+            ..gguf_file.header.clone()
+        };
+        header.write(&mut writer)?;
+        gguf_file.metadata.write(&mut writer)?;
+        for tensor in &tensors {
+            tensor.write(&mut writer)?;
+        }
+        let info_end = writer.stream_position()?;
+        let alignment = gguf_file.alignment();
+        let padding = info_end.div_ceil(alignment) * alignment - info_end;
+        std::io::copy(&mut std::io::repeat(0).take(padding), &mut writer)?;
+
+        reader.seek(SeekFrom::Start(gguf_file.gguf_offset() + gguf_file.data_offset()))?;
+        std::io::copy(&mut reader, &mut writer)?;
+        writer.flush()?;
+        drop(writer);
+
+        let options = ParseOptions { verify_complete: true, ..Default::default() };
+        GgufFile::from_file_with_options(output, &options)?;
+        Ok(())
+    }
+}
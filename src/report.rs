@@ -0,0 +1,196 @@
+/*!
+ * Markdown Model Card
+ *
+ * Registries publish a README-style page per model. `GgufFile::model_card`
+ * renders one from the metadata and tensor infos, so the page always
+ * matches the file it describes.
+ */
+
+use crate::keys;
+use crate::GgufFile;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+/// Placeholder title for files without `general.name`
+const UNTITLED: &str = "Untitled model";
+
+pub(crate) fn model_card(gguf_file: &GgufFile) -> String {
+    let metadata = &gguf_file.metadata;
+    let config = gguf_file.model_config().ok();
+    let mut card = String::new();
+
+    // Writing to a String can't fail, so the results are ignored throughout
+    let _ = writeln!(card, "# {}\n", gguf_file.name().unwrap_or(UNTITLED));
+
+    let mut properties = Vec::new();
+    if let Ok(architecture) = gguf_file.architecture() {
+        properties.push(("Architecture", architecture.to_string()));
+    }
+    let parameters = metadata
+        .get_string_opt(keys::GENERAL_SIZE_LABEL)
+        .map(str::to_string)
+        .or_else(|| config.as_ref().map(|config| config.derived_size_label()));
+    if let Some(parameters) = parameters {
+        properties.push(("Parameters", parameters));
+    }
+    let quantization = match gguf_file.quant_label() {
+        Some(file_type) => Some(file_type.to_string()),
+        None => gguf_file.dominant_quantization_type().map(|t| t.to_string()),
+    };
+    if let Some(quantization) = quantization {
+        properties.push(("Quantization", quantization));
+    }
+    if let Some(context_length) = gguf_file.context_length() {
+        properties.push(("Context length", context_length.to_string()));
+    }
+    let license = metadata
+        .get_string_opt(keys::GENERAL_LICENSE_NAME)
+        .or_else(|| metadata.get_string_opt(keys::GENERAL_LICENSE));
+    if let Some(license) = license {
+        let license = match metadata.get_string_opt(keys::GENERAL_LICENSE_LINK) {
+            Some(link) => format!("[{license}]({link})"),
+            None => license.to_string(),
+        };
+        properties.push(("License", license));
+    }
+    for (label, key) in [("Languages", keys::GENERAL_LANGUAGES), ("Tags", keys::GENERAL_TAGS)] {
+        if let Ok(values) = metadata.get_string_array(key)
+            && !values.is_empty()
+        {
+            properties.push((label, values.join(", ")));
+        }
+    }
+    write_table(&mut card, &properties);
+
+    write_tokenizer(&mut card, gguf_file);
+    write_tensors(&mut card, gguf_file);
+    card
+}
+
+fn write_tokenizer(card: &mut String, gguf_file: &GgufFile) {
+    let metadata = &gguf_file.metadata;
+    let template = metadata.get_string_opt(keys::TOKENIZER_CHAT_TEMPLATE);
+    let vocab = gguf_file.vocab().ok();
+
+    let mut properties = Vec::new();
+    if let Some(model) = metadata.get_string_opt(keys::TOKENIZER_GGML_MODEL) {
+        properties.push(("Model", model.to_string()));
+    }
+    if let Some(vocab) = &vocab {
+        properties.push(("Vocabulary size", vocab.len().to_string()));
+        let special_tokens: Vec<String> = [
+            ("BOS", keys::TOKENIZER_GGML_BOS_TOKEN_ID),
+            ("EOS", keys::TOKENIZER_GGML_EOS_TOKEN_ID),
+            ("UNK", keys::TOKENIZER_GGML_UNKNOWN_TOKEN_ID),
+            ("PAD", keys::TOKENIZER_GGML_PADDING_TOKEN_ID),
+        ]
+        .into_iter()
+        .filter_map(|(label, key)| {
+            let id = metadata.get_u32_opt(key)?;
+            let token = vocab.token(id)?;
+            Some(format!("{label} {} ({id})", inline_code(token)))
+        })
+        .collect();
+        if !special_tokens.is_empty() {
+            properties.push(("Special tokens", special_tokens.join(", ")));
+        }
+    }
+    if let Some(template) = template {
+        properties.push(("Chat template", chat_template_family(template).to_string()));
+    }
+    if properties.is_empty() {
+        return;
+    }
+
+    let _ = writeln!(card, "\n## Tokenizer\n");
+    write_table(card, &properties);
+    if let Some(template) = template {
+        let fence = fence_for(template);
+        let _ = writeln!(card, "\n### Chat template\n\n{fence}jinja\n{}\n{fence}", template.trim_end());
+    }
+}
+
+fn write_tensors(card: &mut String, gguf_file: &GgufFile) {
+    let _ = writeln!(card, "\n## Tensors\n");
+    let _ = writeln!(card, "<details>");
+    let total = match gguf_file.total_size() {
+        Ok(size) => format!(", {}", human_size(size)),
+        Err(_) => String::new(),
+    };
+    let _ = writeln!(card, "<summary>{} tensors{total}</summary>\n", gguf_file.tensors.len());
+    let _ = writeln!(card, "| Type | Tensors | Size |");
+    let _ = writeln!(card, "|---|---|---|");
+    // Most used types first, in the order of `InspectReport::quant_histogram`
+    for (quantization_type, count) in gguf_file.quantization_types_by_frequency() {
+        let bytes = gguf_file
+            .tensors
+            .iter()
+            .filter(|t| t.quantization_type == quantization_type)
+            .try_fold(0u64, |total, t| total.checked_add(t.size_bytes().ok()?));
+        let size = bytes.map_or_else(|| "unknown".to_string(), human_size);
+        let _ = writeln!(card, "| {quantization_type} | {count} | {size} |");
+    }
+    let _ = writeln!(card, "\n</details>");
+}
+
+fn write_table(card: &mut String, rows: &[(&str, String)]) {
+    let _ = writeln!(card, "| Property | Value |");
+    let _ = writeln!(card, "|---|---|");
+    for (label, value) in rows {
+        let _ = writeln!(card, "| {label} | {} |", escape_cell(value));
+    }
+}
+
+/// Name the prompt format a chat template implements, from its role markers
+fn chat_template_family(template: &str) -> &'static str {
+    const MARKERS: &[(&str, &str)] = &[
+        ("<|start_header_id|>", "llama3"),
+        ("<|im_start|>", "chatml"),
+        ("<start_of_turn>", "gemma"),
+        ("<|user|>", "zephyr"),
+        ("[INST]", "llama2"),
+        ("### Instruction", "alpaca"),
+    ];
+    MARKERS
+        .iter()
+        .find(|(marker, _)| template.contains(marker))
+        .map_or("custom", |(_, family)| family)
+}
+
+/// Keep `|` from splitting a table cell and newlines from ending the row
+fn escape_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Wrap a token in backticks, using a longer run if the token contains one
+fn inline_code(token: &str) -> String {
+    let ticks = "`".repeat(longest_backtick_run(token) + 1);
+    let padding = if token.starts_with('`') || token.ends_with('`') { " " } else { "" };
+    format!("{ticks}{padding}{token}{padding}{ticks}")
+}
+
+/// Code fence longer than any backtick run in the content
+fn fence_for(content: &str) -> String {
+    "`".repeat((longest_backtick_run(content) + 1).max(3))
+}
+
+fn longest_backtick_run(text: &str) -> usize {
+    text.split(|c| c != '`').map(str::len).max().unwrap_or(0)
+}
+
+/// Format a byte count with binary units, e.g. "1.5 MiB"
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
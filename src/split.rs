@@ -0,0 +1,84 @@
+/*!
+ * Split Shard Self-Description
+ *
+ * `gguf-split` writes `split.no`, `split.count` and `split.tensors.count`
+ * into every shard. Only the first shard carries the model metadata, later
+ * shards hold just the split keys and their share of the tensors.
+ */
+
+use crate::keys;
+use crate::metadata::GgufMetadata;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Position of one shard within a split model
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SplitInfo {
+    /// Zero-based index of this shard, `split.no`
+    pub no: u32,
+    /// Number of shards in the model, `split.count`
+    pub count: u32,
+    /// Number of tensors across all shards, `split.tensors.count`
+    pub tensors_count: u64,
+}
+
+/// Something about a shard's split keys that doesn't add up
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SplitWarning {
+    /// `split.no` is not below `split.count`
+    NumberOutOfRange { no: u32, count: u32 },
+    /// A shard after the first carries the model metadata as well
+    UnexpectedModelMetadata { no: u32 },
+    /// The shard holds more tensors than the whole model declares
+    TooManyTensors { tensors: u64, tensors_count: u64 },
+}
+
+impl fmt::Display for SplitWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SplitWarning::NumberOutOfRange { no, count } => {
+                write!(f, "split.no ({no}) is not below split.count ({count})")
+            }
+            SplitWarning::UnexpectedModelMetadata { no } => {
+                write!(f, "shard {no} carries model metadata, which only the first shard should")
+            }
+            SplitWarning::TooManyTensors { tensors, tensors_count } => {
+                write!(f, "shard has {tensors} tensors but split.tensors.count is {tensors_count}")
+            }
+        }
+    }
+}
+
+impl SplitInfo {
+    /// Read the split keys, `None` unless all three are present as
+    /// non-negative integers
+    pub fn from_metadata(metadata: &GgufMetadata) -> Option<Self> {
+        let integer = |key: &str| metadata.get(key)?.integer_value();
+        Some(Self {
+            no: integer(keys::SPLIT_NO)?.try_into().ok()?,
+            count: integer(keys::SPLIT_COUNT)?.try_into().ok()?,
+            tensors_count: integer(keys::SPLIT_TENSORS_COUNT)?.try_into().ok()?,
+        })
+    }
+
+    /// Check whether this is the shard holding the model metadata
+    pub fn is_first(&self) -> bool {
+        self.no == 0
+    }
+
+    /// Check the split keys against the shard they were read from
+    pub(crate) fn validate(&self, metadata: &GgufMetadata, tensors: u64) -> Vec<SplitWarning> {
+        let mut warnings = Vec::new();
+        if self.no >= self.count {
+            warnings.push(SplitWarning::NumberOutOfRange { no: self.no, count: self.count });
+        }
+        if !self.is_first() && metadata.contains(keys::GENERAL_ARCHITECTURE) {
+            warnings.push(SplitWarning::UnexpectedModelMetadata { no: self.no });
+        }
+        if tensors > self.tensors_count {
+            warnings.push(SplitWarning::TooManyTensors { tensors, tensors_count: self.tensors_count });
+        }
+        warnings
+    }
+}
@@ -0,0 +1,43 @@
+/*!
+ * Parse Timing and Size Statistics
+ */
+
+use core::time::Duration;
+use serde::{Deserialize, Serialize};
+
+/// How long each section took to parse and how much was read,
+/// see `GgufFile::from_reader_with_stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseStats {
+    /// Time spent reading the metadata key-value section
+    pub metadata_duration: Duration,
+    /// Time spent reading the tensor info section
+    pub tensor_duration: Duration,
+    /// Bytes from the start of the header to the end of the tensor infos
+    pub bytes_read: u64,
+    pub kv_count: u64,
+    pub tensor_count: u64,
+}
+
+/// Measures elapsed time, always zero without the `std` feature since
+/// `core` has no clock
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "std")]
+    started: std::time::Instant,
+}
+
+impl Stopwatch {
+    pub(crate) fn start() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            started: std::time::Instant::now(),
+        }
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        #[cfg(feature = "std")]
+        return self.started.elapsed();
+        #[cfg(not(feature = "std"))]
+        return Duration::ZERO;
+    }
+}
@@ -0,0 +1,79 @@
+/*!
+ * Metadata-Only Stub Files
+ *
+ * A stub is a GGUF holding a model's header, metadata and tensor infos but
+ * no tensor data, small enough to catalog in place of multi-gigabyte
+ * originals. Stubs carry `aiogguf.stub = true` so readers can tell them
+ * apart from truncated downloads.
+ */
+
+use crate::error::Result;
+use crate::header::GgufHeader;
+use crate::keys;
+use crate::types::GgufValue;
+use crate::GgufFile;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Keys dropped from stubs by default, the bulk of most metadata sections
+pub const DEFAULT_STUB_DROP_KEYS: &[&str] = &[
+    keys::TOKENIZER_GGML_TOKENS,
+    keys::TOKENIZER_GGML_SCORES,
+    keys::TOKENIZER_GGML_TOKEN_TYPE,
+    keys::TOKENIZER_GGML_MERGES,
+    keys::TOKENIZER_CHAT_TEMPLATE,
+    keys::TOKENIZER_HUGGINGFACE_JSON,
+];
+
+/// Options for `GgufFile::write_metadata_stub`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StubOptions {
+    /// Metadata keys left out of the stub
+    pub drop_keys: Vec<String>,
+    /// Record the length of each dropped array or string under
+    /// `aiogguf.stub.dropped.<key>`
+    pub count_markers: bool,
+}
+
+impl Default for StubOptions {
+    fn default() -> Self {
+        Self {
+            drop_keys: DEFAULT_STUB_DROP_KEYS.iter().map(|key| key.to_string()).collect(),
+            count_markers: true,
+        }
+    }
+}
+
+pub(crate) fn write_stub(gguf_file: &GgufFile, path: &Path, options: &StubOptions) -> Result<()> {
+    let mut metadata = gguf_file.metadata.clone();
+    for key in &options.drop_keys {
+        let marker = match metadata.remove(key) {
+            Some(GgufValue::Array(items)) => Some(items.len() as u64),
+            Some(GgufValue::String(value)) => Some(value.len() as u64),
+            _ => None,
+        };
+        if let Some(count) = marker
+            && options.count_markers
+        {
+            metadata.insert(format!("{}{key}", keys::AIOGGUF_STUB_DROPPED_PREFIX), count);
+        }
+    }
+    metadata.insert(keys::AIOGGUF_STUB, true);
+
+    let header = GgufHeader {
+        metadata_kv_count: metadata.len() as u64,
+        ..gguf_file.header.clone()
+    };
+
+    let mut writer = BufWriter::new(File::create(path)?);
+    header.write(&mut writer)?;
+    metadata.write(&mut writer)?;
+    for tensor in &gguf_file.tensors {
+        tensor.write(&mut writer)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
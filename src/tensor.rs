@@ -3,156 +3,245 @@
  */
 
 use crate::error::{GgufError, Result};
+use crate::options::ParseOptions;
+use crate::quant_info::{QuantInfo, QUANT_INFO};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Seek};
+use crate::io::{Read, Seek};
+use crate::read::{preallocation, read_string, read_u32, read_u64};
+use crate::tensor_reader::TensorInfoReader;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
 
 /// Quantization types supported by GGUF
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+///
+/// Ids, sizes and labels live in the `QuantInfo` registry, see `info()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
-#[repr(u32)]
 pub enum QuantizationType {
-    F32 = 0,
-    F16 = 1,
-    Q4_0 = 2,
-    Q4_1 = 3,
-    Q5_0 = 6,
-    Q5_1 = 7,
-    Q8_0 = 8,
-    Q8_1 = 9,
-    Q2_K = 10,
-    Q3_K = 11,
-    Q4_K = 12,
-    Q5_K = 13,
-    Q6_K = 14,
-    Q8_K = 15,
-    IQ2_XXS = 16,
-    IQ2_XS = 17,
-    IQ3_XXS = 18,
-    IQ1_S = 19,
-    IQ4_NL = 20,
-    IQ3_S = 21,
-    IQ2_S = 22,
-    IQ4_XS = 23,
-    I8 = 24,
-    I16 = 25,
-    I32 = 26,
-    I64 = 27,
-    F64 = 28,
-    IQ1_M = 29,
+    F32,
+    F16,
+    Q4_0,
+    Q4_1,
+    Q5_0,
+    Q5_1,
+    Q8_0,
+    Q8_1,
+    Q2_K,
+    Q3_K,
+    Q4_K,
+    Q5_K,
+    Q6_K,
+    Q8_K,
+    IQ2_XXS,
+    IQ2_XS,
+    IQ3_XXS,
+    IQ1_S,
+    IQ4_NL,
+    IQ3_S,
+    IQ2_S,
+    IQ4_XS,
+    I8,
+    I16,
+    I32,
+    I64,
+    F64,
+    IQ1_M,
+    BF16,
+    TQ1_0,
+    TQ2_0,
+    /// A type id this crate doesn't know yet, e.g. one added to llama.cpp
+    /// after this release
+    Unknown(u32),
+}
+
+/// Broad family a quantization type belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum QuantFamily {
+    /// Full or half precision floats (F16, BF16, F32, F64)
+    Float,
+    /// Plain integers (I8, I16, I32, I64)
+    Integer,
+    /// Original block quantization (Q4_0, Q4_1, Q5_0, Q5_1, Q8_0, Q8_1)
+    Legacy,
+    /// K-quants (Q2_K through Q8_K)
+    KQuant,
+    /// Importance-matrix quants (IQ*)
+    IQuant,
+    /// Ternary quants for BitNet-style models (TQ1_0, TQ2_0)
+    Ternary,
+    /// Types this crate doesn't recognize
+    Unknown,
+}
+
+/// Rough quality label for a quantized model, as model hubs describe GGUFs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum QualityTier {
+    /// Under 3 bits per weight (Q2_K, IQ2_*, IQ1_*)
+    VerySmall,
+    /// 3 to 4 bits per weight (Q3_K, IQ3_*)
+    Small,
+    /// 4 to 6 bits per weight (Q4_K, Q5_K, Q4_0)
+    Balanced,
+    /// 6 bits per weight and up (Q6_K, Q8_0, F16)
+    High,
+}
+
+impl QualityTier {
+    /// Classify a bits-per-weight figure
+    pub fn from_bits_per_weight(bits_per_weight: f32) -> Self {
+        if bits_per_weight >= 6.0 {
+            QualityTier::High
+        } else if bits_per_weight >= 4.0 {
+            QualityTier::Balanced
+        } else if bits_per_weight >= 3.0 {
+            QualityTier::Small
+        } else {
+            QualityTier::VerySmall
+        }
+    }
 }
 
 impl QuantizationType {
-    /// Check if this is a quantized type (not full precision)
+    /// Get the registry entry for this type, `None` for unknown types
+    pub fn info(&self) -> Option<&'static QuantInfo> {
+        QUANT_INFO.iter().find(|info| info.quantization_type == *self)
+    }
+
+    /// Iterate over every known quantization type, ordered by id
+    pub fn all() -> impl Iterator<Item = QuantizationType> {
+        QUANT_INFO.iter().map(|info| info.quantization_type)
+    }
+
+    /// Get the family this quantization type belongs to
+    pub fn family(&self) -> QuantFamily {
+        self.info().map_or(QuantFamily::Unknown, |info| info.family)
+    }
+
+    /// Check if this is a quantized type (neither a float nor a plain integer)
     pub fn is_quantized(&self) -> bool {
-        !matches!(self, QuantizationType::F32 | QuantizationType::F16 | QuantizationType::F64)
+        self.info().is_none_or(|info| info.is_quantized)
     }
 
-    /// Get the bits per weight for this quantization type
-    pub fn bits_per_weight(&self) -> f32 {
-        match self {
-            QuantizationType::F32 => 32.0,
-            QuantizationType::F16 => 16.0,
-            QuantizationType::F64 => 64.0,
-            QuantizationType::Q4_0 | QuantizationType::Q4_1 => 4.5,
-            QuantizationType::Q5_0 | QuantizationType::Q5_1 => 5.5,
-            QuantizationType::Q8_0 | QuantizationType::Q8_1 => 8.5,
-            QuantizationType::Q2_K => 2.5625,
-            QuantizationType::Q3_K => 3.4375,
-            QuantizationType::Q4_K => 4.5,
-            QuantizationType::Q5_K => 5.5,
-            QuantizationType::Q6_K => 6.5625,
-            QuantizationType::Q8_K => 8.5,
-            QuantizationType::IQ2_XXS => 2.0625,
-            QuantizationType::IQ2_XS => 2.3125,
-            QuantizationType::IQ3_XXS => 3.0625,
-            QuantizationType::IQ1_S => 1.5625,
-            QuantizationType::IQ4_NL => 4.5,
-            QuantizationType::IQ3_S => 3.4375,
-            QuantizationType::IQ2_S => 2.5,
-            QuantizationType::IQ4_XS => 4.25,
-            QuantizationType::I8 => 8.0,
-            QuantizationType::I16 => 16.0,
-            QuantizationType::I32 => 32.0,
-            QuantizationType::I64 => 64.0,
-            QuantizationType::IQ1_M => 1.75,
-        }
+    /// Check if this is an unquantized float type
+    pub fn is_float(&self) -> bool {
+        self.family() == QuantFamily::Float
+    }
+
+    /// Check if this is a plain integer type
+    pub fn is_integer(&self) -> bool {
+        self.family() == QuantFamily::Integer
+    }
+
+    /// Get the bits per weight for this quantization type, `None` for unknown types
+    pub fn bits_per_weight(&self) -> Option<f32> {
+        self.info().map(|info| info.bits_per_weight)
+    }
+
+    /// Get how many times smaller weights of this type are than F16, e.g.
+    /// about 3.56 for Q4_0, `None` for unknown types
+    pub fn compression_ratio_vs_f16(&self) -> Option<f32> {
+        self.bits_per_weight().map(|bits| 16.0 / bits)
+    }
+
+    /// Number of weights stored per block, `None` for unknown types
+    pub fn block_size(&self) -> Option<u64> {
+        self.info().map(|info| info.block_size)
+    }
+
+    /// Number of bytes one block occupies, `None` for unknown types
+    pub fn type_size(&self) -> Option<u64> {
+        self.info().map(|info| info.type_size_bytes)
     }
 
     /// Get a human-readable description
     pub fn description(&self) -> &'static str {
+        self.info().map_or("unknown quantization", |info| info.description)
+    }
+
+    /// Get the numeric type id stored in the file
+    pub fn id(&self) -> u32 {
         match self {
-            QuantizationType::F32 => "32-bit float",
-            QuantizationType::F16 => "16-bit float",
-            QuantizationType::F64 => "64-bit float",
-            QuantizationType::Q4_0 => "4-bit quantized (symmetric)",
-            QuantizationType::Q4_1 => "4-bit quantized (asymmetric)",
-            QuantizationType::Q5_0 => "5-bit quantized (symmetric)",
-            QuantizationType::Q5_1 => "5-bit quantized (asymmetric)",
-            QuantizationType::Q8_0 => "8-bit quantized (symmetric)",
-            QuantizationType::Q8_1 => "8-bit quantized (asymmetric)",
-            QuantizationType::Q2_K => "2-bit K-quantized",
-            QuantizationType::Q3_K => "3-bit K-quantized",
-            QuantizationType::Q4_K => "4-bit K-quantized",
-            QuantizationType::Q5_K => "5-bit K-quantized",
-            QuantizationType::Q6_K => "6-bit K-quantized",
-            QuantizationType::Q8_K => "8-bit K-quantized",
-            QuantizationType::IQ2_XXS => "2-bit IMatrix (extra small)",
-            QuantizationType::IQ2_XS => "2-bit IMatrix (small)",
-            QuantizationType::IQ3_XXS => "3-bit IMatrix (extra small)",
-            QuantizationType::IQ1_S => "1-bit IMatrix (small)",
-            QuantizationType::IQ4_NL => "4-bit IMatrix (non-linear)",
-            QuantizationType::IQ3_S => "3-bit IMatrix (small)",
-            QuantizationType::IQ2_S => "2-bit IMatrix (small)",
-            QuantizationType::IQ4_XS => "4-bit IMatrix (extra small)",
-            QuantizationType::I8 => "8-bit integer",
-            QuantizationType::I16 => "16-bit integer",
-            QuantizationType::I32 => "32-bit integer",
-            QuantizationType::I64 => "64-bit integer",
-            QuantizationType::IQ1_M => "1-bit IMatrix (medium)",
+            QuantizationType::Unknown(id) => *id,
+            known => known.info().map_or(u32::MAX, |info| info.id),
         }
     }
+
+    /// Get the canonical llama.cpp label, e.g. `"Q4_K"` or `"IQ2_XXS"`
+    pub fn short_name(&self) -> &'static str {
+        self.info().map_or("unknown", |info| info.name)
+    }
+
+    /// Map a type id, keeping ids this crate doesn't know as `Unknown`
+    ///
+    /// Unlike `try_from`, this never fails, so files using quantization types
+    /// newer than this crate still parse.
+    pub fn from_id(id: u32) -> Self {
+        QuantizationType::try_from(id).unwrap_or(QuantizationType::Unknown(id))
+    }
 }
 
 impl TryFrom<u32> for QuantizationType {
     type Error = GgufError;
 
     fn try_from(value: u32) -> Result<Self> {
-        match value {
-            0 => Ok(QuantizationType::F32),
-            1 => Ok(QuantizationType::F16),
-            2 => Ok(QuantizationType::Q4_0),
-            3 => Ok(QuantizationType::Q4_1),
-            6 => Ok(QuantizationType::Q5_0),
-            7 => Ok(QuantizationType::Q5_1),
-            8 => Ok(QuantizationType::Q8_0),
-            9 => Ok(QuantizationType::Q8_1),
-            10 => Ok(QuantizationType::Q2_K),
-            11 => Ok(QuantizationType::Q3_K),
-            12 => Ok(QuantizationType::Q4_K),
-            13 => Ok(QuantizationType::Q5_K),
-            14 => Ok(QuantizationType::Q6_K),
-            15 => Ok(QuantizationType::Q8_K),
-            16 => Ok(QuantizationType::IQ2_XXS),
-            17 => Ok(QuantizationType::IQ2_XS),
-            18 => Ok(QuantizationType::IQ3_XXS),
-            19 => Ok(QuantizationType::IQ1_S),
-            20 => Ok(QuantizationType::IQ4_NL),
-            21 => Ok(QuantizationType::IQ3_S),
-            22 => Ok(QuantizationType::IQ2_S),
-            23 => Ok(QuantizationType::IQ4_XS),
-            24 => Ok(QuantizationType::I8),
-            25 => Ok(QuantizationType::I16),
-            26 => Ok(QuantizationType::I32),
-            27 => Ok(QuantizationType::I64),
-            28 => Ok(QuantizationType::F64),
-            29 => Ok(QuantizationType::IQ1_M),
-            _ => Err(GgufError::InvalidQuantizationType(value)),
+        QUANT_INFO
+            .iter()
+            .find(|info| info.id == value)
+            .map(|info| info.quantization_type)
+            .ok_or(GgufError::InvalidQuantizationType(value))
+    }
+}
+
+impl fmt::Display for QuantizationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuantizationType::Unknown(id) => write!(f, "unknown({id})"),
+            known => f.write_str(known.short_name()),
         }
     }
 }
 
+impl FromStr for QuantizationType {
+    type Err = GgufError;
+
+    /// Parse a label like `"Q4_K"`, ignoring case and separators so `"q4k"` works too
+    fn from_str(label: &str) -> Result<Self> {
+        let wanted = normalize_label(label);
+        QuantizationType::all()
+            .find(|t| normalize_label(t.short_name()) == wanted)
+            .ok_or_else(|| GgufError::UnknownQuantizationLabel(label.to_string()))
+    }
+}
+
+/// Uppercase a quantization label and drop `_`, `-` and `.` for lenient matching
+pub(crate) fn normalize_label(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | '.'))
+        .map(|c| c.to_ascii_uppercase())
+        .collect()
+}
+
+/// Order in which tensor dimensions are listed
+///
+/// GGUF stores dimensions in ggml order, fastest-varying first, so an
+/// embedding matrix reads `[n_embd, n_vocab]`. Row-major order is the reverse,
+/// matching how PyTorch and NumPy print the same tensor as `[n_vocab, n_embd]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DimOrder {
+    /// As stored in the file (`ne[0]` first)
+    #[default]
+    Ggml,
+    /// Reversed, slowest-varying first
+    RowMajor,
+}
+
 /// Information about a tensor in a GGUF file
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TensorInfo {
     pub name: String,
     pub dimensions: Vec<u64>,
@@ -163,85 +252,161 @@ pub struct TensorInfo {
 impl TensorInfo {
     /// Read all tensor information from a reader
     pub fn read_all<R: Read + Seek>(reader: &mut R, tensor_count: u64) -> Result<Vec<Self>> {
-        let mut tensors = Vec::with_capacity(tensor_count as usize);
-
-        for _ in 0..tensor_count {
-            // Read tensor name
-            let name = {
-                let mut name_len_buf = [0u8; 8];
-                reader.read_exact(&mut name_len_buf)?;
-                let name_len = u64::from_le_bytes(name_len_buf);
-
-                let mut name_buf = vec![0u8; name_len as usize];
-                reader.read_exact(&mut name_buf)?;
-                String::from_utf8(name_buf)?
-            };
-
-            // Read number of dimensions
-            let n_dimensions = {
-                let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
-                u32::from_le_bytes(buf)
-            };
-
-            if n_dimensions > 4 {
-                return Err(GgufError::InvalidTensorDimensions);
-            }
+        Self::read_all_with_options(reader, tensor_count, &ParseOptions::default())
+    }
 
-            // Read dimensions
-            let mut dimensions = Vec::with_capacity(n_dimensions as usize);
-            for _ in 0..n_dimensions {
-                let mut dim_buf = [0u8; 8];
-                reader.read_exact(&mut dim_buf)?;
-                dimensions.push(u64::from_le_bytes(dim_buf));
-            }
+    /// Read all tensor information from a reader using custom parse options
+    pub fn read_all_with_options<R: Read + Seek>(
+        reader: &mut R,
+        tensor_count: u64,
+        options: &ParseOptions,
+    ) -> Result<Vec<Self>> {
+        TensorInfoReader::with_options(reader, tensor_count, options).collect()
+    }
+
+    /// Stream tensor infos from a reader as they are parsed
+    ///
+    /// Yields at most `tensor_count` items and stops after the first error.
+    pub fn iter_from_reader<R: Read>(reader: &mut R, tensor_count: u64) -> TensorInfoReader<'_, R> {
+        TensorInfoReader::new(reader, tensor_count)
+    }
+
+    /// Read a single tensor info entry from a reader
+    pub fn read<R: Read>(reader: &mut R) -> Result<Self> {
+        Self::read_with_options(reader, &ParseOptions::default())
+    }
+
+    /// Read a single tensor info entry from a reader using custom parse options
+    pub fn read_with_options<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<Self> {
+        Self::read_fields(reader, options).map_err(|e| e.at_stage("tensor info"))
+    }
+
+    fn read_fields<R: Read>(reader: &mut R, options: &ParseOptions) -> Result<Self> {
+        // Read tensor name
+        let name = read_string(reader, &options.limits)?;
+
+        // Read number of dimensions
+        let n_dimensions = read_u32(reader)?;
 
-            // Read quantization type
-            let quantization_type = {
-                let mut type_buf = [0u8; 4];
-                reader.read_exact(&mut type_buf)?;
-                QuantizationType::try_from(u32::from_le_bytes(type_buf))?
-            };
-
-            // Read tensor data offset
-            let offset = {
-                let mut offset_buf = [0u8; 8];
-                reader.read_exact(&mut offset_buf)?;
-                u64::from_le_bytes(offset_buf)
-            };
-
-            tensors.push(TensorInfo {
+        if n_dimensions as usize > options.max_tensor_dims {
+            return Err(GgufError::InvalidTensorDimensions {
                 name,
-                dimensions,
-                quantization_type,
-                offset,
+                n_dimensions,
+                max: options.max_tensor_dims,
             });
         }
 
-        Ok(tensors)
+        // Read dimensions
+        let mut dimensions = Vec::with_capacity(preallocation(n_dimensions.into()));
+        for _ in 0..n_dimensions {
+            dimensions.push(read_u64(reader)?);
+        }
+
+        // Read quantization type
+        let quantization_type = QuantizationType::from_id(read_u32(reader)?);
+
+        // Read tensor data offset
+        let offset = read_u64(reader)?;
+
+        Ok(TensorInfo {
+            name,
+            dimensions,
+            quantization_type,
+            offset,
+        })
     }
 
-    /// Calculate the size of this tensor in bytes
-    pub fn size_bytes(&self) -> u64 {
-        if self.dimensions.is_empty() {
-            return 0;
-        }
+    /// Number of elements, the product of the dimensions
+    ///
+    /// A tensor without dimensions is a scalar with one element, like
+    /// llama.cpp's `ggml_nelements`. Saturates at `u64::MAX` for corrupt
+    /// shapes; `size_bytes` reports those as `SizeOverflow`.
+    pub fn element_count(&self) -> u64 {
+        self.dimensions.iter().fold(1, |count, &dimension| count.saturating_mul(dimension))
+    }
+
+    /// Calculate the size of this tensor in bytes from whole blocks
+    ///
+    /// Fails for quantization types this crate doesn't know the size of, and
+    /// for shapes whose size doesn't fit in 64 bits.
+    pub fn size_bytes(&self) -> Result<u64> {
+        let info = self
+            .quantization_type
+            .info()
+            .ok_or(GgufError::UnknownQuantizationSize(self.quantization_type.id()))?;
+        let element_count = self
+            .dimensions
+            .iter()
+            .try_fold(1u64, |count, &dimension| count.checked_mul(dimension))
+            .ok_or_else(|| GgufError::SizeOverflow(self.name.clone()))?;
+        element_count
+            .div_ceil(info.block_size)
+            .checked_mul(info.type_size_bytes)
+            .ok_or_else(|| GgufError::SizeOverflow(self.name.clone()))
+    }
 
-        let element_count: u64 = self.dimensions.iter().product();
-        let bits_per_element = self.quantization_type.bits_per_weight();
-        
-        // Round up to nearest byte
-        ((element_count as f64 * bits_per_element as f64) / 8.0).ceil() as u64
+    /// Absolute byte range of this tensor's data, given the file's data offset
+    pub fn byte_range(&self, data_offset: u64) -> Result<Range<u64>> {
+        let overflow = || GgufError::SizeOverflow(self.name.clone());
+        let start = data_offset.checked_add(self.offset).ok_or_else(overflow)?;
+        let end = start.checked_add(self.size_bytes()?).ok_or_else(overflow)?;
+        Ok(start..end)
     }
 
-    /// Get tensor shape as a formatted string
+    /// Get tensor shape as a formatted string, in ggml order as stored
+    ///
+    /// Scalars, stored with no dimensions, render as `[]` like NumPy's `()`
+    /// shape rather than `[1]`, so the string reflects what the file holds.
     pub fn shape_string(&self) -> String {
-        format!("[{}]", self.dimensions.iter()
+        self.shape_string_ordered(DimOrder::Ggml)
+    }
+
+    /// Get tensor shape as a formatted string in the given order
+    pub fn shape_string_ordered(&self, order: DimOrder) -> String {
+        let dims = match order {
+            DimOrder::Ggml => self.shape_ggml().to_vec(),
+            DimOrder::RowMajor => self.shape_logical(),
+        };
+        format!("[{}]", dims.iter()
             .map(|d| d.to_string())
             .collect::<Vec<_>>()
             .join(", "))
     }
 
+    /// Dimensions as stored, fastest-varying first (`ne[0]` is the column count)
+    pub fn shape_ggml(&self) -> &[u64] {
+        &self.dimensions
+    }
+
+    /// Dimensions in row-major (PyTorch/NumPy) order, slowest-varying first
+    pub fn shape_logical(&self) -> Vec<u64> {
+        self.dimensions.iter().rev().copied().collect()
+    }
+
+    /// Number of elements in each row (`ne[0]`)
+    pub fn num_cols(&self) -> u64 {
+        self.dimensions.first().copied().unwrap_or(1)
+    }
+
+    /// Number of rows, i.e. the product of every dimension but the first,
+    /// saturating at `u64::MAX` like `element_count`
+    pub fn num_rows(&self) -> u64 {
+        self.dimensions.iter().skip(1).fold(1, |rows, &dimension| rows.saturating_mul(dimension))
+    }
+
+    /// Check if all dimensions beyond the first are 1, as `ggml_is_vector`
+    pub fn is_vector(&self) -> bool {
+        !self.dimensions.is_empty() && self.dimensions.iter().skip(1).all(|&d| d == 1)
+    }
+
+    /// Check if the tensor has at least two dimensions and all beyond the
+    /// second are 1
+    ///
+    /// Unlike `ggml_is_matrix`, 1-D tensors such as norms don't count.
+    pub fn is_matrix(&self) -> bool {
+        self.dimensions.len() >= 2 && self.dimensions.iter().skip(2).all(|&d| d == 1)
+    }
+
     /// Check if this is a weight tensor (not bias or other auxiliary tensors)
     pub fn is_weight_tensor(&self) -> bool {
         self.name.contains("weight") || 
@@ -253,26 +418,18 @@ impl TensorInfo {
     }
 
     /// Get the layer number if this tensor belongs to a specific layer
+    ///
+    /// The number is the name component after a whole `blk`, `layers` or
+    /// `blocks` component, so `blk.0.attn_q.weight` and
+    /// `model.layers.0.mlp.up_proj.weight` match but `sublk.0.weight` doesn't.
     pub fn layer_number(&self) -> Option<u32> {
-        // Common patterns: "layers.0.weight", "blocks.15.norm", etc.
-        if let Some(layers_pos) = self.name.find("layers.") {
-            let start = layers_pos + 7; // "layers.".len()
-            if let Some(dot_pos) = self.name[start..].find('.') {
-                if let Ok(layer_num) = self.name[start..start + dot_pos].parse::<u32>() {
-                    return Some(layer_num);
-                }
+        let components: Vec<&str> = self.name.split('.').collect();
+        components.windows(3).find_map(|window| {
+            let [prefix, number, _] = window else { return None };
+            if !matches!(*prefix, "blk" | "layers" | "blocks") || !number.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
             }
-        }
-        
-        if let Some(blocks_pos) = self.name.find("blocks.") {
-            let start = blocks_pos + 7; // "blocks.".len()
-            if let Some(dot_pos) = self.name[start..].find('.') {
-                if let Ok(layer_num) = self.name[start..start + dot_pos].parse::<u32>() {
-                    return Some(layer_num);
-                }
-            }
-        }
-        
-        None
+            number.parse().ok()
+        })
     }
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+/*!
+ * Expected Tensor Names
+ *
+ * llama.cpp loads tensors by fixed names: a few global tensors plus a set
+ * per transformer block named `blk.N.*`. Which per-block tensors exist
+ * depends on the architecture and on whether the feed-forward is a mixture
+ * of experts.
+ */
+
+use crate::metadata::ModelConfig;
+use crate::tensor::TensorInfo;
+use alloc::collections::BTreeSet;
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Architectures whose tensors follow the llama layout or one of the
+/// variations `block_tensor_names` knows
+const KNOWN_LAYOUTS: &[&str] = &["llama", "mistral", "mixtral", "codellama", "qwen2", "qwen2moe"];
+
+const GLOBAL_TENSORS: &[&str] = &["token_embd.weight", "output_norm.weight", "output.weight"];
+
+const ATTENTION_TENSORS: &[&str] = &[
+    "attn_norm.weight",
+    "attn_q.weight",
+    "attn_k.weight",
+    "attn_v.weight",
+    "attn_output.weight",
+    "ffn_norm.weight",
+];
+
+const ATTENTION_BIASES: &[&str] = &["attn_q.bias", "attn_k.bias", "attn_v.bias"];
+
+const DENSE_FFN_TENSORS: &[&str] = &["ffn_gate.weight", "ffn_up.weight", "ffn_down.weight"];
+
+const EXPERT_FFN_TENSORS: &[&str] = &[
+    "ffn_gate_inp.weight",
+    "ffn_gate_exps.weight",
+    "ffn_up_exps.weight",
+    "ffn_down_exps.weight",
+];
+
+const SHARED_EXPERT_TENSORS: &[&str] = &[
+    "ffn_gate_inp_shexp.weight",
+    "ffn_gate_shexp.weight",
+    "ffn_up_shexp.weight",
+    "ffn_down_shexp.weight",
+];
+
+/// Which of a block's expected tensors a file has, see
+/// `GgufFile::layer_completeness`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerReport {
+    pub layer: u32,
+    /// Expected tensors the file has, in expected order
+    pub present: Vec<String>,
+    /// Expected tensors the file lacks, in expected order
+    pub missing: Vec<String>,
+}
+
+impl LayerReport {
+    /// Check whether every expected tensor of the layer is present
+    pub fn is_complete(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+
+impl ModelConfig {
+    /// Get the tensor names llama.cpp expects for this configuration, globals
+    /// first and then each block in order
+    ///
+    /// Follows the llama layout, which Mistral models share. Qwen2 adds
+    /// query, key and value biases, configurations with `expert_count` use
+    /// expert tensors in place of the dense feed-forward, and Qwen2-MoE adds
+    /// a shared expert. With `tied_embeddings` the output projection reuses
+    /// `token_embd.weight`, so `output.weight` is left out.
+    ///
+    /// `None` for other architectures, whose layouts differ, e.g. Phi-3's
+    /// fused QKV projection or Mamba's state-space blocks.
+    pub fn expected_tensor_names(&self, tied_embeddings: bool) -> Option<Vec<String>> {
+        let block_tensors = self.block_tensor_names()?;
+        let mut names: Vec<String> = GLOBAL_TENSORS
+            .iter()
+            .filter(|&&name| !(tied_embeddings && name == "output.weight"))
+            .map(|name| name.to_string())
+            .collect();
+        for block in 0..self.block_count {
+            names.extend(block_tensors.iter().map(|name| format!("blk.{block}.{name}")));
+        }
+        Some(names)
+    }
+
+    /// Per-block tensor names without the `blk.N.` prefix, `None` for
+    /// architectures without a known layout
+    fn block_tensor_names(&self) -> Option<Vec<&'static str>> {
+        if !KNOWN_LAYOUTS.contains(&self.architecture.as_str()) {
+            return None;
+        }
+        let is_qwen2 = matches!(self.architecture.as_str(), "qwen2" | "qwen2moe");
+        let is_moe = self.expert_count.is_some_and(|experts| experts > 0);
+
+        let mut block_tensors: Vec<&str> = ATTENTION_TENSORS.to_vec();
+        if is_qwen2 {
+            block_tensors.extend(ATTENTION_BIASES);
+        }
+        if is_moe {
+            block_tensors.extend(EXPERT_FFN_TENSORS);
+            if self.architecture == "qwen2moe" {
+                block_tensors.extend(SHARED_EXPERT_TENSORS);
+            }
+        } else {
+            block_tensors.extend(DENSE_FFN_TENSORS);
+        }
+        Some(block_tensors)
+    }
+}
+
+pub(crate) fn layer_completeness(tensors: &[TensorInfo], config: &ModelConfig) -> Option<Vec<LayerReport>> {
+    let names: BTreeSet<&str> = tensors.iter().map(|t| t.name.as_str()).collect();
+    let block_tensors = config.block_tensor_names()?;
+    let reports = (0..config.block_count)
+        .map(|layer| {
+            let (present, missing) = block_tensors
+                .iter()
+                .map(|name| format!("blk.{layer}.{name}"))
+                .partition(|name| names.contains(name.as_str()));
+            LayerReport { layer, present, missing }
+        })
+        .collect();
+    Some(reports)
+}
@@ -0,0 +1,103 @@
+/*!
+ * Streaming Tensor Info Reader
+ *
+ * Counterpart of `GgufMetadataReader` for the tensor info section: tensor
+ * infos are yielded as they are parsed, so a caller can keep a subset or
+ * report progress without collecting every entry first.
+ */
+
+use crate::error::Result;
+use crate::io::Read;
+use crate::options::ParseOptions;
+use crate::tensor::TensorInfo;
+
+/// Iterator over the tensor infos of a reader positioned after the metadata
+///
+/// Each item is a whole tensor info, so after any successful item the reader
+/// sits at the start of the next one and `into_inner` can hand it back to
+/// resume parsing. After an error the iterator is poisoned: it yields
+/// nothing more and the reader's position is unspecified.
+#[derive(Debug)]
+pub struct TensorInfoReader<'r, R> {
+    reader: CountingReader<'r, R>,
+    remaining: u64,
+    options: ParseOptions,
+    poisoned: bool,
+}
+
+impl<'r, R: Read> TensorInfoReader<'r, R> {
+    /// Read `tensor_count` tensor infos from `reader`
+    pub fn new(reader: &'r mut R, tensor_count: u64) -> Self {
+        Self::with_options(reader, tensor_count, &ParseOptions::default())
+    }
+
+    /// Read tensor infos using custom parse options
+    pub fn with_options(reader: &'r mut R, tensor_count: u64, options: &ParseOptions) -> Self {
+        Self {
+            reader: CountingReader { reader, count: 0 },
+            remaining: tensor_count,
+            options: options.clone(),
+            poisoned: false,
+        }
+    }
+
+    /// Number of tensor infos not yet read
+    pub fn remaining(&self) -> u64 {
+        self.remaining
+    }
+
+    /// Bytes read so far, which added to the start of the tensor info section
+    /// gives the position of the next unread entry
+    pub fn bytes_consumed(&self) -> u64 {
+        self.reader.count
+    }
+
+    /// Check whether an error stopped the iteration
+    pub fn is_poisoned(&self) -> bool {
+        self.poisoned
+    }
+
+    /// Get the reader back, positioned at the next unread entry unless poisoned
+    pub fn into_inner(self) -> &'r mut R {
+        self.reader.reader
+    }
+}
+
+impl<R: Read> Iterator for TensorInfoReader<'_, R> {
+    type Item = Result<TensorInfo>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.poisoned || self.remaining == 0 {
+            return None;
+        }
+        let tensor = TensorInfo::read_with_options(&mut self.reader, &self.options);
+        match tensor {
+            Ok(_) => self.remaining -= 1,
+            Err(_) => self.poisoned = true,
+        }
+        Some(tensor)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.poisoned {
+            return (0, Some(0));
+        }
+        let remaining = usize::try_from(self.remaining).unwrap_or(usize::MAX);
+        (0, Some(remaining))
+    }
+}
+
+/// Counts the bytes read through it
+#[derive(Debug)]
+struct CountingReader<'r, R> {
+    reader: &'r mut R,
+    count: u64,
+}
+
+impl<R: Read> Read for CountingReader<'_, R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<()> {
+        self.reader.read_exact(buf)?;
+        self.count += buf.len() as u64;
+        Ok(())
+    }
+}
@@ -6,8 +6,140 @@ use crate::*;
 use std::path::Path;
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
     use super::*;
+    use std::io::{Cursor, Seek};
+    use std::sync::Arc;
+
+    /// Counts allocations made by the current thread while `count_allocations` runs
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: std::cell::Cell<Option<usize>> = const { std::cell::Cell::new(None) };
+    }
+
+    unsafe impl std::alloc::GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get().map(|n| n + 1)));
+            unsafe { std::alloc::System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+            unsafe { std::alloc::System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+        ALLOCATIONS.with(|count| count.set(Some(0)));
+        let result = f();
+        let count = ALLOCATIONS.with(|count| count.take().unwrap());
+        (result, count)
+    }
+
+    fn write_string(buf: &mut Vec<u8>, s: &str) {
+        buf.extend_from_slice(&(s.len() as u64).to_le_bytes());
+        buf.extend_from_slice(s.as_bytes());
+    }
+
+    fn write_value(buf: &mut Vec<u8>, value: &GgufValue) {
+        match value {
+            GgufValue::Uint8(v) => buf.push(*v),
+            GgufValue::Int8(v) => buf.push(*v as u8),
+            GgufValue::Uint16(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Int16(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Uint32(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Int32(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Float32(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Bool(v) => buf.push(*v as u8),
+            GgufValue::String(v) => write_string(buf, v),
+            GgufValue::Array(items) => {
+                let element_type = items.first().map(GgufValue::value_type).unwrap_or(GgufValueType::Uint8);
+                buf.extend_from_slice(&(element_type as u32).to_le_bytes());
+                buf.extend_from_slice(&(items.len() as u64).to_le_bytes());
+                for item in items {
+                    write_value(buf, item);
+                }
+            }
+            GgufValue::Uint64(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Int64(v) => buf.extend_from_slice(&v.to_le_bytes()),
+            GgufValue::Float64(v) => buf.extend_from_slice(&v.to_le_bytes()),
+        }
+    }
+
+    fn write_kv(buf: &mut Vec<u8>, key: &str, value: &GgufValue) {
+        write_string(buf, key);
+        buf.extend_from_slice(&(value.value_type() as u32).to_le_bytes());
+        write_value(buf, value);
+    }
+
+    fn write_tensor_info(buf: &mut Vec<u8>, tensor: &TensorInfo) {
+        write_string(buf, &tensor.name);
+        buf.extend_from_slice(&(tensor.dimensions.len() as u32).to_le_bytes());
+        for dim in &tensor.dimensions {
+            buf.extend_from_slice(&dim.to_le_bytes());
+        }
+        buf.extend_from_slice(&tensor.quantization_type.id().to_le_bytes());
+        buf.extend_from_slice(&tensor.offset.to_le_bytes());
+    }
+
+    /// Build an in-memory GGUF v3 file from metadata pairs and tensor infos
+    fn build_gguf(metadata: &[(&str, GgufValue)], tensors: &[TensorInfo]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"GGUF");
+        buf.extend_from_slice(&3u32.to_le_bytes());
+        buf.extend_from_slice(&(tensors.len() as u64).to_le_bytes());
+        buf.extend_from_slice(&(metadata.len() as u64).to_le_bytes());
+        for (key, value) in metadata {
+            write_kv(&mut buf, key, value);
+        }
+        for tensor in tensors {
+            write_tensor_info(&mut buf, tensor);
+        }
+        buf
+    }
+
+    fn tensor(name: &str, dimensions: &[u64], quantization_type: QuantizationType) -> TensorInfo {
+        TensorInfo {
+            name: name.to_string(),
+            dimensions: dimensions.to_vec(),
+            quantization_type,
+            offset: 0,
+        }
+    }
+
+    fn sample_metadata() -> Vec<(&'static str, GgufValue)> {
+        vec![
+            ("general.architecture", GgufValue::String("llama".to_string())),
+            ("general.name", GgufValue::String("Tiny".to_string())),
+            ("llama.context_length", GgufValue::Uint32(2048)),
+            ("llama.block_count", GgufValue::Uint32(2)),
+            ("llama.embedding_length", GgufValue::Uint32(64)),
+            ("llama.feed_forward_length", GgufValue::Uint32(256)),
+            ("llama.attention.head_count", GgufValue::Uint32(4)),
+            ("llama.attention.head_count_kv", GgufValue::Uint32(2)),
+            ("tokenizer.ggml.model", GgufValue::String("llama".to_string())),
+            (
+                "tokenizer.ggml.tokens",
+                GgufValue::Array(vec![
+                    GgufValue::String("<s>".to_string()),
+                    GgufValue::String("</s>".to_string()),
+                    GgufValue::String("a".to_string()),
+                ]),
+            ),
+            (
+                "tokenizer.ggml.scores",
+                GgufValue::Array(vec![
+                    GgufValue::Float32(0.0),
+                    GgufValue::Float32(-1.0),
+                    GgufValue::Float32(-2.0),
+                ]),
+            ),
+        ]
+    }
 
     #[test]
     fn test_tinyllama_gguf_parsing() {
@@ -26,7 +158,7 @@ mod tests {
         
         // Print all metadata keys for debugging
         println!("Metadata keys:");
-        for key in gguf_file.metadata.data.keys() {
+        for key in gguf_file.metadata.keys() {
             println!("  {}", key);
         }
         
@@ -48,12 +180,12 @@ mod tests {
         
         // Print some tensor info
         println!("Total tensors: {}", gguf_file.tensors.len());
-        println!("Total size: {} MB", gguf_file.total_size() / 1024 / 1024);
+        println!("Total size: {} MB", gguf_file.total_size().unwrap() / 1024 / 1024);
         
         for (i, tensor) in gguf_file.tensors.iter().take(5).enumerate() {
             println!("Tensor {}: {} {} {:?} ({} bytes)", 
                 i, tensor.name, tensor.shape_string(), 
-                tensor.quantization_type, tensor.size_bytes());
+                tensor.quantization_type, tensor.size_bytes().unwrap());
         }
     }
 
@@ -107,10 +239,10 @@ mod tests {
 
     #[test]
     fn test_quantization_type_bits() {
-        assert_eq!(QuantizationType::F32.bits_per_weight(), 32.0);
-        assert_eq!(QuantizationType::Q4_0.bits_per_weight(), 4.5);
-        assert_eq!(QuantizationType::Q8_0.bits_per_weight(), 8.5);
-        assert_eq!(QuantizationType::Q2_K.bits_per_weight(), 2.5625);
+        assert_eq!(QuantizationType::F32.bits_per_weight(), Some(32.0));
+        assert_eq!(QuantizationType::Q4_0.bits_per_weight(), Some(4.5));
+        assert_eq!(QuantizationType::Q8_0.bits_per_weight(), Some(8.5));
+        assert_eq!(QuantizationType::Q2_K.bits_per_weight(), Some(2.625));
         
         assert!(QuantizationType::Q4_0.is_quantized());
         assert!(!QuantizationType::F32.is_quantized());
@@ -128,5 +260,3479 @@ mod tests {
         assert_eq!(tensor.layer_number(), Some(15));
         assert!(tensor.is_weight_tensor());
         assert_eq!(tensor.shape_string(), "[4096, 4096]");
+
+        // The layer component must be a whole name component of digits
+        let mut renamed = tensor.clone();
+        for (name, layer) in [("model.layers.12.mlp.up_proj.weight", Some(12)), ("sublk.3.weight", None), ("blk.+3.weight", None)] {
+            renamed.name = name.to_string();
+            assert_eq!(renamed.layer_number(), layer, "{name}");
+        }
+    }
+
+    #[test]
+    fn test_read_selected_metadata() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+
+        let mut full_reader = Cursor::new(&bytes);
+        let header = GgufHeader::read(&mut full_reader).unwrap();
+        let full = GgufMetadata::read(&mut full_reader, header.metadata_kv_count).unwrap();
+
+        let mut reader = Cursor::new(&bytes);
+        let header = GgufHeader::read(&mut reader).unwrap();
+        let selected = GgufMetadata::read_keys(
+            &mut reader,
+            header.metadata_kv_count,
+            &["general.architecture", "llama.block_count"],
+        )
+        .unwrap();
+
+        assert_eq!(selected.len(), 2);
+        assert_eq!(selected.get_string("general.architecture").unwrap(), "llama");
+        assert_eq!(selected.get_u32("llama.block_count").unwrap(), 2);
+        assert!(selected.get("tokenizer.ggml.tokens").is_none());
+        assert!(selected.get("tokenizer.ggml.scores").is_none());
+        assert_eq!(reader.stream_position().unwrap(), full_reader.stream_position().unwrap());
+        assert_eq!(full.len(), sample_metadata().len());
+    }
+
+    #[test]
+    fn test_keys_with_prefix() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+
+        let mut keys: Vec<_> = gguf_file.metadata.keys_with_prefix("tokenizer.").collect();
+        keys.sort();
+        assert_eq!(keys, vec!["tokenizer.ggml.model", "tokenizer.ggml.scores", "tokenizer.ggml.tokens"]);
+        assert_eq!(gguf_file.metadata.keys_with_prefix("clip.").count(), 0);
+    }
+
+    #[test]
+    fn test_section_byte_counts() {
+        let metadata = sample_metadata();
+        let tensors = vec![tensor("token_embd.weight", &[64, 3], QuantizationType::F16)];
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        let mut metadata_section = Vec::new();
+        for (key, value) in &metadata {
+            write_kv(&mut metadata_section, key, value);
+        }
+        let mut tensor_section = Vec::new();
+        write_tensor_info(&mut tensor_section, &tensors[0]);
+
+        assert_eq!(gguf_file.header_bytes(), GgufHeader::SIZE as u64);
+        assert_eq!(gguf_file.metadata_bytes(), metadata_section.len() as u64);
+        assert_eq!(gguf_file.tensor_info_bytes(), tensor_section.len() as u64);
+        assert_eq!(
+            gguf_file.header_bytes() + gguf_file.metadata_bytes() + gguf_file.tensor_info_bytes(),
+            bytes.len() as u64
+        );
+
+        // 8-byte length prefix plus "llama"
+        assert_eq!(gguf_file.metadata.value_size("general.architecture"), Some(13));
+        // element type + count + three f32 scores
+        assert_eq!(gguf_file.metadata.value_size("tokenizer.ggml.scores"), Some(4 + 8 + 12));
+        assert_eq!(gguf_file.metadata.value_size("missing.key"), None);
+    }
+
+    #[test]
+    fn test_provenance_fields() {
+        let mut metadata = sample_metadata();
+        metadata.extend([
+            ("general.author", GgufValue::String("Jane".to_string())),
+            ("general.url", GgufValue::String("https://example.com/tiny".to_string())),
+            ("general.source.url", GgufValue::String("https://example.com/src".to_string())),
+            ("general.organization", GgufValue::String("Example".to_string())),
+            ("general.base_model.count", GgufValue::Uint32(2)),
+            ("general.base_model.0.name", GgufValue::String("Base A".to_string())),
+            ("general.base_model.1.name", GgufValue::String("Base B".to_string())),
+        ]);
+        let bytes = build_gguf(&metadata, &[]);
+        let config = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap().model_config().unwrap();
+
+        assert_eq!(config.general_author.as_deref(), Some("Jane"));
+        assert_eq!(config.general_url.as_deref(), Some("https://example.com/tiny"));
+        assert_eq!(config.general_source_url.as_deref(), Some("https://example.com/src"));
+        assert_eq!(config.general_organization.as_deref(), Some("Example"));
+        let names: Vec<_> = config.base_models.iter().map(|m| m.name.as_deref()).collect();
+        assert_eq!(names, vec![Some("Base A"), Some("Base B")]);
+    }
+
+    #[test]
+    fn test_well_known_key_constants() {
+        assert_eq!(keys::GENERAL_ARCHITECTURE, "general.architecture");
+        assert_eq!(keys::TOKENIZER_GGML_TOKENS, "tokenizer.ggml.tokens");
+        assert_eq!(keys::TOKENIZER_GGML_BOS_TOKEN_ID, "tokenizer.ggml.bos_token_id");
+        assert_eq!(keys::TOKENIZER_CHAT_TEMPLATE, "tokenizer.chat_template");
+        assert_eq!(keys::SPLIT_COUNT, "split.count");
+        assert_eq!(keys::arch_key("llama", keys::BLOCK_COUNT), "llama.block_count");
+        assert_eq!(
+            keys::arch_key("llama", keys::ATTENTION_HEAD_COUNT_KV),
+            "llama.attention.head_count_kv"
+        );
+        assert_eq!(keys::base_model_key(1, keys::ENTRY_NAME), "general.base_model.1.name");
+        assert_eq!(keys::field_aliases("llama", "vocab_size"), ["general.vocab_size", "llama.vocab_size"]);
+        assert!(keys::KeyAlias::Arch(keys::BLOCK_COUNT).matches("qwen2", "qwen2.block_count"));
+        assert!(!keys::KeyAlias::Arch(keys::BLOCK_COUNT).matches("qwen", "qwen2.block_count"));
+
+        // Every key in the sample file is well known
+        let mut metadata = sample_metadata();
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert!(gguf_file.metadata.contains(keys::GENERAL_ARCHITECTURE));
+        assert!(gguf_file.metadata.known_unknown_keys().is_empty());
+
+        metadata.push(("llama.brand_new_key", GgufValue::Uint32(1)));
+        metadata.push(("vendor.custom", GgufValue::Bool(true)));
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(gguf_file.metadata.known_unknown_keys(), vec!["llama.brand_new_key", "vendor.custom"]);
+    }
+
+    #[test]
+    fn test_base_models_and_datasets() {
+        let mut metadata = sample_metadata();
+        metadata.extend([
+            ("general.base_model.count", GgufValue::Uint32(2)),
+            ("general.base_model.0.name", GgufValue::String("Llama 2 7b".to_string())),
+            ("general.base_model.0.organization", GgufValue::String("Meta Llama".to_string())),
+            ("general.base_model.0.repo_url", GgufValue::String("https://huggingface.co/meta-llama/Llama-2-7b".to_string())),
+            ("general.base_model.0.version", GgufValue::String("2".to_string())),
+            ("general.base_model.1.name", GgufValue::String("Adapter".to_string())),
+            ("general.dataset.count", GgufValue::Uint32(1)),
+            ("general.dataset.0.name", GgufValue::String("SlimPajama".to_string())),
+            ("general.dataset.0.organization", GgufValue::String("Cerebras".to_string())),
+        ]);
+        let bytes = build_gguf(&metadata, &[]);
+        let config = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap().model_config().unwrap();
+
+        assert_eq!(config.base_models.len(), 2);
+        assert_eq!(
+            config.base_models[0],
+            BaseModel {
+                name: Some("Llama 2 7b".to_string()),
+                organization: Some("Meta Llama".to_string()),
+                repo_url: Some("https://huggingface.co/meta-llama/Llama-2-7b".to_string()),
+                version: Some("2".to_string()),
+            }
+        );
+        assert_eq!(config.base_models[1].name.as_deref(), Some("Adapter"));
+        assert_eq!(config.base_models[1].organization, None);
+
+        assert_eq!(config.datasets.len(), 1);
+        assert_eq!(config.datasets[0].name.as_deref(), Some("SlimPajama"));
+        assert_eq!(config.datasets[0].organization.as_deref(), Some("Cerebras"));
+    }
+
+    #[test]
+    fn test_array_length_guard() {
+        // Uint8 array claiming 10^12 elements with only a few bytes behind it
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(GgufValueType::Uint8 as u32).to_le_bytes());
+        bytes.extend_from_slice(&1_000_000_000_000u64.to_le_bytes());
+        bytes.extend_from_slice(&[1, 2, 3]);
+
+        let err = GgufValue::read(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap_err();
+        assert!(matches!(
+            err,
+            GgufError::ArrayTooLarge { element_type: GgufValueType::Uint8, length: 1_000_000_000_000, .. }
+        ));
+        let err = GgufValue::skip(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap_err();
+        assert!(matches!(err, GgufError::ArrayTooLarge { .. }));
+
+        // Strings need at least their 8-byte length prefix each
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(GgufValueType::String as u32).to_le_bytes());
+        bytes.extend_from_slice(&4u64.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 24]);
+        let err = GgufValue::read(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap_err();
+        assert!(matches!(err, GgufError::ArrayTooLarge { min_bytes: 32, available: 24, .. }));
+    }
+
+    #[test]
+    fn test_array_nesting_guard() {
+        fn nested(levels: usize) -> GgufValue {
+            let mut value = GgufValue::Array(vec![GgufValue::Uint8(7)]);
+            for _ in 1..levels {
+                value = GgufValue::Array(vec![value]);
+            }
+            value
+        }
+
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &nested(MAX_ARRAY_DEPTH));
+        let value = GgufValue::read(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap();
+        assert!(matches!(value, GgufValue::Array(_)));
+
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &nested(MAX_ARRAY_DEPTH + 1));
+        let err = GgufValue::read(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap_err();
+        assert!(matches!(err, GgufError::ArrayNestingTooDeep(MAX_ARRAY_DEPTH)));
+        let err = GgufValue::skip(&mut Cursor::new(&bytes), GgufValueType::Array).unwrap_err();
+        assert!(matches!(err, GgufError::ArrayNestingTooDeep(_)));
+    }
+
+    #[test]
+    fn test_quantization_family() {
+        assert_eq!(QuantizationType::F16.family(), QuantFamily::Float);
+        assert_eq!(QuantizationType::I32.family(), QuantFamily::Integer);
+        assert_eq!(QuantizationType::Q4_0.family(), QuantFamily::Legacy);
+        assert_eq!(QuantizationType::Q6_K.family(), QuantFamily::KQuant);
+        assert_eq!(QuantizationType::IQ4_XS.family(), QuantFamily::IQuant);
+    }
+
+    #[test]
+    fn test_lenient_parsing_recovers_bad_value_type() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F16),
+            tensor("output.weight", &[64, 3], QuantizationType::Q8_0),
+        ];
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"GGUF");
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        bytes.extend_from_slice(&(tensors.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&3u64.to_le_bytes());
+        write_kv(&mut bytes, "general.architecture", &GgufValue::String("llama".to_string()));
+        let bad_type_offset = bytes.len() as u64 + 8 + "bad.key".len() as u64;
+        write_string(&mut bytes, "bad.key");
+        bytes.extend_from_slice(&99u32.to_le_bytes());
+        bytes.extend_from_slice(&[0xff; 4]);
+        write_kv(&mut bytes, "llama.block_count", &GgufValue::Uint32(2));
+        for tensor in &tensors {
+            write_tensor_info(&mut bytes, tensor);
+        }
+
+        assert!(GgufFile::from_reader(&mut Cursor::new(&bytes)).is_err());
+
+        let (partial, errors) = GgufFile::from_reader_lenient(&mut Cursor::new(&bytes));
+        assert_eq!(errors.len(), 1);
+        match &errors[0] {
+            GgufError::AtOffset { offset, source } => {
+                assert_eq!(*offset, bad_type_offset);
+                assert!(matches!(**source, GgufError::InvalidValueType(99)));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+
+        assert!(partial.header_complete);
+        assert!(!partial.metadata_complete);
+        assert!(partial.tensors_complete);
+        assert!(!partial.is_complete());
+        assert_eq!(partial.malformed_keys, vec!["bad.key"]);
+        assert_eq!(partial.metadata.get("bad.key"), Some(&GgufValue::Array(Vec::new())));
+        assert_eq!(partial.metadata.get_string("general.architecture").unwrap(), "llama");
+        assert_eq!(partial.metadata.get_u32("llama.block_count").unwrap(), 2);
+        assert_eq!(partial.tensors.len(), 2);
+        assert_eq!(partial.tensors[1].name, "output.weight");
+
+        // A value over the limits is skipped and replaced by its type's zero
+        let mut metadata = sample_metadata();
+        metadata.push(("general.description", GgufValue::String("long ".repeat(20))));
+        let bytes = build_gguf(&metadata, &tensors);
+        let limits = ParseLimits { max_string_len: 64, ..Default::default() };
+        let options = ParseOptions { limits, ..Default::default() };
+        let (partial, errors) = PartialGgufFile::read_with_options(&mut Cursor::new(&bytes), &options);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].category(), ErrorCategory::Limit);
+        assert_eq!(partial.malformed_keys, vec!["general.description"]);
+        assert_eq!(partial.metadata.get_string("general.description").unwrap(), "");
+        assert_eq!(partial.metadata.get_string("general.name").unwrap(), "Tiny");
+        assert!(partial.tensors_complete);
+    }
+
+    #[test]
+    fn test_lenient_parsing_truncated_header() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let (partial, errors) = GgufFile::from_reader_lenient(&mut Cursor::new(&bytes[..10]));
+
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0], GgufError::AtOffset { offset: 0, .. }));
+        assert!(partial.header.is_none());
+        assert!(!partial.header_complete);
+        assert!(!partial.metadata_complete);
+        assert!(!partial.tensors_complete);
+        assert!(partial.metadata.is_empty());
+        assert!(partial.tensors.is_empty());
+    }
+
+    #[test]
+    fn test_legacy_format_detection() {
+        for (on_disk, name) in [(b"lmgg", "ggml"), (b"fmgg", "ggmf"), (b"tjgg", "ggjt"), (b"algg", "ggla")] {
+            let mut bytes = on_disk.to_vec();
+            bytes.extend_from_slice(&[0u8; 20]);
+
+            match GgufHeader::read(&mut Cursor::new(&bytes)) {
+                Err(GgufError::LegacyFormat { magic }) => assert_eq!(magic, name),
+                other => panic!("unexpected result for {name}: {other:?}"),
+            }
+            assert_eq!(sniff(&mut Cursor::new(&bytes)).unwrap(), FormatKind::LegacyGgml(name));
+        }
+
+        // Plain-text magic as reported in bug reports
+        let err = GgufHeader::read(&mut Cursor::new(b"ggml\0\0\0\0")).unwrap_err();
+        assert!(matches!(err, GgufError::LegacyFormat { .. }));
+
+        let zip = b"PK\x03\x04\x14\x00\x00\x00\x08";
+        assert_eq!(sniff(&mut Cursor::new(zip)).unwrap(), FormatKind::Zip);
+        assert!(matches!(GgufHeader::read(&mut Cursor::new(zip)), Err(GgufError::InvalidMagic(_))));
+
+        let mut safetensors = 64u64.to_le_bytes().to_vec();
+        safetensors.extend_from_slice(b"{\"__metadata__\":{}}");
+        assert_eq!(sniff(&mut Cursor::new(&safetensors)).unwrap(), FormatKind::Safetensors);
+
+        let gguf = build_gguf(&sample_metadata(), &[]);
+        assert_eq!(sniff(&mut Cursor::new(&gguf)).unwrap(), FormatKind::Gguf(3));
+        assert_eq!(sniff(&mut Cursor::new(b"GG")).unwrap(), FormatKind::Unknown);
+    }
+
+    #[test]
+    fn test_gguf_with_misleading_extension() {
+        let path = std::env::temp_dir().join(format!("aiogguf-{}-model.bin", std::process::id()));
+        std::fs::write(&path, build_gguf(&sample_metadata(), &[])).unwrap();
+
+        assert!(is_gguf(&path));
+        let gguf_file = GgufFile::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(gguf_file.unwrap().metadata.get_string("general.architecture").unwrap(), "llama");
+        assert!(!is_gguf(Path::new("/nonexistent/model.gguf")));
+    }
+
+    #[test]
+    fn test_integer_tensors_are_not_quantized() {
+        assert!(!QuantizationType::I32.is_quantized());
+        assert!(QuantizationType::I32.is_integer());
+        assert!(QuantizationType::F16.is_float());
+        assert!(!QuantizationType::Q4_K.is_float() && !QuantizationType::Q4_K.is_integer());
+
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F16),
+            tensor("positions", &[16], QuantizationType::I32),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert!(!gguf_file.is_quantized());
+    }
+
+    #[test]
+    fn test_stream_tensor_infos() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F16),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q4_K),
+            tensor("blk.1.attn_q.weight", &[64, 64], QuantizationType::Q4_K),
+        ];
+        let mut bytes = Vec::new();
+        for tensor in &tensors {
+            write_tensor_info(&mut bytes, tensor);
+        }
+
+        let mut reader = Cursor::new(&bytes);
+        let quantized = TensorInfo::iter_from_reader(&mut reader, tensors.len() as u64)
+            .map(|t| t.unwrap())
+            .filter(|t| t.quantization_type.is_quantized())
+            .count();
+        assert_eq!(quantized, 2);
+        assert_eq!(reader.position(), bytes.len() as u64);
+
+        // A truncated entry yields one error and then stops
+        let mut reader = Cursor::new(&bytes[..bytes.len() - 4]);
+        let results: Vec<_> = TensorInfo::iter_from_reader(&mut reader, 5).collect();
+        assert_eq!(results.len(), 3);
+        assert!(results[2].is_err());
+    }
+
+    #[test]
+    fn test_infer_config_from_tensors() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[256, 1000], QuantizationType::F16),
+            tensor("blk.0.attn_q.weight", &[256, 256], QuantizationType::Q4_K),
+            tensor("blk.0.attn_k.weight", &[256, 128], QuantizationType::Q4_K),
+            tensor("blk.0.attn_v.weight", &[256, 128], QuantizationType::Q4_K),
+            tensor("blk.0.ffn_up.weight", &[256, 704], QuantizationType::Q4_K),
+            tensor("blk.1.attn_q.weight", &[256, 256], QuantizationType::Q4_K),
+            tensor("blk.2.attn_q.weight", &[256, 256], QuantizationType::Q4_K),
+            tensor("output.weight", &[256, 1000], QuantizationType::Q6_K),
+        ];
+        let bytes = build_gguf(&[], &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+
+        assert!(gguf_file.model_config().is_err());
+        let config = gguf_file.model_config_inferred().unwrap();
+        assert_eq!(config.architecture, "llama");
+        assert_eq!(config.block_count, 3);
+        assert_eq!(config.embedding_length, 256);
+        assert_eq!(config.vocab_size, 1000);
+        assert_eq!(config.feed_forward_length, 704);
+        assert_eq!(config.attention_head_count, 2);
+        assert_eq!(config.attention_head_count_kv, Some(1));
+        assert_eq!(config.context_length, DEFAULT_CONTEXT_LENGTH);
+        for field in ["architecture", "block_count", "embedding_length", "vocab_size", "context_length"] {
+            assert!(config.inferred_fields.iter().any(|f| f == field), "{field} not marked inferred");
+        }
+
+        // Metadata values are kept and not marked as inferred
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        let config = gguf_file.model_config_inferred().unwrap();
+        assert_eq!(config.block_count, 2);
+        assert_eq!(config.vocab_size, 3);
+        assert!(config.inferred_fields.is_empty());
+
+        let ssm = vec![tensor("blk.0.ssm_in.weight", &[256, 1024], QuantizationType::F16)];
+        let bytes = build_gguf(&[], &ssm);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        let err = gguf_file.model_config_inferred().unwrap_err();
+        assert!(matches!(err, GgufError::IncompleteModelConfig(_)));
+
+        // A block index of u64::MAX has no count, rather than overflowing
+        let mut huge = tensors.clone();
+        huge.push(tensor(&format!("blk.{}.attn_q.weight", u64::MAX), &[256, 256], QuantizationType::Q4_K));
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&[], &huge))).unwrap();
+        assert_eq!(
+            gguf_file.model_config_inferred().unwrap_err(),
+            GgufError::IncompleteModelConfig("block_count".to_string())
+        );
+    }
+
+    #[test]
+    fn test_header_size_constant() {
+        assert_eq!(GgufHeader::SIZE, 24);
+    }
+
+    #[test]
+    fn test_vocab_size_fallback_chain() {
+        let metadata: Vec<_> = sample_metadata()
+            .into_iter()
+            .filter(|(key, _)| !key.starts_with("tokenizer.ggml.tokens"))
+            .collect();
+        let tensors = vec![tensor("token_embd.weight", &[64, 32000], QuantizationType::F16)];
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+
+        // Without tensors there is nothing left to fall back to
+        assert!(matches!(
+            ModelConfig::from_metadata(&gguf_file.metadata),
+            Err(GgufError::IncompleteModelConfig(field)) if field == "vocab_size"
+        ));
+        let config = ModelConfig::from_gguf(&gguf_file).unwrap();
+        assert_eq!(config.vocab_size, 32000);
+        assert!(gguf_file.warnings().is_empty());
+
+        // The token array wins over the embedding tensor
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(ModelConfig::from_gguf(&gguf_file).unwrap().vocab_size, 3);
+
+        // The explicit key wins over the token array, with a warning
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.vocab_size", GgufValue::Uint32(5)));
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let config = ModelConfig::from_gguf(&gguf_file).unwrap();
+        assert_eq!(config.vocab_size, 5);
+        let mismatch = ParseWarning::VocabSizeMismatch { vocab_size: 5, tokens: 3 };
+        assert_eq!(gguf_file.warnings()[0].warning, mismatch);
+        assert_eq!(gguf_file.warnings()[0].subject(), "tokenizer.ggml.tokens");
+        let options = ParseOptions { warnings_as_errors: vec![WarningKind::VocabSizeMismatch], ..Default::default() };
+        assert!(matches!(
+            GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options),
+            Err(GgufError::PromotedWarning(promoted)) if promoted.warning == mismatch
+        ));
+    }
+
+    #[test]
+    fn test_metadata_section_len() {
+        let tensors = vec![tensor("token_embd.weight", &[64, 3], QuantizationType::F16)];
+        let mut bytes = build_gguf(&sample_metadata(), &tensors);
+        let info_end = bytes.len() as u64;
+        let padded = info_end.div_ceil(DEFAULT_ALIGNMENT) * DEFAULT_ALIGNMENT;
+        bytes.resize(padded as usize, 0);
+
+        let mut reader = Cursor::new(&bytes);
+        let gguf_file = GgufFile::from_reader(&mut reader).unwrap();
+        assert_eq!(reader.position(), info_end);
+        assert_eq!(gguf_file.metadata_section_len(), padded);
+        assert_eq!(gguf_file.data_offset() % DEFAULT_ALIGNMENT, 0);
+
+        // A custom alignment is honored
+        let mut metadata = sample_metadata();
+        metadata.push(("general.alignment", GgufValue::Uint32(256)));
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(gguf_file.metadata_section_len(), (bytes.len() as u64).div_ceil(256) * 256);
+    }
+
+    fn llama_config(d: u32, heads: u32, kv_heads: u32, ff: u32, blocks: u32, vocab: u32) -> ModelConfig {
+        let entries = [
+            ("general.architecture", GgufValue::String("llama".to_string())),
+            ("llama.vocab_size", GgufValue::Uint32(vocab)),
+            ("llama.context_length", GgufValue::Uint32(4096)),
+            ("llama.block_count", GgufValue::Uint32(blocks)),
+            ("llama.embedding_length", GgufValue::Uint32(d)),
+            ("llama.feed_forward_length", GgufValue::Uint32(ff)),
+            ("llama.attention.head_count", GgufValue::Uint32(heads)),
+            ("llama.attention.head_count_kv", GgufValue::Uint32(kv_heads)),
+        ];
+        let metadata: GgufMetadata = entries.into_iter().collect();
+        ModelConfig::from_metadata(&metadata).unwrap()
+    }
+
+    fn assert_within_percent(estimate: u64, expected: u64, percent: f64) {
+        let error = (estimate as f64 - expected as f64).abs() / expected as f64 * 100.0;
+        assert!(error < percent, "estimate {estimate} is {error:.2}% off {expected}");
+    }
+
+    #[test]
+    fn test_estimated_param_count_known_models() {
+        let llama2_7b = llama_config(4096, 32, 32, 11008, 32, 32000);
+        assert_within_percent(llama2_7b.estimated_param_count(false), 6_738_415_616, 1.0);
+
+        let llama3_8b = llama_config(4096, 32, 8, 14336, 32, 128256);
+        assert_within_percent(llama3_8b.estimated_param_count(false), 8_030_261_248, 1.0);
+
+        let tinyllama = llama_config(2048, 32, 4, 5632, 22, 32000);
+        assert_within_percent(tinyllama.estimated_param_count(false), 1_100_048_384, 1.0);
+
+        // Tied embeddings drop the output projection
+        assert_eq!(
+            tinyllama.estimated_param_count(false) - tinyllama.estimated_param_count(true),
+            32000 * 2048
+        );
+    }
+
+    #[test]
+    fn test_quantization_outliers() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::Q4_K),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_k.weight", &[64, 64], QuantizationType::Q4_K),
+            tensor("output.weight", &[64, 3], QuantizationType::Q6_K),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap();
+
+        assert_eq!(gguf_file.dominant_quantization_type(), Some(QuantizationType::Q4_K));
+        let outliers = gguf_file.quantization_outliers();
+        assert_eq!(outliers.len(), 1);
+        assert_eq!(outliers[0].name, "output.weight");
+    }
+
+    #[test]
+    fn test_value_type_sizes() {
+        let cases = [
+            (GgufValue::Uint8(1), GgufValueType::Uint8, Some(1)),
+            (GgufValue::Int8(-1), GgufValueType::Int8, Some(1)),
+            (GgufValue::Uint16(1), GgufValueType::Uint16, Some(2)),
+            (GgufValue::Int16(-1), GgufValueType::Int16, Some(2)),
+            (GgufValue::Uint32(1), GgufValueType::Uint32, Some(4)),
+            (GgufValue::Int32(-1), GgufValueType::Int32, Some(4)),
+            (GgufValue::Float32(1.5), GgufValueType::Float32, Some(4)),
+            (GgufValue::Bool(true), GgufValueType::Bool, Some(1)),
+            (GgufValue::Uint64(1), GgufValueType::Uint64, Some(8)),
+            (GgufValue::Int64(-1), GgufValueType::Int64, Some(8)),
+            (GgufValue::Float64(1.5), GgufValueType::Float64, Some(8)),
+            (GgufValue::String("abc".to_string()), GgufValueType::String, None),
+            (GgufValue::Array(vec![]), GgufValueType::Array, None),
+        ];
+
+        for (value, value_type, element_size) in cases {
+            assert_eq!(value.value_type(), value_type);
+            assert_eq!(value_type.element_size(), element_size);
+            assert_eq!(value_type.is_fixed_size(), element_size.is_some());
+
+            // serialized_size must match what actually hits the disk
+            let mut bytes = Vec::new();
+            write_value(&mut bytes, &value);
+            assert_eq!(value.serialized_size(), bytes.len() as u64, "{value:?}");
+            if let Some(size) = element_size {
+                assert_eq!(value.serialized_size(), size as u64);
+            }
+        }
+
+        assert_eq!(GgufValue::String("abc".to_string()).serialized_size(), 8 + 3);
+        assert_eq!(GgufValue::Array(vec![]).serialized_size(), 4 + 8);
+        assert_eq!(
+            GgufValue::Array(vec![GgufValue::Uint16(1), GgufValue::Uint16(2)]).serialized_size(),
+            4 + 8 + 2 * 2
+        );
+
+        let nested = GgufValue::Array(vec![
+            GgufValue::Array(vec![GgufValue::String("a".to_string()), GgufValue::String("bc".to_string())]),
+            GgufValue::Array(vec![]),
+        ]);
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &nested);
+        assert_eq!(nested.serialized_size(), (4 + 8) + (4 + 8 + 9 + 10) + (4 + 8));
+        assert_eq!(nested.serialized_size(), bytes.len() as u64);
+    }
+
+    #[test]
+    fn test_io_error_stage() {
+        let tensors = vec![tensor("token_embd.weight", &[64, 3], QuantizationType::F16)];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+
+        let stage_of = |len: usize| match GgufFile::from_reader(&mut Cursor::new(&bytes[..len])) {
+            Err(GgufError::UnexpectedEofAt { stage }) => stage,
+            other => panic!("unexpected result: {other:?}"),
+        };
+        assert_eq!(stage_of(10), "header");
+        assert_eq!(stage_of(GgufHeader::SIZE + 4), "metadata");
+        assert_eq!(stage_of(bytes.len() - 4), "tensor info");
+    }
+
+    #[test]
+    fn test_value_equality() {
+        // Exact equality compares floats bitwise
+        assert_eq!(GgufValue::Float32(f32::NAN), GgufValue::Float32(f32::NAN));
+        assert_eq!(GgufValue::Float64(f64::NAN), GgufValue::Float64(f64::NAN));
+        assert_ne!(GgufValue::Float32(0.0), GgufValue::Float32(-0.0));
+        assert_ne!(GgufValue::Uint32(5), GgufValue::Uint64(5));
+
+        // Loose equality compares across widths
+        assert!(GgufValue::Uint32(5).loosely_equals(&GgufValue::Uint64(5)));
+        assert!(GgufValue::Int8(-1).loosely_equals(&GgufValue::Int64(-1)));
+        assert!(!GgufValue::Int8(-1).loosely_equals(&GgufValue::Uint8(255)));
+        assert!(GgufValue::Float32(1e-5).loosely_equals(&GgufValue::Float64(1e-5)));
+        assert!(GgufValue::Float32(10000.0).loosely_equals(&GgufValue::Uint32(10000)));
+        assert!(!GgufValue::Float32(1.0).loosely_equals(&GgufValue::Float64(1.001)));
+        assert!(GgufValue::Float32(f32::NAN).loosely_equals(&GgufValue::Float64(f64::NAN)));
+        assert!(!GgufValue::Float32(f32::NAN).loosely_equals(&GgufValue::Float64(1.0)));
+        assert!(!GgufValue::String("1".to_string()).loosely_equals(&GgufValue::Uint8(1)));
+
+        let narrow = GgufValue::Array(vec![GgufValue::Uint32(1), GgufValue::Uint32(2)]);
+        let wide = GgufValue::Array(vec![GgufValue::Uint64(1), GgufValue::Uint64(2)]);
+        assert_ne!(narrow, wide);
+        assert!(narrow.loosely_equals(&wide));
+        assert!(!narrow.loosely_equals(&GgufValue::Array(vec![GgufValue::Uint64(1)])));
+        assert_eq!(narrow.clone(), narrow);
+    }
+
+    #[test]
+    fn test_max_tensor_dims_option() {
+        let tensors = vec![tensor("conv3d.weight", &[3, 3, 3, 16, 2], QuantizationType::F32)];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+
+        match GgufFile::from_reader(&mut Cursor::new(&bytes)) {
+            Err(GgufError::InvalidTensorDimensions { name, n_dimensions, max }) => {
+                assert_eq!(name, "conv3d.weight");
+                assert_eq!(n_dimensions, 5);
+                assert_eq!(max, DEFAULT_MAX_TENSOR_DIMS);
+            }
+            other => panic!("unexpected result: {other:?}"),
+        }
+
+        let options = ParseOptions { max_tensor_dims: 5, ..Default::default() };
+        let gguf_file = GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options).unwrap();
+        let tensor = &gguf_file.tensors[0];
+        assert_eq!(tensor.dimensions, vec![3, 3, 3, 16, 2]);
+        assert_eq!(tensor.shape_string(), "[3, 3, 3, 16, 2]");
+        assert_eq!(tensor.size_bytes().unwrap(), 3 * 3 * 3 * 16 * 2 * 4);
+        assert!(tensor.is_weight_tensor());
+        assert_eq!(tensor.layer_number(), None);
+    }
+
+    #[test]
+    fn test_slice_reader() {
+        let tensors = vec![tensor("token_embd.weight", &[64, 3], QuantizationType::F16)];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+
+        let from_slice = GgufFile::from_reader(&mut io::SliceReader::new(&bytes)).unwrap();
+        let from_cursor = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(from_slice.tensors.len(), 1);
+        assert_eq!(from_slice.metadata.len(), from_cursor.metadata.len());
+        assert_eq!(from_slice.data_offset(), from_cursor.data_offset());
+        assert!(GgufFile::from_reader(&mut io::SliceReader::new(&bytes[..30])).is_err());
+    }
+
+    #[test]
+    fn test_clone_and_compare_parsed_file() {
+        let tensors = vec![tensor("token_embd.weight", &[64, 3], QuantizationType::F16)];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        let mut copy = gguf_file.clone();
+        assert_eq!(copy, gguf_file);
+        assert_eq!(copy.header, gguf_file.header);
+        assert_eq!(copy.metadata, gguf_file.metadata);
+        assert_eq!(copy.tensors, gguf_file.tensors);
+
+        copy.tensors[0].offset = 128;
+        assert_ne!(copy, gguf_file);
+    }
+
+    #[test]
+    fn test_dimension_order_helpers() {
+        let embedding = tensor("token_embd.weight", &[2048, 32000], QuantizationType::Q8_0);
+
+        assert_eq!(embedding.shape_ggml(), &[2048, 32000]);
+        assert_eq!(embedding.shape_logical(), vec![32000, 2048]);
+        assert_eq!(embedding.shape_string(), "[2048, 32000]");
+        assert_eq!(embedding.shape_string_ordered(DimOrder::Ggml), "[2048, 32000]");
+        assert_eq!(embedding.shape_string_ordered(DimOrder::RowMajor), "[32000, 2048]");
+        assert_eq!(embedding.num_cols(), 2048);
+        assert_eq!(embedding.num_rows(), 32000);
+        assert!(embedding.is_matrix());
+        assert!(!embedding.is_vector());
+
+        let norm = tensor("output_norm.weight", &[2048], QuantizationType::F32);
+        assert!(norm.is_vector());
+        assert!(!norm.is_matrix());
+        assert_eq!(norm.num_rows(), 1);
+    }
+
+    #[test]
+    fn test_rope_type() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(gguf_file.model_config().unwrap().rope_type, Some(RopeType::Norm));
+
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.rope.type", GgufValue::String("neox".to_string())));
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(gguf_file.model_config().unwrap().rope_type, Some(RopeType::Neox));
+
+        assert_eq!(RopeType::from_code(4), RopeType::GlmRoPE);
+        assert_eq!(RopeType::from_name("yarn"), RopeType::Other("yarn".to_string()));
+        assert_eq!(RopeType::for_architecture("qwen2"), Some(RopeType::Neox));
+        assert_eq!(RopeType::for_architecture("unknown"), None);
+    }
+
+    #[test]
+    fn test_cheap_accessors_with_sparse_metadata() {
+        let bytes = build_gguf(&[("general.architecture", GgufValue::String("clip".to_string()))], &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert!(gguf_file.model_config().is_err());
+        assert_eq!(gguf_file.architecture().unwrap(), "clip");
+        assert_eq!(gguf_file.name(), None);
+        assert_eq!(gguf_file.context_length(), None);
+        assert_eq!(gguf_file.quant_label(), None);
+
+        let mut metadata = sample_metadata();
+        metadata.push(("general.file_type", GgufValue::Uint32(15)));
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(gguf_file.architecture().unwrap(), "llama");
+        assert_eq!(gguf_file.name(), Some("Tiny"));
+        assert_eq!(gguf_file.context_length(), Some(2048));
+        assert_eq!(gguf_file.quant_label(), Some(FileType::Q4_K_M));
+        assert_eq!(FileType::Q4_K_M.label(), "Q4_K_M");
+        assert_eq!(FileType::from(99), FileType::Unknown(99));
+        assert_eq!(FileType::from(99).id(), 99);
+    }
+
+    #[test]
+    fn test_explicit_key_and_value_length() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let config = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().model_config().unwrap();
+        assert_eq!(config.attention_key_length, None);
+        assert_eq!(config.head_dim(), 16);
+
+        // Heads wider than embedding_length / head_count, as in Gemma 7B
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.attention.key_length", GgufValue::Uint32(32)));
+        metadata.push(("llama.attention.value_length", GgufValue::Uint32(32)));
+        let bytes = build_gguf(&metadata, &[]);
+        let wide = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().model_config().unwrap();
+        assert_eq!(wide.attention_key_length, Some(32));
+        assert_eq!(wide.attention_value_length, Some(32));
+        assert_eq!(wide.head_dim(), 32);
+
+        // Attention weights double, everything else is unchanged
+        let attention = |c: &ModelConfig| 2 * 64 * (4 + 2) * c.head_dim() as u64 * c.block_count as u64;
+        assert_eq!(
+            wide.estimated_param_count(true) - config.estimated_param_count(true),
+            attention(&wide) - attention(&config)
+        );
+    }
+
+    #[test]
+    fn test_verify_complete_detects_truncation_and_trailing_data() {
+        let mut second = tensor("b.weight", &[4], QuantizationType::F32);
+        second.offset = 64;
+        let tensors = vec![tensor("a.weight", &[10], QuantizationType::F32), second];
+        let mut bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let data_offset = gguf_file.data_offset();
+
+        // The last tensor ends 80 bytes into the data, 96 once padded
+        bytes.resize(data_offset as usize + 80, 0);
+        assert!(gguf_file.verify_complete(bytes.len() as u64).is_ok());
+        assert!(gguf_file.verify_complete(data_offset + 96).is_ok());
+
+        match gguf_file.verify_complete(data_offset + 50) {
+            Err(GgufError::TruncatedFile { expected, actual, missing }) => {
+                assert_eq!(expected, data_offset + 80);
+                assert_eq!(actual, data_offset + 50);
+                assert_eq!(missing, 30);
+            }
+            other => panic!("expected TruncatedFile, got {other:?}"),
+        }
+        match gguf_file.verify_complete(data_offset + 106) {
+            Err(GgufError::TrailingData { expected, extra, .. }) => {
+                assert_eq!(expected, data_offset + 96);
+                assert_eq!(extra, 10);
+            }
+            other => panic!("expected TrailingData, got {other:?}"),
+        }
+
+        // Opening by path only checks when asked to
+        let path = std::env::temp_dir().join(format!("aiogguf-truncated-{}.gguf", std::process::id()));
+        std::fs::write(&path, &bytes[..data_offset as usize + 50]).unwrap();
+        let strict = ParseOptions { verify_complete: true, ..Default::default() };
+        let lenient = GgufFile::from_file(&path);
+        let checked = GgufFile::from_file_with_options(&path, &strict);
+        std::fs::remove_file(&path).unwrap();
+        assert!(lenient.is_ok());
+        assert!(matches!(checked, Err(GgufError::TruncatedFile { missing: 30, .. })));
+    }
+
+    #[test]
+    fn test_validate_consistent_config() {
+        let mut config = llama_config(4096, 32, 8, 14336, 32, 128256);
+        config.rope_dimension_count = Some(128);
+        config.expert_count = Some(8);
+        config.expert_used_count = Some(2);
+        config.tokenizer_ggml_tokens = Some(vec![String::new(); 128256].into());
+        assert_eq!(config.validate(), vec![]);
+    }
+
+    #[test]
+    fn test_validate_reports_each_rule() {
+        let base = llama_config(4096, 32, 8, 14336, 32, 128256);
+        let warnings_for = |change: fn(&mut ModelConfig)| {
+            let mut config = base.clone();
+            change(&mut config);
+            config.validate()
+        };
+
+        assert_eq!(
+            warnings_for(|c| c.context_length = 0),
+            vec![ConfigWarning::Zero { field: "context_length".to_string() }]
+        );
+        assert_eq!(
+            warnings_for(|c| c.embedding_length = 4100),
+            vec![ConfigWarning::NotDivisible {
+                field: "embedding_length".to_string(),
+                value: 4100,
+                divisor_field: "attention_head_count".to_string(),
+                divisor: 32,
+            }]
+        );
+        // An explicit key length makes the division irrelevant
+        assert_eq!(
+            warnings_for(|c| {
+                c.embedding_length = 4100;
+                c.attention_key_length = Some(128);
+            }),
+            vec![]
+        );
+        assert_eq!(
+            warnings_for(|c| c.attention_head_count_kv = Some(64)),
+            vec![ConfigWarning::ExceedsLimit {
+                field: "attention_head_count_kv".to_string(),
+                value: 64,
+                limit_field: "attention_head_count".to_string(),
+                limit: 32,
+            }]
+        );
+        assert!(matches!(
+            warnings_for(|c| c.attention_head_count_kv = Some(6)).as_slice(),
+            [ConfigWarning::NotDivisible { divisor: 6, .. }]
+        ));
+        assert_eq!(
+            warnings_for(|c| c.rope_dimension_count = Some(64)),
+            vec![ConfigWarning::Mismatch {
+                field: "rope_dimension_count".to_string(),
+                value: 64,
+                other_field: "head_dim".to_string(),
+                other: 128,
+            }]
+        );
+        assert!(matches!(
+            warnings_for(|c| c.tokenizer_ggml_tokens = Some(vec![String::new(); 3].into())).as_slice(),
+            [ConfigWarning::Mismatch { value: 128256, other: 3, .. }]
+        ));
+        let moe = warnings_for(|c| {
+            c.expert_count = Some(8);
+            c.expert_used_count = Some(10);
+        });
+        assert_eq!(moe.len(), 1);
+        assert_eq!(moe[0].to_string(), "expert_used_count (10) exceeds expert_count (8)");
+
+        // Parsed files count the tokens without materializing them
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.vocab_size", GgufValue::Uint32(5)));
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        let config = gguf_file.model_config().unwrap();
+        assert!(config.tokenizer_ggml_tokens.is_none());
+        assert_eq!(config.tokenizer_token_count, Some(3));
+        assert!(config.validate().contains(&ConfigWarning::Mismatch {
+            field: "vocab_size".to_string(),
+            value: 5,
+            other_field: "tokenizer_ggml_tokens".to_string(),
+            other: 3,
+        }));
+    }
+
+    #[test]
+    fn test_unknown_quantization_type_still_parses() {
+        let tensors = vec![tensor("blk.0.ffn_up.weight", &[64, 256], QuantizationType::Unknown(99))];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let tensor = &gguf_file.tensors[0];
+        assert_eq!(tensor.quantization_type, QuantizationType::Unknown(99));
+        assert_eq!(tensor.quantization_type.family(), QuantFamily::Unknown);
+        assert_eq!(tensor.quantization_type.bits_per_weight(), None);
+        assert!(matches!(tensor.size_bytes(), Err(GgufError::UnknownQuantizationSize(99))));
+        assert!(gguf_file.total_size().is_err());
+
+        assert!(QuantizationType::try_from(99).is_err());
+        assert_eq!(QuantizationType::from_id(8), QuantizationType::Q8_0);
+        assert_eq!(QuantizationType::Q8_0.id(), 8);
+    }
+
+    #[test]
+    fn test_ternary_quantization_types() {
+        let tensors = vec![tensor("blk.0.ffn_down.weight", &[2048, 8192], QuantizationType::TQ2_0)];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let tensor = &gguf_file.tensors[0];
+        assert_eq!(tensor.quantization_type, QuantizationType::TQ2_0);
+        assert_eq!(tensor.quantization_type.family(), QuantFamily::Ternary);
+        assert_eq!(tensor.size_bytes().unwrap(), 2048 * 8192 / 256 * 66);
+
+        assert_eq!(QuantizationType::try_from(34).unwrap(), QuantizationType::TQ1_0);
+        assert_eq!(QuantizationType::TQ1_0.block_size(), Some(256));
+        assert_eq!(QuantizationType::TQ1_0.type_size(), Some(54));
+        assert_eq!(QuantizationType::TQ1_0.bits_per_weight(), Some(1.6875));
+        assert_eq!(QuantizationType::TQ2_0.bits_per_weight(), Some(2.0625));
+    }
+
+    #[test]
+    fn test_model_config_reads_older_key_names() {
+        let entries = [
+            ("general.architecture", GgufValue::String("llama".to_string())),
+            ("llama.vocab_size", GgufValue::Uint32(32000)),
+            ("llama.context_length", GgufValue::Uint32(4096)),
+            ("llama.block_count", GgufValue::Uint32(2)),
+            ("llama.embedding_length", GgufValue::Uint32(64)),
+            ("llama.feed_forward_length", GgufValue::Uint32(256)),
+            ("llama.attention.head_count", GgufValue::Uint32(4)),
+            ("llama.attention.layer_norm_epsilon", GgufValue::Float32(1e-5)),
+            ("llama.rope.theta", GgufValue::Float32(500000.0)),
+        ];
+        let metadata: GgufMetadata = entries.into_iter().collect();
+        let config = ModelConfig::from_metadata(&metadata).unwrap();
+
+        assert_eq!(config.context_length, 4096);
+        assert_eq!(config.attention_layer_norm_rms_epsilon, Some(1e-5));
+        assert_eq!(config.rope_freq_base, Some(500000.0));
+        assert_eq!(config.source_keys["context_length"], "llama.context_length");
+        assert_eq!(config.source_keys["rope_freq_base"], "llama.rope.theta");
+        assert!(!config.source_keys.contains_key("attention_head_count_kv"));
+
+        assert_eq!(
+            metadata.get_with_aliases(&["llama.rope.freq_base", "llama.rope.theta"]),
+            Some(("llama.rope.theta", &GgufValue::Float32(500000.0)))
+        );
+        assert_eq!(metadata.get_with_aliases(&["missing.key"]), None);
+    }
+
+    #[test]
+    fn test_header_and_tensor_info_serde_round_trip() {
+        let mut info = tensor("blk.0.attn_q.weight", &[4096, 4096], QuantizationType::Q4_K);
+        info.offset = 1 << 20;
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<TensorInfo>(&json).unwrap(), info);
+
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let header = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().header;
+        let json = serde_json::to_string(&header).unwrap();
+        assert!(json.contains("\"magic\":[71,71,85,70]"));
+        assert_eq!(serde_json::from_str::<GgufHeader>(&json).unwrap(), header);
+    }
+
+    #[test]
+    fn test_embedded_tokenizer_json() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let plain = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(plain.embedded_tokenizer_json(), None);
+        let path = std::env::temp_dir().join(format!("aiogguf-{}-tokenizer.json", std::process::id()));
+        assert!(matches!(
+            plain.write_embedded_tokenizer_json(&path),
+            Err(GgufError::MetadataKeyNotFound(_))
+        ));
+
+        let json = r#"{"version":"1.0","model":{"type":"BPE","vocab":{"<s>":0,"</s>":1,"a":2}}}"#;
+        let mut metadata = sample_metadata();
+        metadata.push(("tokenizer.huggingface.json", GgufValue::String(json.to_string())));
+        let bytes = build_gguf(&metadata, &[]);
+        let embedded = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(embedded.embedded_tokenizer_json(), Some(json));
+        assert_eq!(embedded.metadata.largest_values(1), vec![("tokenizer.huggingface.json", 8 + json.len() as u64)]);
+
+        embedded.write_embedded_tokenizer_json(&path).unwrap();
+        let written = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(written, json);
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_malformed_embedded_tokenizer_json() {
+        let mut metadata = sample_metadata();
+        metadata.push(("tokenizer.huggingface.json", GgufValue::String("{\n  \"model\": {,}\n}".to_string())));
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        match gguf_file.parse_embedded_tokenizer_json() {
+            Err(GgufError::InvalidEmbeddedJson { key, line, column, .. }) => {
+                assert_eq!(key, "tokenizer.huggingface.json");
+                assert_eq!((line, column), (2, 13));
+            }
+            other => panic!("expected InvalidEmbeddedJson, got {other:?}"),
+        }
+        let path = std::env::temp_dir().join(format!("aiogguf-{}-malformed.json", std::process::id()));
+        assert!(gguf_file.write_embedded_tokenizer_json(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_kv_cache_bytes() {
+        // Llama 2 7B: 32 layers, 32 KV heads of 128, F16 cache at 4096 tokens is 2 GiB
+        let llama2_7b = llama_config(4096, 32, 32, 11008, 32, 32000);
+        assert_eq!(llama2_7b.kv_cache_bytes(4096, 1, 2), Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(llama2_7b.kv_cache_bytes(4096, 4, 2), Some(8 * 1024 * 1024 * 1024));
+
+        let mut windowed = llama2_7b.clone();
+        windowed.attention_sliding_window = Some(1024);
+        assert_eq!(windowed.kv_cache_bytes(4096, 1, 2), Some(512 * 1024 * 1024));
+
+        // Gemma 2 alternates window and global layers, so only half are capped
+        let mut gemma2 = windowed.clone();
+        gemma2.architecture = "gemma2".to_string();
+        gemma2.context_length = 32768;
+        assert_eq!(gemma2.sliding_window_layer_count(), 16);
+        assert_eq!(gemma2.kv_cache_bytes(4096, 1, 2), Some(1280 * 1024 * 1024));
+        assert_eq!(gemma2.max_context_for_memory(2 * 1024 * 1024 * 1024, QuantizationType::F16), Some(7168));
+
+        let mut patterned = gemma2;
+        patterned.attention_sliding_window_pattern = Some(4);
+        assert_eq!(patterned.sliding_window_layer_count(), 24);
+        assert_eq!(patterned.kv_cache_bytes(4096, 1, 2), Some(896 * 1024 * 1024));
+
+        let mut headless = llama2_7b;
+        headless.attention_head_count = 0;
+        assert_eq!(headless.kv_cache_bytes(4096, 1, 2), None);
+    }
+
+    #[test]
+    fn test_quantization_labels_round_trip() {
+        let tensor_types: Vec<_> = (0..=u8::MAX as u32).filter_map(|id| QuantizationType::try_from(id).ok()).collect();
+        assert!(tensor_types.len() >= 30);
+        for quantization_type in tensor_types {
+            let label = quantization_type.short_name();
+            assert_eq!(label.parse::<QuantizationType>().unwrap(), quantization_type);
+            assert_eq!(label.to_lowercase().parse::<QuantizationType>().unwrap(), quantization_type);
+            assert_eq!(quantization_type.to_string(), label);
+        }
+
+        let file_types: Vec<_> = (0..=u8::MAX as u32)
+            .map(FileType::from)
+            .filter(|t| !matches!(t, FileType::Unknown(_)))
+            .collect();
+        for file_type in file_types {
+            assert_eq!(file_type.label().parse::<FileType>().unwrap(), file_type);
+            assert_eq!(file_type.to_string(), file_type.label());
+        }
+
+        assert_eq!("q4_K".parse::<QuantizationType>().unwrap(), QuantizationType::Q4_K);
+        assert_eq!("q4k".parse::<QuantizationType>().unwrap(), QuantizationType::Q4_K);
+        assert_eq!("iq2-xxs".parse::<QuantizationType>().unwrap(), QuantizationType::IQ2_XXS);
+        assert_eq!("Q4_k_m".parse::<FileType>().unwrap(), FileType::Q4_K_M);
+        assert!("Q4_K_M".parse::<QuantizationType>().is_err());
+        assert!(matches!("Q9_K".parse::<FileType>(), Err(GgufError::UnknownQuantizationLabel(label)) if label == "Q9_K"));
+        assert_eq!(QuantizationType::Unknown(99).to_string(), "unknown(99)");
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let warnings: Vec<_> = gguf_file.warnings().iter().map(|w| w.warning.clone()).collect();
+        assert_eq!(warnings, vec![ParseWarning::InferredVocabSize { tokens: 3 }]);
+
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.vocab_size", GgufValue::Uint32(3)));
+        metadata.push(("general.name", GgufValue::String("Renamed".to_string())));
+        let tensors = vec![tensor("blk.0.ffn_up.weight", &[64, 256], QuantizationType::Unknown(99))];
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let warnings: Vec<_> = gguf_file.warnings().iter().map(|w| w.warning.clone()).collect();
+        assert_eq!(gguf_file.name(), Some("Renamed"));
+        assert_eq!(
+            warnings,
+            vec![
+                ParseWarning::DuplicateKey("general.name".to_string()),
+                ParseWarning::UnknownQuantType { tensor: "blk.0.ffn_up.weight".to_string(), id: 99 },
+            ]
+        );
+        assert_eq!(warnings[0].to_string(), "metadata key 'general.name' appears more than once");
+    }
+
+    #[test]
+    fn test_quant_registry_is_consistent() {
+        let all: Vec<_> = QuantizationType::all().collect();
+        assert_eq!(all.len(), 31);
+        for quantization_type in all {
+            let info = quantization_type.info().unwrap();
+            assert_eq!(info.quantization_type, quantization_type);
+            assert_eq!(QuantizationType::try_from(info.id).unwrap(), quantization_type);
+            assert_eq!(info.bits_per_weight, info.type_size_bytes as f32 * 8.0 / info.block_size as f32);
+            assert_eq!(quantization_type.bits_per_weight(), Some(info.bits_per_weight));
+            assert_eq!(quantization_type.short_name(), info.name);
+            assert_eq!(quantization_type.is_quantized(), info.is_quantized);
+            assert_eq!(info.is_imatrix, info.name.starts_with("IQ"));
+        }
+
+        let q4_k = QuantizationType::Q4_K.info().unwrap();
+        assert_eq!((q4_k.id, q4_k.block_size, q4_k.type_size_bytes), (12, 256, 144));
+        assert_eq!(QuantizationType::Unknown(99).info(), None);
+    }
+
+    #[test]
+    fn test_tensor_byte_range() {
+        let mut second = tensor("blk.0.attn_k.weight", &[4096, 1024], QuantizationType::Q4_K);
+        second.offset = 4096 * 4;
+        let tensors = vec![tensor("blk.0.attn_norm.weight", &[4096], QuantizationType::F32), second];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let data_offset = gguf_file.data_offset();
+
+        assert_eq!(gguf_file.tensors[0].byte_range(data_offset).unwrap(), data_offset..data_offset + 16384);
+        // 4096 * 1024 weights in 256-wide blocks of 144 bytes
+        let start = data_offset + 16384;
+        assert_eq!(gguf_file.tensors[1].byte_range(data_offset).unwrap(), start..start + 16384 * 144);
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn test_sidecar_cache() {
+        let dir = std::env::temp_dir().join(format!("aiogguf-{}-cache", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("model.gguf");
+        let cache_path = GgufCache::cache_path(&path);
+        assert_eq!(cache_path, dir.join("model.gguf.aiocache"));
+
+        std::fs::write(&path, build_gguf(&sample_metadata(), &[])).unwrap();
+        let read_only = CacheOptions { write_cache: false, ..Default::default() };
+        assert_eq!(GgufCache::load_or_parse_with_options(&path, &read_only).unwrap().name(), Some("Tiny"));
+        assert!(!cache_path.exists());
+
+        let parsed = GgufCache::load_or_parse(&path).unwrap();
+        let cache = std::fs::read(&cache_path).unwrap();
+        assert_eq!(GgufCache::load_or_parse(&path).unwrap(), parsed);
+        assert_eq!(std::fs::read(&cache_path).unwrap(), cache);
+
+        // Changing the model invalidates the cache
+        let mut metadata = sample_metadata();
+        metadata[1].1 = GgufValue::String("Renamed".to_string());
+        std::fs::write(&path, build_gguf(&metadata, &[])).unwrap();
+        assert_eq!(GgufCache::load_or_parse(&path).unwrap().name(), Some("Renamed"));
+        assert_ne!(std::fs::read(&cache_path).unwrap(), cache);
+
+        // A corrupt cache is replaced rather than reported
+        std::fs::write(&cache_path, b"not a cache").unwrap();
+        assert_eq!(GgufCache::load_or_parse(&path).unwrap().name(), Some("Renamed"));
+        assert_ne!(std::fs::read(&cache_path).unwrap(), b"not a cache");
+
+        // A cache from a default parse doesn't satisfy a stricter one
+        metadata.push(("general.name", GgufValue::from("Twice")));
+        std::fs::write(&path, build_gguf(&metadata, &[])).unwrap();
+        assert!(GgufCache::load_or_parse(&path).is_ok());
+        let strict = CacheOptions {
+            parse_options: ParseOptions { warnings_as_errors: vec![WarningKind::DuplicateKey], ..Default::default() },
+            ..Default::default()
+        };
+        assert!(matches!(
+            GgufCache::load_or_parse_with_options(&path, &strict),
+            Err(GgufError::PromotedWarning(_))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(any(feature = "gzip", feature = "zstd"))]
+    #[test]
+    fn test_compressed_reader() {
+        // A vocabulary larger than the first decompressed prefix
+        let tokens = (0..300_000).map(|i| GgufValue::String(format!("token_{i:06}"))).collect();
+        let metadata = [
+            ("general.architecture", GgufValue::String("llama".to_string())),
+            ("tokenizer.ggml.tokens", GgufValue::Array(tokens)),
+        ];
+        let tensors = [tensor("token_embd.weight", &[64, 300_000], QuantizationType::F16)];
+        let bytes = build_gguf(&metadata, &tensors);
+        let expected = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        #[cfg(feature = "gzip")]
+        {
+            use std::io::Write;
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+            encoder.write_all(&bytes).unwrap();
+            let compressed = encoder.finish().unwrap();
+            let gguf_file = GgufFile::from_compressed_reader(&mut compressed.as_slice(), Compression::Gzip).unwrap();
+            assert_eq!(gguf_file.metadata, expected.metadata);
+            assert_eq!(gguf_file.tensors, expected.tensors);
+            assert_eq!(gguf_file.data_offset(), expected.data_offset());
+
+            // Truncated streams fail like truncated files
+            let truncated = &compressed[..compressed.len() / 2];
+            assert!(GgufFile::from_compressed_reader(&mut &truncated[..], Compression::Gzip).is_err());
+
+            // Input that isn't gzip at all is corrupt data, not an I/O failure
+            let error = GgufFile::from_compressed_reader(&mut &bytes[..], Compression::Gzip).unwrap_err();
+            assert!(matches!(error, GgufError::InvalidCompressedData(_)));
+            assert!(error.is_format_error());
+        }
+        #[cfg(feature = "zstd")]
+        {
+            let compressed = ruzstd::encoding::compress_to_vec(bytes.as_slice(), ruzstd::encoding::CompressionLevel::Fastest);
+            let gguf_file = GgufFile::from_compressed_reader(&mut compressed.as_slice(), Compression::Zstd).unwrap();
+            assert_eq!(gguf_file.metadata, expected.metadata);
+            assert_eq!(gguf_file.tensors, expected.tensors);
+
+            let error = GgufFile::from_compressed_reader(&mut &bytes[..], Compression::Zstd);
+            assert!(matches!(error, Err(GgufError::InvalidCompressedData(_))));
+        }
+    }
+
+    #[test]
+    fn test_quality_tier() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::Q4_K),
+            tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_v.weight", &[64, 64], QuantizationType::Q6_K),
+            tensor("blk.0.ffn_down.weight", &[256, 64], QuantizationType::Q4_K),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(gguf_file.quality_tier(), Some(QualityTier::Balanced));
+
+        assert_eq!(QualityTier::from_bits_per_weight(6.5625), QualityTier::High);
+        assert_eq!(QualityTier::from_bits_per_weight(3.4375), QualityTier::Small);
+        assert_eq!(QualityTier::from_bits_per_weight(2.0625), QualityTier::VerySmall);
+
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        assert_eq!(GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().quality_tier(), None);
+    }
+
+    #[test]
+    fn test_model_bundle_discovery() {
+        let dir = std::env::temp_dir().join(format!("aiogguf-{}-bundle", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let gguf = |entries: &[(&str, &str)]| {
+            let metadata: Vec<_> = entries.iter().map(|(k, v)| (*k, GgufValue::String(v.to_string()))).collect();
+            build_gguf(&metadata, &[])
+        };
+
+        let model = dir.join("Gemma-3-4B-it-Q4_K_M.gguf");
+        std::fs::write(&model, gguf(&[("general.architecture", "gemma3"), ("general.name", "Gemma 3 4b It")])).unwrap();
+        std::fs::write(dir.join("mmproj-model-f16.gguf"), gguf(&[("general.architecture", "clip"), ("general.name", "Gemma-3-4B-it")])).unwrap();
+        std::fs::write(dir.join("style-lora.gguf"), gguf(&[("general.architecture", "gemma3"), ("general.type", "adapter")])).unwrap();
+        std::fs::write(dir.join("qwen-lora.gguf"), gguf(&[("general.architecture", "qwen2"), ("general.type", "adapter")])).unwrap();
+        std::fs::write(dir.join("untyped-lora.gguf"), gguf(&[("general.architecture", "gemma3"), ("adapter.type", "lora")])).unwrap();
+        std::fs::write(dir.join("Gemma-3-4B-it-Q8_0.gguf"), gguf(&[("general.architecture", "gemma3")])).unwrap();
+        std::fs::write(dir.join("imatrix.dat"), b"").unwrap();
+        std::fs::write(dir.join("config.json"), b"{}").unwrap();
+        std::fs::write(dir.join("README.md"), b"").unwrap();
+        std::fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        let bundle = ModelBundle::discover(&model).unwrap();
+        assert_eq!(bundle.shards(), std::slice::from_ref(&model));
+        assert_eq!(bundle.projector(), Some(dir.join("mmproj-model-f16.gguf").as_path()));
+        assert_eq!(bundle.adapters(), &[dir.join("style-lora.gguf"), dir.join("untyped-lora.gguf")]);
+        assert_eq!(bundle.imatrix_files(), &[dir.join("imatrix.dat")]);
+        assert_eq!(bundle.originals(), &[dir.join("README.md"), dir.join("config.json")]);
+        assert_eq!(bundle.validate(), vec![]);
+
+        // A projector for a different model, recognized by its clip.* keys, and a missing shard are flagged
+        let llava = gguf(&[("general.architecture", "llava"), ("general.name", "Llava 1.5"), ("clip.projector_type", "mlp")]);
+        std::fs::write(dir.join("mmproj-model-f16.gguf"), llava).unwrap();
+        let first = dir.join("Big-Q4_K_M-00001-of-00003.gguf");
+        std::fs::write(&first, gguf(&[("general.architecture", "gemma3"), ("general.name", "Gemma 3 4b It")])).unwrap();
+        std::fs::write(dir.join("Big-Q4_K_M-00002-of-00003.gguf"), b"").unwrap();
+        let split = ModelBundle::discover(&first).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(split.shards().len(), 2);
+        assert_eq!(
+            split.validate(),
+            vec![
+                BundleWarning::ProjectorNameMismatch {
+                    model: "Gemma 3 4b It".to_string(),
+                    projector: "Llava 1.5".to_string(),
+                },
+                BundleWarning::MissingShards { expected: 3, found: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_header_with_custom_versions() {
+        let mut bytes = build_gguf(&sample_metadata(), &[]);
+        bytes[4..8].copy_from_slice(&4u32.to_le_bytes());
+
+        let err = GgufHeader::read(&mut Cursor::new(&bytes)).unwrap_err();
+        assert!(matches!(err, GgufError::UnsupportedVersion(4)));
+
+        let header = GgufHeader::read_with_versions(&mut Cursor::new(&bytes), &[3, 4]).unwrap();
+        assert_eq!(header.version, 4);
+        assert_eq!(header.metadata_kv_count, sample_metadata().len() as u64);
+
+        let err = GgufHeader::read_with_versions(&mut Cursor::new(&bytes), &[]).unwrap_err();
+        assert!(matches!(err, GgufError::UnsupportedVersion(4)));
+    }
+
+    #[test]
+    fn test_imatrix_info() {
+        let iq_tensors = [tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::IQ2_XS)];
+
+        let mut metadata = sample_metadata();
+        metadata.push(("quantize.imatrix.file", GgufValue::String("imatrix.dat".to_string())));
+        metadata.push(("quantize.imatrix.dataset", GgufValue::String("calibration.txt".to_string())));
+        metadata.push(("quantize.imatrix.entries_count", GgufValue::Int32(224)));
+        metadata.push(("quantize.imatrix.chunks_count", GgufValue::Int32(125)));
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &iq_tensors))).unwrap();
+        let info = gguf_file.imatrix_info().unwrap();
+        assert_eq!(info.file.as_deref(), Some("imatrix.dat"));
+        assert_eq!(info.dataset.as_deref(), Some("calibration.txt"));
+        assert_eq!(info.entries_count, Some(224));
+        assert_eq!(info.chunks_count, Some(125));
+        assert!(info.uses_iq_types);
+        assert!(!info.is_suspicious());
+
+        // IQ2_XS without imatrix metadata
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &iq_tensors))).unwrap();
+        let info = gguf_file.imatrix_info().unwrap();
+        assert!(!info.has_metadata());
+        assert!(info.is_suspicious());
+
+        let k_tensors = [tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::Q4_K)];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &k_tensors))).unwrap();
+        assert_eq!(gguf_file.imatrix_info(), None);
+
+        // IQ4 types work without an imatrix
+        let iq4_tensors = [
+            tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::IQ4_XS),
+            tensor("blk.0.attn_k.weight", &[256, 64], QuantizationType::IQ4_NL),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &iq4_tensors))).unwrap();
+        assert_eq!(gguf_file.imatrix_info(), None);
+    }
+
+    #[test]
+    fn test_validate_against_config() {
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F16),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::F16),
+            tensor("blk.0.attn_k.weight", &[64, 32], QuantizationType::F16),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let config = gguf_file.model_config().unwrap();
+        assert!(gguf_file.validate_against_config(&config).is_empty());
+
+        // Truncated embedding dimension
+        let tensors = [
+            tensor("token_embd.weight", &[48, 3], QuantizationType::F16),
+            tensor("output.weight", &[64, 3], QuantizationType::F16),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let mismatches = gguf_file.validate_against_config(&config);
+        assert_eq!(
+            mismatches,
+            vec![ShapeMismatch {
+                tensor: "token_embd.weight".to_string(),
+                expected: vec![64, 3],
+                actual: vec![48, 3],
+            }]
+        );
+        assert_eq!(mismatches[0].to_string(), "tensor 'token_embd.weight' has shape [48, 3], expected [64, 3]");
+    }
+
+    #[test]
+    fn test_file_type_consistency() {
+        let mut metadata = sample_metadata();
+        metadata.push(("general.file_type", GgufValue::Uint32(15)));
+        metadata.push(("general.quantization_version", GgufValue::Uint32(1)));
+
+        // Declared Q4_K_M but quantized to Q8_0
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::Q8_0),
+            tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q8_0),
+            tensor("blk.0.attn_k.weight", &[64, 32], QuantizationType::Q8_0),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &tensors))).unwrap();
+        assert_eq!(gguf_file.quantization_version(), Some(1));
+        assert!(gguf_file.warnings().iter().any(|w| w.warning == ParseWarning::UnexpectedQuantizationVersion(1)));
+
+        let consistency = gguf_file.file_type_consistency().unwrap();
+        assert_eq!(consistency.declared, FileType::Q4_K_M);
+        assert_eq!(consistency.expected, Some(QuantizationType::Q4_K));
+        assert_eq!(consistency.dominant, Some(QuantizationType::Q8_0));
+        assert_eq!(
+            consistency.deviating,
+            vec!["token_embd.weight", "blk.0.attn_q.weight", "blk.0.attn_k.weight"]
+        );
+        assert!(!consistency.is_consistent());
+
+        // A proper Q4_K_M mix with a promoted attn_v
+        let tensors = [
+            tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_k.weight", &[256, 32], QuantizationType::Q4_K),
+            tensor("blk.0.attn_v.weight", &[256, 32], QuantizationType::Q6_K),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &tensors))).unwrap();
+        let consistency = gguf_file.file_type_consistency().unwrap();
+        assert!(consistency.is_consistent());
+        assert_eq!(consistency.deviating, vec!["blk.0.attn_v.weight"]);
+
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        assert_eq!(gguf_file.file_type_consistency(), None);
+
+        assert_eq!(FileType::BF16.base_quantization_type(), Some(QuantizationType::BF16));
+        assert_eq!(QuantizationType::try_from(30).unwrap(), QuantizationType::BF16);
+        assert_eq!(QuantizationType::BF16.type_size(), Some(2));
+        assert!(QuantizationType::BF16.is_float());
+    }
+
+    #[test]
+    fn test_value_as_number() {
+        assert_eq!(GgufValue::Uint8(7).as_number(), Some(7.0));
+        assert_eq!(GgufValue::Int16(-3).as_number(), Some(-3.0));
+        assert_eq!(GgufValue::Uint32(4096).as_number(), Some(4096.0));
+        assert_eq!(GgufValue::Int64(-1).as_number(), Some(-1.0));
+        assert_eq!(GgufValue::Float32(0.5).as_number(), Some(0.5));
+        assert_eq!(GgufValue::Float64(1e-6).as_number(), Some(1e-6));
+        // Above 2^53 the nearest f64 is returned
+        assert_eq!(GgufValue::Uint64(u64::MAX).as_number(), Some(u64::MAX as f64));
+
+        assert_eq!(GgufValue::Bool(true).as_number(), None);
+        assert_eq!(GgufValue::String("1".to_string()).as_number(), None);
+        assert_eq!(GgufValue::Array(vec![GgufValue::Uint8(1)]).as_number(), None);
+    }
+
+    #[test]
+    fn test_metadata_mutation() {
+        assert_eq!(GgufValue::from(7u8), GgufValue::Uint8(7));
+        assert_eq!(GgufValue::from(-7i64), GgufValue::Int64(-7));
+        assert_eq!(GgufValue::from(0.5f32), GgufValue::Float32(0.5));
+        assert_eq!(GgufValue::from(true), GgufValue::Bool(true));
+        assert_eq!(GgufValue::from("llama"), GgufValue::String("llama".to_string()));
+        assert_eq!(
+            GgufValue::from(vec!["<s>".to_string(), "</s>".to_string()]),
+            GgufValue::Array(vec![GgufValue::String("<s>".to_string()), GgufValue::String("</s>".to_string())])
+        );
+        assert_eq!(GgufValue::from(vec![1u32, 2]), GgufValue::Array(vec![GgufValue::Uint32(1), GgufValue::Uint32(2)]));
+
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let mut metadata = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap().metadata;
+        let file_order: Vec<_> = sample_metadata().iter().map(|(key, _)| *key).collect();
+        assert_eq!(metadata.iter().map(|(key, _)| key).collect::<Vec<_>>(), file_order);
+
+        // Replacing keeps the position, new keys go last
+        assert_eq!(metadata.insert("general.name", "Renamed"), Some(GgufValue::String("Tiny".to_string())));
+        assert_eq!(metadata.insert("general.file_type", 15u32), None);
+        let keys: Vec<_> = metadata.iter().map(|(key, _)| key).collect();
+        assert_eq!(keys[1], "general.name");
+        assert_eq!(keys.last(), Some(&"general.file_type"));
+        assert_eq!(metadata.get_string_opt("general.name"), Some("Renamed"));
+
+        // Strip the tokenizer arrays for a metadata stub
+        metadata.retain(|_, value| !matches!(value, GgufValue::Array(_)));
+        assert!(!metadata.contains("tokenizer.ggml.tokens"));
+        assert_eq!(metadata.remove("tokenizer.ggml.model"), Some(GgufValue::String("llama".to_string())));
+        assert_eq!(metadata.remove("tokenizer.ggml.model"), None);
+
+        assert!(metadata.rename_key("llama.context_length", "llama.context_length_trained"));
+        assert!(!metadata.rename_key("missing", "other"));
+        let keys: Vec<_> = metadata.iter().map(|(key, _)| key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "general.architecture",
+                "general.name",
+                "llama.context_length_trained",
+                "llama.block_count",
+                "llama.embedding_length",
+                "llama.feed_forward_length",
+                "llama.attention.head_count",
+                "llama.attention.head_count_kv",
+                "general.file_type",
+            ]
+        );
+        assert_eq!(metadata.len(), keys.len());
+        // Lookups follow entries that moved
+        assert_eq!(metadata.get_u32("llama.block_count").unwrap(), 2);
+        assert_eq!(metadata.get_u32("general.file_type").unwrap(), 15);
+        assert!(!metadata.contains("llama.context_length"));
+    }
+
+    #[test]
+    fn test_effective_context_length() {
+        let mut config = llama_config(4096, 32, 32, 11008, 32, 32000);
+        assert_eq!(config.effective_context_length(Some(131072)), 4096);
+        assert_eq!(config.effective_context_length(Some(2048)), 2048);
+        assert_eq!(config.effective_context_length(None), 4096);
+
+        config.attention_sliding_window = Some(1024);
+        assert_eq!(config.effective_context_length(Some(512)), 1024);
+        assert_eq!(config.effective_context_length(Some(2048)), 2048);
+
+        config.context_length = 0;
+        assert_eq!(config.effective_context_length(Some(8192)), 8192);
+    }
+
+    #[test]
+    fn test_write_metadata_stub() {
+        let mut metadata = sample_metadata();
+        metadata.push(("tokenizer.chat_template", GgufValue::String("{{ messages }}".to_string())));
+        let mut tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::F32),
+        ];
+        tensors[1].offset = 64 * 3 * 4;
+        let mut bytes = build_gguf(&metadata, &tensors);
+        let original = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        bytes.resize((original.data_offset() + original.total_size().unwrap()) as usize, 0);
+
+        let path = std::env::temp_dir().join(format!("aiogguf-{}-stub.gguf", std::process::id()));
+        original.write_metadata_stub(&path, &StubOptions::default()).unwrap();
+        let stub_len = std::fs::metadata(&path).unwrap().len();
+        assert!(stub_len < bytes.len() as u64);
+
+        let options = ParseOptions { verify_complete: true, ..Default::default() };
+        let stub = GgufFile::from_file_with_options(&path, &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(stub.is_stub());
+        assert!(!original.is_stub());
+        assert_eq!(stub.tensors, original.tensors);
+        assert_eq!(stub.header.tensor_count, 2);
+        assert_eq!(stub_len, stub.header_bytes() + stub.metadata_bytes() + stub.tensor_info_bytes());
+        assert!(!stub.metadata.contains("tokenizer.ggml.tokens"));
+        assert!(!stub.metadata.contains("tokenizer.chat_template"));
+        assert_eq!(stub.metadata.get_u64_opt("aiogguf.stub.dropped.tokenizer.ggml.tokens"), Some(3));
+        assert_eq!(stub.metadata.get_u64_opt("aiogguf.stub.dropped.tokenizer.chat_template"), Some(14));
+        assert_eq!(stub.metadata.get_string_opt("general.name"), Some("Tiny"));
+        assert_eq!(stub.metadata.get_string_opt("tokenizer.ggml.model"), Some("llama"));
+
+        // Without the marker the same file is a truncated model
+        let mut unmarked = stub.clone();
+        unmarked.metadata.remove("aiogguf.stub");
+        assert!(matches!(unmarked.verify_complete(stub_len), Err(GgufError::TruncatedFile { .. })));
+    }
+
+    #[test]
+    fn test_ffn_activation() {
+        let mut config = llama_config(4096, 32, 32, 11008, 32, 32000);
+        assert_eq!(config.ffn_activation(), Some(Activation::SwiGLU));
+
+        config.architecture = "gemma".to_string();
+        assert_eq!(config.ffn_activation(), Some(Activation::GeGLU));
+        assert!(Activation::GeGLU.is_gated());
+
+        config.architecture = "gpt2".to_string();
+        assert_eq!(config.ffn_activation(), Some(Activation::Gelu));
+        assert!(!Activation::Gelu.is_gated());
+
+        config.architecture = "unheard-of".to_string();
+        assert_eq!(config.ffn_activation(), None);
+    }
+
+    #[test]
+    fn test_tensor_renamer() {
+        let legacy = [
+            tensor("tok_embeddings.weight", &[64, 3], QuantizationType::F32),
+            tensor("layers.0.attention.wq.weight", &[64, 64], QuantizationType::F32),
+            tensor("layers.0.attention_norm.weight", &[64], QuantizationType::F32),
+            tensor("layers.1.feed_forward.w2.weight", &[256, 64], QuantizationType::F32),
+            tensor("norm.weight", &[64], QuantizationType::F32),
+        ];
+        let mut tensors = legacy.to_vec();
+        assert_eq!(TensorRenamer::legacy_llama().apply(&mut tensors).unwrap(), 5);
+        let names: Vec<_> = tensors.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["token_embd.weight", "blk.0.attn_q.weight", "blk.0.attn_norm.weight", "blk.1.ffn_down.weight", "output_norm.weight"]
+        );
+        assert_eq!(tensors[1].layer_number(), Some(0));
+        assert_eq!(tensors[3].layer_number(), Some(1));
+        assert!(tensors[3].is_weight_tensor());
+
+        let hf = TensorRenamer::hf_llama();
+        assert_eq!(hf.rename("model.layers.12.self_attn.q_proj.bias").as_deref(), Some("blk.12.attn_q.bias"));
+        assert_eq!(hf.rename("model.layers.x.self_attn.q_proj.bias"), None);
+
+        let custom = TensorRenamer::new().prefix("transformer.", "").pattern("h.{N}.mlp", "blk.{N}.ffn");
+        assert_eq!(custom.rename("transformer.wte.weight").as_deref(), Some("wte.weight"));
+        assert_eq!(custom.rename("h.3.mlp").as_deref(), Some("blk.3.ffn"));
+
+        // Both tensors would become token_embd.weight, nothing is renamed
+        let mut colliding = vec![
+            tensor("tok_embeddings.weight", &[64, 3], QuantizationType::F32),
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+        ];
+        match TensorRenamer::legacy_llama().apply(&mut colliding) {
+            Err(GgufError::TensorNameCollision { name, first, second }) => {
+                assert_eq!(name, "token_embd.weight");
+                assert_eq!((first.as_str(), second.as_str()), ("tok_embeddings.weight", "token_embd.weight"));
+            }
+            other => panic!("expected a collision, got {other:?}"),
+        }
+        assert_eq!(colliding[0].name, "tok_embeddings.weight");
+
+        // Rewriting keeps the tensor data byte for byte
+        let mut on_disk = legacy.to_vec();
+        let mut offset = 0;
+        for t in &mut on_disk {
+            t.offset = offset;
+            offset += t.size_bytes().unwrap().div_ceil(32) * 32;
+        }
+        let mut bytes = build_gguf(&sample_metadata(), &on_disk);
+        let data_offset = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().data_offset() as usize;
+        bytes.resize(data_offset, 0);
+        bytes.extend((0..offset).map(|i| i as u8));
+
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("aiogguf-{}-legacy.gguf", std::process::id()));
+        let output = dir.join(format!("aiogguf-{}-renamed.gguf", std::process::id()));
+        std::fs::write(&input, &bytes).unwrap();
+        TensorRenamer::legacy_llama().rewrite_file(&input, &output).unwrap();
+        assert!(matches!(
+            TensorRenamer::legacy_llama().rewrite_file(&input, &input),
+            Err(GgufError::OutputIsInput(_))
+        ));
+        assert_eq!(std::fs::read(&input).unwrap(), bytes);
+
+        let options = ParseOptions { verify_complete: true, ..Default::default() };
+        let renamed = GgufFile::from_file_with_options(&output, &options).unwrap();
+        let renamed_bytes = std::fs::read(&output).unwrap();
+        std::fs::remove_file(&input).unwrap();
+        std::fs::remove_file(&output).unwrap();
+
+        let renamed_names: Vec<_> = renamed.tensors.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(renamed_names, names);
+        assert!(renamed.tensors.iter().zip(&on_disk).all(|(r, d)| r.offset == d.offset && r.dimensions == d.dimensions));
+        assert_eq!(renamed.metadata, GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().metadata);
+        assert_eq!(&renamed_bytes[renamed.data_offset() as usize..], &bytes[data_offset..]);
+
+        // Duplicate keys are merged, so the header counts the entries written
+        let mut metadata = sample_metadata();
+        metadata.push(("general.name", GgufValue::from("Again")));
+        let mut bytes = build_gguf(&metadata, &[tensor("tok_embeddings.weight", &[4], QuantizationType::F32)]);
+        let data_offset = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().data_offset() as usize;
+        bytes.resize(data_offset + 16, 0);
+        std::fs::write(&input, &bytes).unwrap();
+        TensorRenamer::legacy_llama().rewrite_file(&input, &output).unwrap();
+        let renamed = GgufFile::from_file(&output).unwrap();
+        std::fs::remove_file(&input).unwrap();
+        std::fs::remove_file(&output).unwrap();
+        assert_eq!(renamed.header.metadata_kv_count, sample_metadata().len() as u64);
+        assert_eq!(renamed.name(), Some("Again"));
+        assert_eq!(renamed.tensors[0].name, "token_embd.weight");
+    }
+
+    #[test]
+    fn test_tied_embeddings() {
+        let tied = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+        ];
+        let untied = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+            tensor("output.weight", &[64, 3], QuantizationType::F32),
+        ];
+        let tied = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tied))).unwrap();
+        let untied = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &untied))).unwrap();
+        assert!(tied.has_tied_embeddings());
+        assert!(!untied.has_tied_embeddings());
+
+        // The output projection is counted only when it is stored separately
+        let vocab_times_embedding = 3 * 64;
+        assert_eq!(
+            untied.estimated_param_count().unwrap() - tied.estimated_param_count().unwrap(),
+            vocab_times_embedding
+        );
+    }
+
+    #[test]
+    fn test_gguf_in_container_and_trailing_data() {
+        let tensors = [tensor("token_embd.weight", &[64, 3], QuantizationType::F32)];
+        let mut gguf = build_gguf(&sample_metadata(), &tensors);
+        let parsed = GgufFile::from_reader(&mut Cursor::new(&gguf)).unwrap();
+        gguf.resize((parsed.data_offset() + parsed.total_size().unwrap()) as usize, 0);
+
+        let mut wrapped = vec![0x5a; 512];
+        wrapped.extend_from_slice(&gguf);
+        assert_eq!(find_gguf_offset(&mut Cursor::new(&wrapped), 1024).unwrap(), Some(512));
+        assert_eq!(find_gguf_offset(&mut Cursor::new(&wrapped), 512).unwrap(), None);
+        assert_eq!(find_gguf_offset(&mut Cursor::new(&wrapped), 513).unwrap(), Some(512));
+
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("aiogguf-{}-wrapped.gguf", std::process::id()));
+        std::fs::write(&path, &wrapped).unwrap();
+        assert!(matches!(GgufFile::from_file(&path), Err(GgufError::InvalidMagic(_))));
+        let options = ParseOptions {
+            scan_for_magic: Some(4096),
+            verify_complete: true,
+            ..Default::default()
+        };
+        let found = GgufFile::from_file_with_options(&path, &options).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(found.gguf_offset(), 512);
+        assert_eq!(found.tensors, parsed.tensors);
+        assert_eq!(found.data_offset(), parsed.data_offset());
+
+        // A signature block appended after the tensor data
+        let mut signed = gguf.clone();
+        signed.extend_from_slice(b"-----BEGIN SIGNATURE-----");
+        let path = dir.join(format!("aiogguf-{}-signed.gguf", std::process::id()));
+        std::fs::write(&path, &signed).unwrap();
+        assert!(GgufFile::from_file(&path).is_ok());
+        let options = ParseOptions { reject_trailing_data: true, ..Default::default() };
+        let err = GgufFile::from_file_with_options(&path, &options).unwrap_err();
+        std::fs::write(&path, &gguf[..gguf.len() - 8]).unwrap();
+        let truncated = GgufFile::from_file_with_options(&path, &options);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(err, GgufError::TrailingData { extra: 25, .. }));
+        // Only trailing bytes are rejected, truncation is left to verify_complete
+        assert!(truncated.is_ok());
+    }
+
+    #[test]
+    fn test_get_string_array() {
+        let mut metadata: GgufMetadata = sample_metadata().into_iter().collect();
+        assert_eq!(metadata.get_string_array("tokenizer.ggml.tokens").unwrap(), vec!["<s>", "</s>", "a"]);
+
+        assert!(matches!(
+            metadata.get_string_array("tokenizer.ggml.merges"),
+            Err(GgufError::MetadataKeyNotFound(key)) if key == "tokenizer.ggml.merges"
+        ));
+        assert!(matches!(
+            metadata.get_string_array("general.name"),
+            Err(GgufError::InvalidMetadataValueType { key, .. }) if key == "general.name"
+        ));
+
+        metadata.insert(
+            "tokenizer.ggml.tokens",
+            GgufValue::Array(vec![GgufValue::from("<s>"), GgufValue::from("</s>"), GgufValue::Uint32(7)]),
+        );
+        match metadata.get_string_array("tokenizer.ggml.tokens") {
+            Err(GgufError::InvalidMetadataValueType { key, expected, found }) => {
+                assert_eq!(key, "tokenizer.ggml.tokens[2]");
+                assert_eq!(expected, "string");
+                assert_eq!(found, "Uint32");
+            }
+            other => panic!("expected a type error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_effective_context() {
+        // Llama 3 8B, native context only
+        let mut llama3 = llama_config(4096, 32, 8, 14336, 32, 128256);
+        llama3.context_length = 8192;
+        let context = llama3.effective_context();
+        assert_eq!(context, EffectiveContext { native: 8192, extended: None, attention_window: None });
+        // 128 KiB of F16 cache per token
+        assert_eq!(llama3.max_context_for_memory(1 << 30, QuantizationType::F16), Some(8192));
+        assert_eq!(llama3.max_context_for_memory(1 << 28, QuantizationType::F16), Some(2048));
+        assert_eq!(llama3.max_context_for_memory(1 << 28, QuantizationType::Q8_0), Some(3855));
+
+        // Fine-tune extending a 2048 context eightfold with linear scaling
+        let mut longchat = llama_config(4096, 32, 32, 11008, 32, 32000);
+        longchat.context_length = 16384;
+        longchat.rope_scaling_type = Some("linear".to_string());
+        longchat.rope_scaling_factor = Some(8.0);
+        longchat.rope_scaling_original_context_length = Some(2048);
+        assert_eq!(longchat.effective_context(), EffectiveContext { native: 2048, extended: Some(16384), attention_window: None });
+        assert_eq!(longchat.effective_context().max(), 16384);
+
+        // context_length is already scaled, without the original it isn't scaled again
+        let mut unscaled = longchat.clone();
+        unscaled.rope_scaling_original_context_length = None;
+        assert_eq!(unscaled.effective_context(), EffectiveContext { native: 16384, extended: None, attention_window: None });
+
+        // A scaling type without a factor doesn't extend anything
+        longchat.rope_scaling_factor = None;
+        assert_eq!(longchat.effective_context().extended, None);
+
+        // LongRoPE stores the extended length as context_length
+        let mut phi3 = longchat.clone();
+        phi3.context_length = 131072;
+        phi3.rope_scaling_type = Some("longrope".to_string());
+        phi3.rope_scaling_original_context_length = Some(4096);
+        assert_eq!(phi3.effective_context(), EffectiveContext { native: 4096, extended: Some(131072), attention_window: None });
+
+        // Mistral 7B v0.1 with a 4096 token sliding window
+        let mut mistral = llama_config(4096, 32, 8, 14336, 32, 32000);
+        mistral.context_length = 32768;
+        mistral.attention_sliding_window = Some(4096);
+        assert_eq!(mistral.effective_context().attention_window, Some(4096));
+        assert_eq!(mistral.max_context_for_memory(1 << 29, QuantizationType::F16), Some(32768));
+        assert_eq!(mistral.max_context_for_memory(1 << 28, QuantizationType::F16), Some(2048));
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_token_array`
+    #[test]
+    #[ignore]
+    fn bench_token_array() {
+        use std::time::Instant;
+
+        // Llama 3 sized vocabulary of short tokens
+        let tokens: Vec<GgufValue> = (0..128_256u32).map(|i| GgufValue::String(format!("tok{i:x}"))).collect();
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &GgufValue::Array(tokens.clone()));
+        let path = std::env::temp_dir().join(format!("aiogguf-{}-bench-tokens.bin", std::process::id()));
+        std::fs::write(&path, &bytes).unwrap();
+        const RUNS: u32 = 20;
+
+        // Before: one length read, allocation and validation per token
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            let mut reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+            let mut header = [0u8; 12];
+            std::io::Read::read_exact(&mut reader, &mut header).unwrap();
+            let strings: Vec<_> = (0..tokens.len())
+                .map(|_| GgufValue::read(&mut reader, GgufValueType::String).unwrap())
+                .collect();
+            assert_eq!(strings.len(), tokens.len());
+        }
+        let per_element = start.elapsed() / RUNS;
+
+        // After: chunked reads sliced into strings
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            let mut reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+            let value = GgufValue::read(&mut reader, GgufValueType::Array).unwrap();
+            assert!(matches!(value, GgufValue::Array(ref items) if items.len() == tokens.len()));
+        }
+        let chunked = start.elapsed() / RUNS;
+
+        // Arena: chunked reads into one buffer, no allocation per token
+        let start = Instant::now();
+        for _ in 0..RUNS {
+            let mut reader = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+            let arena = TokenArena::read_array(&mut reader).unwrap();
+            assert_eq!(arena.len(), tokens.len());
+        }
+        let arena = start.elapsed() / RUNS;
+        std::fs::remove_file(&path).unwrap();
+
+        println!(
+            "{} tokens: per-element {per_element:?}, chunked {chunked:?} ({:.1}x), arena {arena:?} ({:.1}x)",
+            tokens.len(),
+            per_element.as_secs_f64() / chunked.as_secs_f64(),
+            per_element.as_secs_f64() / arena.as_secs_f64()
+        );
+    }
+
+    #[test]
+    fn test_string_array_spanning_chunks() {
+        // Larger than one read chunk, with strings straddling chunk boundaries
+        let tokens: Vec<GgufValue> = (0..40_000u32).map(|i| GgufValue::String(format!("token-{i}-é"))).collect();
+        let mut bytes = Vec::new();
+        write_value(&mut bytes, &GgufValue::Array(tokens.clone()));
+        write_value(&mut bytes, &GgufValue::Uint32(0xfeed));
+
+        let mut reader = Cursor::new(&bytes);
+        assert_eq!(GgufValue::read(&mut reader, GgufValueType::Array).unwrap(), GgufValue::Array(tokens));
+        assert_eq!(GgufValue::read(&mut reader, GgufValueType::Uint32).unwrap(), GgufValue::Uint32(0xfeed));
+        assert_eq!(reader.position(), bytes.len() as u64);
+
+        let mut invalid = Vec::new();
+        write_value(&mut invalid, &GgufValue::Array(vec![GgufValue::from("ok"), GgufValue::from("xx")]));
+        let last = invalid.len() - 1;
+        invalid[last] = 0xff;
+        assert!(matches!(
+            GgufValue::read(&mut Cursor::new(&invalid), GgufValueType::Array),
+            Err(GgufError::InvalidUtf8(_))
+        ));
+        assert!(GgufValue::read(&mut Cursor::new(&invalid[..last]), GgufValueType::Array).is_err());
+    }
+
+    #[test]
+    fn test_tokenizer_optional_arrays() {
+        // sample_metadata has scores but no token types
+        let config = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &[])))
+            .unwrap()
+            .model_config()
+            .unwrap();
+        assert!(config.has_tokenizer_scores());
+        assert!(!config.has_tokenizer_token_types());
+        assert!(config.tokenizer_ggml_scores.is_none());
+
+        let mut metadata = sample_metadata();
+        metadata.retain(|(key, _)| *key != "tokenizer.ggml.scores");
+        metadata.push(("tokenizer.ggml.token_type", GgufValue::Array(vec![GgufValue::Int32(1); 3])));
+        let config = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[])))
+            .unwrap()
+            .model_config()
+            .unwrap();
+        assert!(!config.has_tokenizer_scores());
+        assert!(config.has_tokenizer_token_types());
+    }
+
+    #[test]
+    fn test_inspect_report() {
+        let tensors = [
+            tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F16),
+            tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_k.weight", &[256, 32], QuantizationType::Q4_K),
+            tensor("blk.0.ffn_down.weight", &[256, 64], QuantizationType::Q6_K),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let report = gguf_file.inspect();
+
+        assert_eq!(report.tensor_count, 5);
+        assert_eq!(report.header.tensor_count, 5);
+        assert_eq!(report.model_config.as_ref().map(|c| c.block_count), Some(2));
+        assert_eq!(report.quant_histogram[0], (QuantizationType::Q4_K, 2));
+        assert_eq!(report.data_size, Some(gguf_file.total_size().unwrap()));
+        assert_eq!(report.total_size, Some(gguf_file.data_offset() + gguf_file.total_size().unwrap()));
+
+        let sizes: Vec<u64> = report.largest_tensors.iter().map(|t| t.size_bytes).collect();
+        assert!(sizes.windows(2).all(|pair| pair[0] >= pair[1]), "not sorted descending: {sizes:?}");
+        assert_eq!(report.largest_tensors[0].name, "blk.0.ffn_down.weight");
+
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(json.contains("\"largest_tensors\""));
+
+        // Data that fits in 64 bits only without the header has no total size
+        let huge = [tensor("token_embd.weight", &[(1 << 62) - 1], QuantizationType::F32)];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &huge))).unwrap();
+        let report = gguf_file.inspect();
+        assert_eq!(report.data_size, Some(u64::MAX - 3));
+        assert_eq!(report.total_size, None);
+        let err = QuantComparison::build(&[gguf_file]).unwrap_err();
+        assert!(matches!(err, GgufError::SizeOverflow(_)));
+    }
+
+    #[test]
+    fn test_metadata_clone_shares_values() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<GgufFile>();
+        assert_send_sync::<ModelConfig>();
+
+        let tokens: Vec<String> = (0..150_000).map(|i| format!("token{i}")).collect();
+        let mut metadata = sample_metadata();
+        metadata.retain(|(key, _)| *key != "tokenizer.ggml.tokens");
+        metadata.push(("tokenizer.ggml.tokens", GgufValue::from(tokens)));
+        let original: GgufMetadata = metadata.into_iter().collect();
+
+        let clones: Vec<GgufMetadata> = (0..1000).map(|_| original.clone()).collect();
+        let shared = original.get(keys::TOKENIZER_GGML_TOKENS).unwrap();
+        assert!(clones.iter().all(|clone| core::ptr::eq(shared, clone.get(keys::TOKENIZER_GGML_TOKENS).unwrap())));
+        assert_eq!(clones[999], original);
+
+        // Mutating a clone copies only the value it touches
+        let mut edited = original.clone();
+        let removed = edited.remove(keys::TOKENIZER_GGML_TOKENS).unwrap();
+        assert!(matches!(removed, GgufValue::Array(ref items) if items.len() == 150_000));
+        assert!(original.contains(keys::TOKENIZER_GGML_TOKENS));
+
+        let mut config = llama_config(64, 4, 2, 256, 2, 32000);
+        config.tokenizer_ggml_tokens = Some(vec![String::new(); 32000].into());
+        let copy = config.clone();
+        assert!(Arc::ptr_eq(config.tokenizer_ggml_tokens.as_ref().unwrap(), copy.tokenizer_ggml_tokens.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn test_warnings_on_gguf_file() {
+        let mut metadata = sample_metadata();
+        metadata.push(("general.name", GgufValue::String("Renamed".to_string())));
+        metadata.push(("general.quantization_version", GgufValue::Uint32(1)));
+        let tensors = vec![tensor("blk.0.ffn_up.weight", &[64, 256], QuantizationType::Unknown(99))];
+        let bytes = build_gguf(&metadata, &tensors);
+
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let kinds: Vec<_> = gguf_file.warnings().iter().map(|w| w.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                WarningKind::DuplicateKey,
+                WarningKind::UnknownQuantType,
+                WarningKind::InferredVocabSize,
+                WarningKind::UnexpectedQuantizationVersion,
+            ]
+        );
+
+        // The duplicate's offset points at its key length prefix
+        let duplicate = &gguf_file.warnings()[0];
+        assert_eq!(duplicate.subject(), "general.name");
+        let offset = duplicate.offset.unwrap() as usize;
+        assert_eq!(&bytes[offset + 8..offset + 8 + "general.name".len()], b"general.name");
+        assert_eq!(
+            duplicate.to_string(),
+            format!("metadata key 'general.name' appears more than once (at byte offset {offset})")
+        );
+        assert_eq!(gguf_file.warnings()[1].subject(), "blk.0.ffn_up.weight");
+        assert_eq!(gguf_file.warnings()[1].offset, None);
+
+        let options = ParseOptions { warnings_as_errors: vec![WarningKind::UnknownQuantType], ..Default::default() };
+        match GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options) {
+            Err(GgufError::PromotedWarning(warning)) => assert_eq!(warning.subject(), "blk.0.ffn_up.weight"),
+            other => panic!("expected a promoted warning, got {other:?}"),
+        }
+
+        let clean = build_gguf(&[("general.architecture", GgufValue::String("llama".to_string()))], &[]);
+        let strict = ParseOptions { warnings_as_errors: vec![WarningKind::DuplicateKey], ..Default::default() };
+        assert!(GgufFile::from_reader_with_options(&mut Cursor::new(&clean), &strict).unwrap().warnings().is_empty());
+    }
+
+    #[test]
+    fn test_suspicious_value_and_lossy_utf8_warnings() {
+        let mut metadata = sample_metadata();
+        metadata.push(("llama.context_length", GgufValue::Uint64(u64::MAX)));
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        let suspicious = ParseWarning::SuspiciousValue { key: "llama.context_length".to_string(), value: u64::MAX };
+        assert!(gguf_file.warnings().iter().any(|w| w.warning == suspicious));
+
+        // Invalid UTF-8 in a string value fails unless decoded lossily
+        let mut bytes = build_gguf(&[("general.name", GgufValue::String("Tiny".to_string()))], &[]);
+        let position = bytes.windows(4).position(|w| w == b"Tiny").unwrap();
+        bytes[position] = 0xFF;
+        assert!(matches!(GgufFile::from_reader(&mut Cursor::new(&bytes)), Err(GgufError::InvalidUtf8(_))));
+        let options = ParseOptions { lossy_utf8: true, ..Default::default() };
+        let gguf_file = GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options).unwrap();
+        assert_eq!(gguf_file.name(), Some("\u{FFFD}iny"));
+        assert_eq!(gguf_file.warnings()[0].warning, ParseWarning::LossyUtf8("general.name".to_string()));
+        assert!(gguf_file.warnings()[0].offset.is_some());
+
+        let options = ParseOptions { warnings_as_errors: vec![WarningKind::LossyUtf8], ..options };
+        assert!(matches!(
+            GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options),
+            Err(GgufError::PromotedWarning(warning)) if warning.kind() == WarningKind::LossyUtf8
+        ));
+    }
+
+    #[test]
+    fn test_tensors_in_disk_order() {
+        let mut tensors = vec![
+            tensor("output.weight", &[64, 3], QuantizationType::F32),
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+        ];
+        tensors[0].offset = 1024;
+        tensors[2].offset = 768;
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        let names: Vec<_> = gguf_file.tensors_in_disk_order().iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["token_embd.weight", "output_norm.weight", "output.weight"]);
+        assert_eq!(gguf_file.tensors[0].name, "output.weight");
+    }
+
+    #[test]
+    fn test_section_write_read_roundtrip() {
+        // xorshift64, deterministic so failures reproduce
+        let mut state = 0x9e37_79b9_7f4a_7c15u64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        fn random_value(next: &mut dyn FnMut() -> u64, value_type: u64, depth: usize) -> GgufValue {
+            let bits = next();
+            match value_type % 13 {
+                0 => GgufValue::Uint8(bits as u8),
+                1 => GgufValue::Int8(bits as i8),
+                2 => GgufValue::Uint16(bits as u16),
+                3 => GgufValue::Int16(bits as i16),
+                4 => GgufValue::Uint32(bits as u32),
+                5 => GgufValue::Int32(bits as i32),
+                6 => GgufValue::Float32(bits as i32 as f32 / 7.0),
+                7 => GgufValue::Bool(bits & 1 == 1),
+                8 => GgufValue::String((0..bits % 12).map(|i| char::from(b'a' + ((bits >> i) % 26) as u8)).collect()),
+                9 if depth < 2 => {
+                    let element_type = next();
+                    GgufValue::Array((0..bits % 6).map(|_| random_value(next, element_type, depth + 1)).collect())
+                }
+                9 | 10 => GgufValue::Uint64(bits),
+                11 => GgufValue::Int64(bits as i64),
+                _ => GgufValue::Float64(bits as i64 as f64 / 3.0),
+            }
+        }
+
+        let quant_types: Vec<_> = QuantizationType::all().collect();
+        for round in 0..200 {
+            let metadata: GgufMetadata = (0..next() % 16)
+                .map(|i| {
+                    let value_type = next();
+                    (format!("key.{round}.{i}"), random_value(&mut next, value_type, 0))
+                })
+                .collect();
+            let tensors: Vec<_> = (0..next() % 8)
+                .map(|i| TensorInfo {
+                    name: format!("blk.{i}.weight"),
+                    dimensions: (0..1 + next() % 4).map(|_| 1 + next() % 4096).collect(),
+                    quantization_type: quant_types[next() as usize % quant_types.len()],
+                    offset: next() % (1 << 40),
+                })
+                .collect();
+            let header = GgufHeader {
+                magic: *b"GGUF",
+                version: 3,
+                tensor_count: tensors.len() as u64,
+                metadata_kv_count: metadata.len() as u64,
+            };
+
+            let mut bytes = Vec::new();
+            header.write(&mut bytes).unwrap();
+            metadata.write(&mut bytes).unwrap();
+            for tensor in &tensors {
+                tensor.write(&mut bytes).unwrap();
+            }
+
+            let mut reader = Cursor::new(&bytes);
+            assert_eq!(GgufHeader::read(&mut reader).unwrap(), header);
+            let read_metadata = GgufMetadata::read(&mut reader, header.metadata_kv_count).unwrap();
+            assert!(read_metadata.iter().eq(metadata.iter()), "metadata differs in round {round}");
+            assert_eq!(TensorInfo::read_all(&mut reader, header.tensor_count).unwrap(), tensors);
+            assert_eq!(reader.position(), bytes.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_write_rejects_mixed_array() {
+        let mixed = GgufValue::Array(vec![GgufValue::Uint32(1), GgufValue::String("two".to_string())]);
+        let mut bytes = Vec::new();
+        assert_eq!(
+            mixed.write(&mut bytes).unwrap_err(),
+            GgufError::MixedArrayTypes { expected: GgufValueType::Uint32, found: GgufValueType::String }
+        );
+        assert!(bytes.is_empty());
+
+        // Nested arrays only need to agree on being arrays
+        let nested = GgufValue::Array(vec![
+            GgufValue::Array(vec![GgufValue::Uint8(1)]),
+            GgufValue::Array(vec![GgufValue::Bool(true)]),
+        ]);
+        nested.write(&mut bytes).unwrap();
+    }
+
+    #[test]
+    fn test_rewrite_with_alignment() {
+        // The second tensor starts at 40, breaking the declared alignment of 32
+        let mut tensors = vec![
+            tensor("token_embd.weight", &[10], QuantizationType::F32),
+            tensor("output.weight", &[7], QuantizationType::F32),
+        ];
+        tensors[1].offset = 40;
+        let mut bytes = build_gguf(&sample_metadata(), &tensors);
+        let data_offset = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap().data_offset() as usize;
+        bytes.resize(data_offset, 0);
+        bytes.extend((0..68u8).map(|i| i.wrapping_mul(37)));
+
+        let tensor_data = |path: &Path| {
+            let gguf_file = GgufFile::from_file(path).unwrap();
+            let file = std::fs::read(path).unwrap();
+            let data: Vec<Vec<u8>> = gguf_file
+                .tensors
+                .iter()
+                .map(|t| {
+                    let range = t.byte_range(gguf_file.data_offset()).unwrap();
+                    file[range.start as usize..range.end as usize].to_vec()
+                })
+                .collect();
+            (gguf_file, data)
+        };
+
+        let dir = std::env::temp_dir();
+        let input = dir.join(format!("aiogguf-{}-align-in.gguf", std::process::id()));
+        let wide = dir.join(format!("aiogguf-{}-align-4096.gguf", std::process::id()));
+        let narrow = dir.join(format!("aiogguf-{}-align-32.gguf", std::process::id()));
+        std::fs::write(&input, &bytes).unwrap();
+        let (_, original) = tensor_data(&input);
+
+        GgufFile::rewrite_with_alignment(&input, &wide, 4096).unwrap();
+        let (wide_file, wide_data) = tensor_data(&wide);
+        assert_eq!(wide_file.alignment(), 4096);
+        assert_eq!(wide_file.data_offset() % 4096, 0);
+        assert_eq!(wide_file.tensors.iter().map(|t| t.offset).collect::<Vec<_>>(), [0, 4096]);
+        assert_eq!(wide_data, original);
+
+        GgufFile::rewrite_with_alignment(&wide, &narrow, 32).unwrap();
+        let (narrow_file, narrow_data) = tensor_data(&narrow);
+        assert_eq!(narrow_file.alignment(), 32);
+        assert_eq!(narrow_file.tensors.iter().map(|t| t.offset).collect::<Vec<_>>(), [0, 64]);
+        assert_eq!(narrow_data, original);
+
+        assert!(matches!(
+            GgufFile::rewrite_with_alignment(&input, &narrow, 48),
+            Err(GgufError::InvalidAlignment(48))
+        ));
+
+        // Rewriting in place would truncate the source before reading it
+        let same = input.parent().unwrap().join(".").join(input.file_name().unwrap());
+        assert!(matches!(
+            GgufFile::rewrite_with_alignment(&input, &same, 4096),
+            Err(GgufError::OutputIsInput(_))
+        ));
+        assert_eq!(std::fs::read(&input).unwrap(), bytes);
+
+        for path in [&input, &wide, &narrow] {
+            std::fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_read_helpers() {
+        use crate::read::*;
+
+        let mut bytes = vec![0xab];
+        bytes.extend_from_slice(&0xbeefu16.to_le_bytes());
+        bytes.extend_from_slice(&0xdead_beefu32.to_le_bytes());
+        bytes.extend_from_slice(&u64::MAX.to_le_bytes());
+        bytes.extend_from_slice(&1.5f32.to_le_bytes());
+        bytes.extend_from_slice(&(-2.25f64).to_le_bytes());
+        write_string(&mut bytes, "héllo");
+        let mut reader = Cursor::new(&bytes);
+        assert_eq!(read_u8(&mut reader).unwrap(), 0xab);
+        assert_eq!(read_u16(&mut reader).unwrap(), 0xbeef);
+        assert_eq!(read_u32(&mut reader).unwrap(), 0xdead_beef);
+        assert_eq!(read_u64(&mut reader).unwrap(), u64::MAX);
+        assert_eq!(read_f32(&mut reader).unwrap(), 1.5);
+        assert_eq!(read_f64(&mut reader).unwrap(), -2.25);
+        assert_eq!(read_string(&mut reader, &ParseLimits::UNLIMITED).unwrap(), "héllo");
+        assert_eq!(reader.position(), bytes.len() as u64);
+
+        // Short reads fail rather than returning partial values
+        assert!(read_u32(&mut Cursor::new(&[1u8, 2, 3])).is_err());
+        assert!(read_string(&mut Cursor::new(&[4u8, 0, 0, 0, 0, 0, 0, 0, b'a']), &ParseLimits::UNLIMITED).is_err());
+        let mut invalid = Vec::new();
+        invalid.extend_from_slice(&2u64.to_le_bytes());
+        invalid.extend_from_slice(&[0xff, 0xfe]);
+        assert!(matches!(read_string(&mut Cursor::new(&invalid), &ParseLimits::UNLIMITED), Err(GgufError::InvalidUtf8(_))));
+        let mut slice = io::SliceReader::new(&[7, 0]);
+        assert_eq!(read_u16(&mut slice).unwrap(), 7);
+    }
+
+    #[test]
+    fn test_display_truncated() {
+        let tokens = GgufValue::from((0..150_000).map(|i| format!("t{i}")).collect::<Vec<_>>());
+        assert_eq!(tokens.display_truncated(3), r#"["t0", "t1", "t2", ... (+149997 more)]"#);
+        assert_eq!(tokens.display_truncated(0), "[... (+150000 more)]");
+
+        let short = GgufValue::from(vec![1u32, 2]);
+        assert_eq!(short.display_truncated(2), "[1, 2]");
+        let nested = GgufValue::Array(vec![short.clone(), short]);
+        assert_eq!(nested.display_truncated(1), "[[1, ... (+1 more)], ... (+1 more)]");
+        assert_eq!(GgufValue::Float32(0.5).display_truncated(1), "0.5");
+    }
+
+    #[test]
+    fn test_field_lookup_builds_no_keys() {
+        let mut entries = sample_metadata();
+        entries.push(("llama.rope.theta", GgufValue::Float32(500000.0)));
+        entries.push(("llama.rope.freq_base", GgufValue::Float32(10000.0)));
+        entries.push(("general.base_model.count", GgufValue::Uint32(2)));
+        entries.push(("general.base_model.1.name", GgufValue::from("Second")));
+        entries.push(("general.base_model.01.name", GgufValue::from("Not canonical")));
+        entries.push(("general.base_model.0.repo_url", GgufValue::from("https://example.com")));
+        let mut metadata: GgufMetadata = entries.into_iter().collect();
+        for i in 0..300u32 {
+            metadata.insert(format!("custom.key.{i}"), i);
+        }
+
+        let (rope_freq_base, allocations) = count_allocations(|| {
+            let mut lookup = crate::metadata::FieldLookup::new(&metadata, "llama");
+            for (field, _) in keys::FIELD_ALIASES {
+                lookup.value(field);
+            }
+            lookup.value("rope_freq_base").and_then(|v| v.as_f32().ok())
+        });
+        assert_eq!(allocations, 0);
+        assert_eq!(rope_freq_base, Some(10000.0));
+
+        let config = ModelConfig::from_metadata(&metadata).unwrap();
+        assert_eq!(config.rope_freq_base, Some(10000.0));
+        assert_eq!(config.source_keys["rope_freq_base"], "llama.rope.freq_base");
+        assert_eq!(config.source_keys["block_count"], "llama.block_count");
+        assert_eq!(config.base_models.len(), 2);
+        assert_eq!(config.base_models[0].repo_url.as_deref(), Some("https://example.com"));
+        assert_eq!(config.base_models[1].name.as_deref(), Some("Second"));
+        assert_eq!(config.base_models[0].name, None);
+    }
+
+    #[test]
+    fn test_parse_stats() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let (gguf_file, stats) = GgufFile::from_reader_with_stats(&mut Cursor::new(&bytes)).unwrap();
+        assert_eq!(stats.kv_count, sample_metadata().len() as u64);
+        assert_eq!(stats.tensor_count, 2);
+        assert_eq!(stats.bytes_read, bytes.len() as u64);
+        assert_eq!(
+            stats.bytes_read,
+            gguf_file.header_bytes() + gguf_file.metadata_bytes() + gguf_file.tensor_info_bytes()
+        );
+    }
+
+    #[test]
+    fn test_vocab_token_types() {
+        let tokens = ["<unk>", "<s>", "hello", "<|im_start|>", "<0x0A>", "[PAD]", "odd"];
+        let types = [2, 3, 1, 4, 6, 5, 99];
+        let mut metadata = sample_metadata();
+        metadata.retain(|(key, _)| *key != "tokenizer.ggml.tokens");
+        metadata.push(("tokenizer.ggml.tokens", GgufValue::from(tokens.map(String::from).to_vec())));
+        metadata.push(("tokenizer.ggml.token_type", GgufValue::from(types.to_vec())));
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+
+        let vocab = gguf_file.vocab().unwrap();
+        assert_eq!(vocab.len(), 7);
+        assert_eq!(vocab.control_tokens(), [(1, "<s>")]);
+        assert_eq!(vocab.user_defined_tokens(), [(3, "<|im_start|>")]);
+        assert_eq!(vocab.byte_tokens(), [(4, "<0x0A>")]);
+        assert_eq!(vocab.tokens_of_type(TokenType::Unused), [(5, "[PAD]")]);
+        assert_eq!(vocab.token_type(0), Some(TokenType::Unknown));
+        assert_eq!(vocab.token_type(2), Some(TokenType::Normal));
+        assert_eq!(vocab.token_type(6), Some(TokenType::Undefined));
+        assert!(vocab.is_control(1) && !vocab.is_control(2) && !vocab.is_control(100));
+        assert_eq!(vocab.find("hello"), Some(2));
+        assert_eq!(vocab.missing_special_tokens(&["<|im_start|>", "<|im_end|>", "hello"]), ["<|im_end|>", "hello"]);
+
+        // Markers come from every template, the default and named ones
+        metadata.push((
+            "tokenizer.chat_template",
+            GgufValue::from("{% for m in messages %}{{ '<|im_start|>' + m['role'] }}{% if loop.index < 3 %}<|im_end|>{% endif %}{% endfor %}"),
+        ));
+        metadata.push(("tokenizer.chat_template.tool_use", GgufValue::from("{{ '<s>' }}<tool_call>{{ '<0x0A>' }}")));
+        let metadata: GgufMetadata = metadata.into_iter().collect();
+        let templates = ChatTemplates::from_metadata(&metadata);
+        assert!(templates.default_template().unwrap().starts_with("{% for"));
+        assert_eq!(templates.get("tool_use"), Some("{{ '<s>' }}<tool_call>{{ '<0x0A>' }}"));
+        assert_eq!(templates.markers(), ["<|im_start|>", "<|im_end|>", "<s>", "<tool_call>", "<0x0A>"]);
+        let vocab = Vocab::from_metadata(&metadata).unwrap();
+        assert_eq!(templates.verify_against_vocab(&vocab), ["<|im_end|>", "<tool_call>", "<0x0A>"]);
+
+        // Without the type array every token is undefined
+        let metadata: GgufMetadata = sample_metadata().into_iter().collect();
+        let vocab = Vocab::from_metadata(&metadata).unwrap();
+        assert_eq!(vocab.token_type(0), Some(TokenType::Undefined));
+        assert!(vocab.control_tokens().is_empty());
+
+        // A bad element reports its type, not its value
+        let mut metadata: GgufMetadata = sample_metadata().into_iter().collect();
+        metadata.insert("tokenizer.ggml.token_type", GgufValue::from(vec!["a".to_string(); 3]));
+        assert_eq!(
+            Vocab::from_metadata(&metadata).unwrap_err(),
+            GgufError::InvalidMetadataValueType {
+                key: "tokenizer.ggml.token_type[0]".to_string(),
+                expected: "integer".to_string(),
+                found: "String".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_size_label() {
+        let mut metadata = sample_metadata();
+        metadata.push(("general.size_label", GgufValue::from("1.1B")));
+        let config = ModelConfig::from_metadata(&metadata.into_iter().collect()).unwrap();
+        assert_eq!(config.general_size_label.as_deref(), Some("1.1B"));
+        assert_eq!(config.source_keys["general_size_label"], "general.size_label");
+
+        assert_eq!(llama_config(2048, 32, 4, 5632, 22, 32000).derived_size_label(), "1.1B");
+        assert_eq!(llama_config(4096, 32, 32, 11008, 32, 32000).derived_size_label(), "6.7B");
+        assert_eq!(llama_config(5120, 40, 40, 13824, 40, 32000).derived_size_label(), "13B");
+        assert_eq!(llama_config(8192, 64, 8, 28672, 80, 128256).derived_size_label(), "71B");
+        assert_eq!(llama_config(64, 4, 2, 256, 2, 3).derived_size_label(), "124K");
+    }
+
+    #[test]
+    fn test_data_layout_tiles_data_section() {
+        let mut tensors = vec![
+            tensor("output.weight", &[10], QuantizationType::F32),
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[7], QuantizationType::F32),
+        ];
+        let mut offset = 0;
+        for index in [1, 0, 2] {
+            tensors[index].offset = offset;
+            offset += tensors[index].size_bytes().unwrap().div_ceil(32) * 32;
+        }
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+
+        let layout = gguf_file.data_layout().unwrap();
+        let names: Vec<_> = layout.iter().map(|extent| extent.name.as_str()).collect();
+        assert_eq!(names, ["token_embd.weight", "output.weight", "output_norm.weight"]);
+        assert_eq!(layout[0].offset, gguf_file.data_offset());
+        for pair in layout.windows(2) {
+            assert_eq!(pair[0].offset + pair[0].padded_len, pair[1].offset);
+        }
+        assert_eq!(layout[1].len, 40);
+        assert_eq!(layout[1].padded_len, 64);
+        assert_eq!(layout.iter().map(|extent| extent.padded_len).sum::<u64>(), offset);
+
+        let gaps = gguf_file.data_gaps().unwrap();
+        assert!(gaps.iter().all(|gap| gap.kind == GapKind::Padding));
+        assert_eq!(gaps, [DataGap { offset: layout[1].end(), len: 24, kind: GapKind::Padding }]);
+
+        // Moving the last tensor further out leaves a hole after the padding
+        tensors[2].offset += 64;
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let gaps = gguf_file.data_gaps().unwrap();
+        let end = gguf_file.data_layout().unwrap()[1].end();
+        assert_eq!(
+            gaps,
+            [
+                DataGap { offset: end, len: 24, kind: GapKind::Padding },
+                DataGap { offset: end + 24, len: 64, kind: GapKind::Hole },
+            ]
+        );
+        assert!(serde_json::to_string(&gguf_file.data_layout().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn test_try_from_value() {
+        assert_eq!(u32::try_from(&GgufValue::Uint32(7)).unwrap(), 7);
+        assert_eq!(u64::try_from(&GgufValue::Uint32(7)).unwrap(), 7);
+        assert_eq!(i32::try_from(&GgufValue::Int32(-7)).unwrap(), -7);
+        assert_eq!(i64::try_from(&GgufValue::Int32(-7)).unwrap(), -7);
+        assert_eq!(f32::try_from(&GgufValue::Float32(0.5)).unwrap(), 0.5);
+        assert_eq!(f64::try_from(&GgufValue::Float32(0.5)).unwrap(), 0.5);
+        assert!(bool::try_from(&GgufValue::Bool(true)).unwrap());
+        assert_eq!(String::try_from(&GgufValue::from("llama")).unwrap(), "llama");
+
+        assert!(u32::try_from(&GgufValue::Int32(7)).is_err());
+        assert!(i32::try_from(&GgufValue::Int64(7)).is_err());
+        assert!(f32::try_from(&GgufValue::Float64(0.5)).is_err());
+        assert!(bool::try_from(&GgufValue::Uint8(1)).is_err());
+        assert!(matches!(
+            String::try_from(&GgufValue::Uint32(1)),
+            Err(GgufError::InvalidMetadataValueType { expected, .. }) if expected == "string"
+        ));
+    }
+
+    #[test]
+    fn test_canonical_value_strings() {
+        assert_eq!(GgufValue::Float32(f32::NAN).to_canonical_string(), "NaN");
+        assert_eq!(GgufValue::Float32(-0.0).to_canonical_string(), "-0.0");
+        assert_eq!(GgufValue::Float32(1e-45).to_canonical_string(), "1e-45");
+        assert_eq!(GgufValue::Float32(1e-5).to_canonical_string(), "1e-5");
+        assert_eq!(GgufValue::Float32(0.1).to_canonical_string(), "0.1");
+        assert_eq!(GgufValue::Float64(1e300).to_canonical_string(), "1e300");
+        assert_eq!(GgufValue::Float32(f32::INFINITY).to_canonical_string(), "inf");
+        assert_eq!(GgufValue::Float32(10000.0).to_canonical_string(), "10000.0");
+        assert_eq!(GgufValue::Int64(-3).to_canonical_string(), "-3");
+        assert_eq!(GgufValue::Bool(false).to_canonical_string(), "false");
+        assert_eq!(
+            GgufValue::from("{% for m in messages %}\n\t\"{{ m }}\"\u{1b}").to_canonical_string(),
+            r#""{% for m in messages %}\n\t\"{{ m }}\"\u{1b}""#
+        );
+
+        let long = GgufValue::from((0..20u32).collect::<Vec<_>>());
+        assert_eq!(
+            long.to_string(),
+            "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, ... (+4 more)]"
+        );
+    }
+
+    #[test]
+    fn test_missing_tensors() {
+        let tensors = vec![
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("blk.0.ffn_up.weight", &[64, 256], QuantizationType::F32),
+            tensor("blk.0.ffn_down.weight", &[256, 64], QuantizationType::F32),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let expected: Vec<String> = ["token_embd.weight", "blk.0.ffn_gate.weight", "blk.0.ffn_up.weight", "blk.0.ffn_down.weight"]
+            .map(String::from)
+            .to_vec();
+        assert_eq!(gguf_file.missing_tensors(&expected), ["blk.0.ffn_gate.weight"]);
+        assert!(gguf_file.missing_tensors(&expected[..1]).is_empty());
+    }
+
+    #[test]
+    fn test_model_config_falls_back_to_other_key_prefix() {
+        let metadata: Vec<_> = sample_metadata()
+            .into_iter()
+            .map(|(key, value)| match key {
+                "general.architecture" => (key, GgufValue::String("qwen2".to_string())),
+                _ => (key, value),
+            })
+            .collect();
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        let config = ModelConfig::from_metadata(&gguf_file.metadata).unwrap();
+        assert_eq!(config.architecture, "qwen2");
+        assert_eq!(config.key_prefix, "llama");
+        assert_eq!(config.block_count, 2);
+        assert_eq!(config.embedding_length, 64);
+        let mismatch = ParseWarning::KeyPrefixMismatch { architecture: "qwen2".to_string(), prefix: "llama".to_string() };
+        assert!(gguf_file.warnings().iter().any(|w| w.warning == mismatch));
+        assert_eq!(gguf_file.context_length(), Some(2048));
+
+        // Without the default fallback the prefix is still found from block_count
+        let config = ModelConfig::from_metadata_with_fallbacks(&gguf_file.metadata, &[]).unwrap();
+        assert_eq!(config.key_prefix, "llama");
+        assert_eq!(config.block_count, 2);
+        assert_eq!(detect_key_prefix(&gguf_file.metadata), Some("llama"));
+
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let config = ModelConfig::from_metadata(&gguf_file.metadata).unwrap();
+        assert_eq!(config.key_prefix, "llama");
+        assert!(gguf_file.warnings().iter().all(|w| w.kind() != WarningKind::KeyPrefixMismatch));
+    }
+
+    #[test]
+    fn test_split_info_and_validation() {
+        let split_keys = |no: u16, count: u16, tensors: i32| {
+            vec![
+                ("split.no", GgufValue::Uint16(no)),
+                ("split.count", GgufValue::Uint16(count)),
+                ("split.tensors.count", GgufValue::Int32(tensors)),
+            ]
+        };
+        let parse = |metadata: &[(&str, GgufValue)], tensors: &[TensorInfo]| {
+            GgufFile::from_reader(&mut Cursor::new(build_gguf(metadata, tensors))).unwrap()
+        };
+
+        let unsplit = parse(&sample_metadata(), &[]);
+        assert_eq!(unsplit.split_info(), None);
+        assert!(unsplit.validate_split().is_empty());
+
+        let mut first = sample_metadata();
+        first.extend(split_keys(0, 3, 5));
+        let shard = parse(&first, &[tensor("token_embd.weight", &[64, 3], QuantizationType::F32)]);
+        let info = shard.split_info().unwrap();
+        assert_eq!(info, SplitInfo { no: 0, count: 3, tensors_count: 5 });
+        assert!(info.is_first());
+        assert!(shard.validate_split().is_empty());
+
+        let json = serde_json::to_string(&info).unwrap();
+        assert_eq!(serde_json::from_str::<SplitInfo>(&json).unwrap(), info);
+
+        let later = parse(&split_keys(2, 3, 5), &[tensor("blk.1.ffn_up.weight", &[64, 256], QuantizationType::F32)]);
+        assert_eq!(later.split_info().map(|info| info.no), Some(2));
+        assert!(later.validate_split().is_empty());
+
+        let mut bad = sample_metadata();
+        bad.extend(split_keys(3, 3, 1));
+        let tensors = [
+            tensor("a.weight", &[4], QuantizationType::F32),
+            tensor("b.weight", &[4], QuantizationType::F32),
+        ];
+        assert_eq!(
+            parse(&bad, &tensors).validate_split(),
+            vec![
+                SplitWarning::NumberOutOfRange { no: 3, count: 3 },
+                SplitWarning::UnexpectedModelMetadata { no: 3 },
+                SplitWarning::TooManyTensors { tensors: 2, tensors_count: 1 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_expected_tensor_names() {
+        let mut config = llama_config(64, 4, 2, 256, 3, 100);
+        let names = config.expected_tensor_names(false).unwrap();
+        assert_eq!(names.len(), 3 * 9 + 3);
+        assert_eq!(&names[..4], ["token_embd.weight", "output_norm.weight", "output.weight", "blk.0.attn_norm.weight"]);
+        assert!(names.contains(&"blk.2.ffn_down.weight".to_string()));
+
+        config.architecture = "qwen2".to_string();
+        let names = config.expected_tensor_names(false).unwrap();
+        assert_eq!(names.len(), 3 * 12 + 3);
+        assert!(names.contains(&"blk.1.attn_k.bias".to_string()));
+
+        config.architecture = "llama".to_string();
+        config.expert_count = Some(8);
+        let names = config.expected_tensor_names(false).unwrap();
+        assert_eq!(names.len(), 3 * 10 + 3);
+        assert!(names.contains(&"blk.0.ffn_gate_exps.weight".to_string()));
+        assert!(!names.contains(&"blk.0.ffn_gate.weight".to_string()));
+
+        config.architecture = "qwen2moe".to_string();
+        assert_eq!(config.expected_tensor_names(false).unwrap().len(), 3 * 17 + 3);
+
+        // Layouts that differ from llama's aren't guessed
+        for architecture in ["mamba", "phi3", "gemma2"] {
+            config.architecture = architecture.to_string();
+            assert_eq!(config.expected_tensor_names(false), None, "{architecture}");
+        }
+        config.architecture = "qwen2moe".to_string();
+
+        // A complete file has nothing missing
+        let tensors: Vec<_> = llama_config(64, 4, 2, 256, 2, 100)
+            .expected_tensor_names(false)
+            .unwrap()
+            .iter()
+            .map(|name| tensor(name, &[4], QuantizationType::F32))
+            .collect();
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        let expected = gguf_file.expected_tensor_names().unwrap();
+        assert!(gguf_file.missing_tensors(&expected).is_empty());
+
+        // Tied embeddings store no output.weight and don't expect one
+        let tied: Vec<_> = tensors.into_iter().filter(|t| t.name != "output.weight").collect();
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tied))).unwrap();
+        let expected = gguf_file.expected_tensor_names().unwrap();
+        assert!(!expected.contains(&"output.weight".to_string()));
+        assert!(gguf_file.missing_tensors(&expected).is_empty());
+        assert_eq!(config.expected_tensor_names(true).unwrap().len(), 17 * 3 + 2);
+
+        let metadata: Vec<_> = sample_metadata()
+            .into_iter()
+            .map(|(key, value)| match key {
+                "general.architecture" => (key, GgufValue::from("phi3")),
+                _ => (key, value),
+            })
+            .collect();
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &tied))).unwrap();
+        assert_eq!(gguf_file.expected_tensor_names(), Err(GgufError::UnknownTensorLayout("phi3".to_string())));
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let parse = |limits: ParseLimits| {
+            let options = ParseOptions { limits, ..Default::default() };
+            GgufFile::from_reader_with_options(&mut Cursor::new(&bytes), &options)
+        };
+        let exceeded = |limits: ParseLimits| match parse(limits) {
+            Err(GgufError::LimitExceeded { limit, value }) => (limit, value),
+            other => panic!("expected LimitExceeded, got {other:?}"),
+        };
+
+        assert!(parse(ParseLimits::default()).is_ok());
+        assert!(parse(ParseLimits::UNLIMITED).is_ok());
+
+        let kv_count = sample_metadata().len() as u64;
+        let limits = ParseLimits { max_metadata_kv: kv_count - 1, ..Default::default() };
+        assert_eq!(exceeded(limits), ("max_metadata_kv", kv_count));
+        assert_eq!(exceeded(ParseLimits { max_tensors: 1, ..Default::default() }), ("max_tensors", 2));
+        // "llama.embedding_length" is the first key over 20 bytes
+        assert_eq!(exceeded(ParseLimits { max_string_len: 20, ..Default::default() }), ("max_string_len", 22));
+        assert_eq!(exceeded(ParseLimits { max_array_len: 2, ..Default::default() }), ("max_array_len", 3));
+        assert_eq!(exceeded(ParseLimits { max_total_bytes: 100, ..Default::default() }).0, "max_total_bytes");
+
+        // The whole metadata and tensor info section fits exactly
+        let parsed = parse(ParseLimits::default()).unwrap();
+        let used = parsed.header_bytes() + parsed.metadata_bytes() + parsed.tensor_info_bytes();
+        assert!(parse(ParseLimits { max_total_bytes: used, ..Default::default() }).is_ok());
+        assert_eq!(
+            exceeded(ParseLimits { max_total_bytes: used - 1, ..Default::default() }),
+            ("max_total_bytes", used)
+        );
+
+        // A hostile tensor count is rejected before anything is allocated for it
+        let mut hostile = bytes.clone();
+        hostile[8..16].copy_from_slice(&u64::MAX.to_le_bytes());
+        let options = ParseOptions::default();
+        assert!(matches!(
+            GgufFile::from_reader_with_options(&mut Cursor::new(&hostile), &options),
+            Err(GgufError::LimitExceeded { limit: "max_tensors", value: u64::MAX })
+        ));
+    }
+
+    #[test]
+    fn test_metadata_stats() {
+        let mut metadata = GgufMetadata::default();
+        metadata.insert("general.architecture", "llama");
+        metadata.insert("general.name", "tiny");
+        metadata.insert("llama.block_count", 2u32);
+        metadata.insert("llama.embedding_length", 64u32);
+        metadata.insert("custom.note", "hello");
+        let tokens: Vec<_> = (0..1000).map(|i| GgufValue::String(format!("tok{i}"))).collect();
+        metadata.insert("tokenizer.ggml.tokens", GgufValue::Array(tokens));
+
+        let stats = metadata.stats();
+        assert_eq!(stats.entry_count, 6);
+        assert_eq!(stats.type_counts, vec![(GgufValueType::String, 3), (GgufValueType::Uint32, 2), (GgufValueType::Array, 1)]);
+        assert_eq!(stats.namespaces, NamespaceCounts { general: 2, tokenizer: 1, architecture: 2, other: 1 });
+
+        // The token array dominates and is listed first
+        let largest = &stats.largest_entries[0];
+        assert_eq!(largest.key, "tokenizer.ggml.tokens");
+        assert_eq!(largest.value_type, GgufValueType::Array);
+        assert_eq!(largest.element_count, 1000);
+        assert!(largest.bytes * 10 > stats.total_bytes * 9);
+        // "general.name" and "custom.note" are both 36 bytes and keep file order
+        let keys: Vec<_> = stats.largest_entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys[1..], ["general.architecture", "llama.embedding_length", "general.name", "custom.note", "llama.block_count"]);
+
+        // Total matches the bytes the writer produces for the section
+        let mut written = Vec::new();
+        metadata.write(&mut written).unwrap();
+        assert_eq!(stats.total_bytes, written.len() as u64);
+
+        let table = stats.to_string();
+        assert!(table.contains("Metadata: 6 entries"));
+        assert!(table.lines().any(|line| line.contains("tokenizer.ggml.tokens") && line.contains("1000")));
+    }
+
+    #[test]
+    fn test_compression_ratio_vs_f16() {
+        let ratio = QuantizationType::Q4_0.compression_ratio_vs_f16().unwrap();
+        assert!((ratio - 3.56).abs() < 0.01, "{ratio}");
+        assert_eq!(QuantizationType::F16.compression_ratio_vs_f16(), Some(1.0));
+        assert_eq!(QuantizationType::F32.compression_ratio_vs_f16(), Some(0.5));
+        assert_eq!(QuantizationType::Unknown(999).compression_ratio_vs_f16(), None);
+    }
+
+    #[test]
+    fn test_architecture_defaults() {
+        // llama without any optional keys
+        let config = llama_config(64, 4, 4, 256, 2, 100);
+        assert_eq!(config.rope_freq_base, None);
+        assert_eq!(config.resolved_rope_freq_base(), Some(10_000.0));
+        assert_eq!(config.resolved_rms_epsilon(), Some(1e-5));
+        assert_eq!(config.resolved_rope_dimension_count(), Some(16));
+        let config = config.with_defaults();
+        assert_eq!(config.rope_freq_base, Some(10_000.0));
+        assert_eq!(config.rope_dimension_count, Some(16));
+        assert_eq!(config.defaulted_fields, ["rope_freq_base", "attention_layer_norm_rms_epsilon", "rope_dimension_count"]);
+
+        // Explicit values win and aren't marked as defaulted
+        let mut config = llama_config(64, 4, 4, 256, 2, 100);
+        config.rope_freq_base = Some(500_000.0);
+        config.attention_head_count_kv = None;
+        let config = config.with_defaults();
+        assert_eq!(config.rope_freq_base, Some(500_000.0));
+        assert_eq!(config.attention_head_count_kv, Some(4));
+        assert!(!config.defaulted_fields.iter().any(|f| f == "rope_freq_base"));
+        assert!(config.defaulted_fields.iter().any(|f| f == "attention_head_count_kv"));
+
+        // Gemma sizes heads independently, rope covers the key length rather than embedding / heads
+        let mut gemma = llama_config(2048, 8, 1, 16384, 2, 100);
+        gemma.architecture = "gemma".to_string();
+        gemma.attention_key_length = Some(256);
+        assert_eq!(gemma.resolved_rope_freq_base(), Some(10_000.0));
+        assert_eq!(gemma.resolved_rms_epsilon(), Some(1e-6));
+        assert_eq!(gemma.resolved_rope_dimension_count(), Some(256));
+        for architecture in ["deepseek2", "olmo2", "baichuan"] {
+            gemma.architecture = architecture.to_string();
+            assert_eq!(gemma.resolved_rms_epsilon(), Some(1e-6), "{architecture}");
+        }
+
+        // No table for this architecture, nothing is filled in
+        let mut mamba = llama_config(64, 4, 4, 256, 2, 100);
+        mamba.architecture = "mamba".to_string();
+        mamba.attention_head_count_kv = None;
+        assert_eq!(mamba.resolved_rope_freq_base(), None);
+        assert_eq!(mamba.resolved_head_count_kv(), None);
+        let mamba = mamba.with_defaults();
+        assert_eq!(mamba.rope_dimension_count, None);
+        assert!(mamba.defaulted_fields.is_empty());
+    }
+
+    #[test]
+    fn test_gguf_kind_and_not_a_model() {
+        let parse = |metadata: &[(&str, GgufValue)]| {
+            GgufFile::from_reader(&mut Cursor::new(build_gguf(metadata, &[]))).unwrap()
+        };
+        let string = |value: &str| GgufValue::String(value.to_string());
+
+        // Vocab-only file as written by convert --vocab-only: tokenizer but no hyperparameters
+        let tokens = GgufValue::Array(vec![string("<s>"), string("a")]);
+        let vocab = parse(&[
+            ("general.architecture", string("llama")),
+            ("tokenizer.ggml.model", string("llama")),
+            ("tokenizer.ggml.tokens", tokens.clone()),
+        ]);
+        assert_eq!(vocab.kind(), GgufKind::Vocab);
+        assert!(matches!(vocab.model_config(), Err(GgufError::NotAModel(GgufKind::Vocab))));
+        assert_eq!(vocab.vocab().unwrap().len(), 2);
+
+        let typed = parse(&[("general.architecture", string("llama")), ("general.type", string("vocab")), ("tokenizer.ggml.tokens", tokens)]);
+        assert_eq!(typed.kind(), GgufKind::Vocab);
+
+        assert_eq!(parse(&sample_metadata()).kind(), GgufKind::Model);
+        assert!(parse(&sample_metadata()).model_config().is_ok());
+        assert_eq!(parse(&[("general.architecture", string("clip"))]).kind(), GgufKind::VisionProjector);
+        assert_eq!(parse(&[("general.type", string("adapter"))]).kind(), GgufKind::Adapter);
+        let untyped_lora = parse(&[("general.architecture", string("llama")), ("adapter.type", string("lora"))]);
+        assert_eq!(untyped_lora.kind(), GgufKind::Adapter);
+        let alpha_only = parse(&[("general.architecture", string("llama")), ("adapter.lora.alpha", GgufValue::Float32(16.0))]);
+        assert_eq!(alpha_only.kind(), GgufKind::Adapter);
+
+        let imatrix = parse(&[("general.type", string("imatrix"))]);
+        assert_eq!(imatrix.kind(), GgufKind::Unknown("imatrix".to_string()));
+        let error = imatrix.model_config().unwrap_err();
+        assert_eq!(error.to_string(), "Expected a model, found file of unknown type 'imatrix'");
+    }
+
+    #[test]
+    fn test_gguf_error_equality() {
+        assert_eq!(GgufError::InvalidMagic(*b"GGML"), GgufError::InvalidMagic(*b"GGML"));
+        assert_ne!(GgufError::InvalidMagic(*b"GGML"), GgufError::InvalidMagic(*b"ggjt"));
+        assert_ne!(GgufError::UnsupportedVersion(1), GgufError::InvalidValueType(1));
+
+        // I/O errors compare by kind, ignoring the message
+        let eof = |message: &str| GgufError::Io(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, message.to_string()));
+        assert_eq!(eof("short read"), eof("other message"));
+        assert_ne!(eof("short read"), GgufError::Io(std::io::ErrorKind::NotFound.into()));
+
+        let result = GgufFile::from_reader(&mut Cursor::new(b"GGMLxxxxxxxxxxxxxxxxxxxxxxxx".to_vec()));
+        assert_eq!(result.unwrap_err(), GgufError::InvalidMagic(*b"GGML"));
+    }
+
+    #[test]
+    fn test_layer_completeness() {
+        let mut config = llama_config(64, 4, 2, 256, 3, 100);
+        let tensors: Vec<_> = config
+            .expected_tensor_names(false)
+            .unwrap()
+            .iter()
+            .filter(|name| *name != "blk.1.ffn_down.weight")
+            .map(|name| tensor(name, &[4], QuantizationType::F32))
+            .collect();
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+
+        let reports = gguf_file.layer_completeness(&config).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(reports[0].is_complete() && reports[2].is_complete());
+        assert_eq!(reports[1].layer, 1);
+        assert_eq!(reports[1].missing, ["blk.1.ffn_down.weight"]);
+        assert_eq!(reports[1].present.len(), 8);
+        assert!(reports[1].present.contains(&"blk.1.ffn_up.weight".to_string()));
+
+        config.architecture = "mamba".to_string();
+        assert_eq!(gguf_file.layer_completeness(&config), None);
+    }
+
+    #[test]
+    fn test_metadata_reader_streaming() {
+        let bytes = build_gguf(&sample_metadata(), &[tensor("token_embd.weight", &[64, 3], QuantizationType::F32)]);
+        let kv_count = sample_metadata().len() as u64;
+
+        // Full iteration matches the buffered map, in file order
+        let mut cursor = Cursor::new(&bytes);
+        let header = GgufHeader::read(&mut cursor).unwrap();
+        let entries: Vec<_> = GgufMetadataReader::new(&mut cursor, kv_count).collect::<Result<_>>().unwrap();
+        let keys: Vec<_> = entries.iter().map(|(key, _)| key.as_str()).collect();
+        let expected: Vec<_> = sample_metadata().iter().map(|(key, _)| *key).collect();
+        assert_eq!(keys, expected);
+        let metadata = GgufMetadata::read(&mut Cursor::new(&bytes[24..]), header.metadata_kv_count).unwrap();
+        assert!(entries.iter().all(|(key, value)| metadata.get(key) == Some(value)));
+        assert_eq!(TensorInfo::read(&mut cursor).unwrap().name, "token_embd.weight");
+
+        // Stop after the architecture, then resume with the rest
+        let mut cursor = Cursor::new(&bytes);
+        GgufHeader::read(&mut cursor).unwrap();
+        let mut reader = GgufMetadataReader::new(&mut cursor, kv_count);
+        let (key, value) = reader.next().unwrap().unwrap();
+        assert_eq!((key.as_str(), value.as_string().unwrap()), ("general.architecture", "llama"));
+        assert_eq!(reader.remaining(), kv_count - 1);
+        let cursor = reader.into_inner();
+        let rest = GgufMetadata::read(cursor, kv_count - 1).unwrap();
+        assert_eq!(rest.len() as u64, kv_count - 1);
+        assert!(!rest.contains("general.architecture"));
+        assert_eq!(TensorInfo::read(cursor).unwrap().name, "token_embd.weight");
+
+        // A bad value type mid-stream is reported once, then the reader is poisoned
+        let mut corrupt = Vec::new();
+        write_kv(&mut corrupt, "a", &GgufValue::Uint32(1));
+        write_string(&mut corrupt, "b");
+        corrupt.extend_from_slice(&99u32.to_le_bytes());
+        write_kv(&mut corrupt, "c", &GgufValue::Uint32(3));
+        let mut cursor = Cursor::new(&corrupt);
+        let mut reader = GgufMetadataReader::new(&mut cursor, 3);
+        assert_eq!(reader.next().unwrap().unwrap(), ("a".to_string(), GgufValue::Uint32(1)));
+        assert_eq!(reader.next().unwrap().unwrap_err(), GgufError::InvalidValueType(99));
+        assert!(reader.is_poisoned());
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_from_url_fetches_only_the_prefix() {
+        use std::io::{BufRead, BufReader, Write};
+        use std::net::TcpListener;
+        use std::sync::Mutex;
+
+        // Metadata larger than the first range, followed by 1 MiB of tensor data
+        let tokens: Vec<_> = (0..4000).map(|i| GgufValue::String(format!("token{i}"))).collect();
+        let mut metadata = sample_metadata();
+        metadata.retain(|(key, _)| !key.starts_with("tokenizer."));
+        metadata.push(("tokenizer.ggml.tokens", GgufValue::Array(tokens)));
+        let mut file = build_gguf(&metadata, &[tensor("token_embd.weight", &[64, 4096], QuantizationType::F32)]);
+        let header_len = file.len();
+        file.resize(header_len.div_ceil(32) * 32 + 64 * 4096 * 4, 0);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/model.gguf", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let server = {
+            let (file, ranges) = (file.clone(), Arc::clone(&ranges));
+            std::thread::spawn(move || {
+                for stream in listener.incoming() {
+                    let mut stream = stream.unwrap();
+                    let mut range = None;
+                    for line in BufReader::new(&stream).lines() {
+                        let line = line.unwrap();
+                        if line.is_empty() {
+                            break;
+                        }
+                        if let Some(spec) = line.to_ascii_lowercase().strip_prefix("range: bytes=") {
+                            let (start, end) = spec.split_once('-').unwrap();
+                            range = Some((start.parse::<usize>().unwrap(), end.parse::<usize>().unwrap()));
+                        }
+                    }
+                    let Some((start, end)) = range else {
+                        // Shutdown request from the test
+                        return;
+                    };
+                    let end = end.min(file.len() - 1);
+                    ranges.lock().unwrap().push((start, end));
+                    write!(
+                        stream,
+                        "HTTP/1.1 206 Partial Content\r\nContent-Length: {}\r\nContent-Range: bytes {start}-{end}/{}\r\nConnection: close\r\n\r\n",
+                        end + 1 - start,
+                        file.len()
+                    )
+                    .unwrap();
+                    stream.write_all(&file[start..=end]).unwrap();
+                }
+            })
+        };
+
+        let gguf_file = http::from_url(&url, 1024).unwrap();
+        let _ = ureq::get(&url).call();
+        server.join().unwrap();
+
+        let expected = GgufFile::from_reader(&mut Cursor::new(&file)).unwrap();
+        assert_eq!(gguf_file, expected);
+
+        // The range grew past the metadata but stopped well short of the tensor data's end
+        let ranges = ranges.lock().unwrap();
+        assert!(ranges.len() > 1, "{ranges:?}");
+        // Each request asks only for the bytes past the previous one
+        assert_eq!(ranges[0].0, 0);
+        assert!(ranges.windows(2).all(|pair| pair[1].0 == pair[0].1 + 1), "{ranges:?}");
+        let fetched = ranges.last().unwrap().1 + 1;
+        assert!(fetched >= header_len && fetched < file.len() / 2, "{fetched} of {}", file.len());
+
+        assert!(matches!(GgufFile::from_url("http://127.0.0.1:1/missing.gguf"), Err(GgufError::Http { .. })));
+    }
+
+    #[test]
+    fn test_tensor_info_reader_streaming() {
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q8_0),
+            tensor("blk.0.attn_k.weight", &[64, 32], QuantizationType::Q8_0),
+            tensor("blk.1.attn_q.weight", &[64, 64], QuantizationType::Q8_0),
+            tensor("output.weight", &[64, 3], QuantizationType::Q6_K),
+        ];
+        let bytes = build_gguf(&sample_metadata(), &tensors);
+        let full = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+        let info_start = full.header_bytes() + full.metadata_bytes();
+
+        // Keep only block 0 while streaming, stopping once past it
+        let mut cursor = Cursor::new(&bytes);
+        cursor.set_position(info_start);
+        let mut reader = TensorInfoReader::new(&mut cursor, tensors.len() as u64);
+        let block0: Vec<_> = reader
+            .by_ref()
+            .map(Result::unwrap)
+            .skip(1)
+            .take_while(|t| t.name.starts_with("blk.0."))
+            .collect();
+        assert_eq!(block0.len(), 2);
+        // take_while consumed the first tensor past block 0 too
+        assert_eq!(reader.remaining(), 1);
+        let consumed = reader.bytes_consumed();
+        let cursor = reader.into_inner();
+        assert_eq!(cursor.position(), info_start + consumed);
+
+        // Resuming reads the rest, and the byte counts add up to the whole section
+        let mut rest = TensorInfoReader::new(cursor, 1);
+        assert_eq!(rest.next().unwrap().unwrap(), full.tensors[4]);
+        assert!(rest.next().is_none());
+        assert_eq!(consumed + rest.bytes_consumed(), full.tensor_info_bytes());
+
+        // read_all is a plain collect over the reader
+        let mut cursor = Cursor::new(&bytes);
+        cursor.set_position(info_start);
+        assert_eq!(TensorInfo::read_all(&mut cursor, tensors.len() as u64).unwrap(), full.tensors);
+
+        // Errors poison the reader
+        let mut cursor = Cursor::new(&bytes[..info_start as usize + 10]);
+        cursor.set_position(info_start);
+        let mut reader = TensorInfoReader::new(&mut cursor, tensors.len() as u64);
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.is_poisoned() && reader.next().is_none());
+    }
+
+    #[test]
+    fn test_estimate_metadata_prefix() {
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::F32),
+            tensor("output.weight", &[64, 3], QuantizationType::Q8_0),
+        ];
+        for alignment in [32u32, 4096] {
+            let mut metadata = sample_metadata();
+            metadata.push(("general.alignment", GgufValue::Uint32(alignment)));
+            let bytes = build_gguf(&metadata, &tensors);
+            let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+            let mut cursor = Cursor::new(&bytes);
+            let estimate = estimate_metadata_prefix(&mut cursor).unwrap();
+            assert_eq!(estimate, gguf_file.header_region_len());
+            assert_eq!(estimate % alignment as u64, 0);
+            // The estimated prefix alone is enough to parse the file
+            let prefix = &bytes[..(estimate as usize).min(bytes.len())];
+            assert_eq!(GgufFile::from_reader(&mut Cursor::new(prefix)).unwrap(), gguf_file);
+        }
+    }
+
+    #[cfg(feature = "report")]
+    #[test]
+    fn test_model_card_snapshot() {
+        let mut metadata = sample_metadata();
+        metadata.extend([
+            ("general.file_type", GgufValue::Uint32(7)),
+            ("general.license", GgufValue::String("apache-2.0".to_string())),
+            ("general.license.link", GgufValue::String("https://www.apache.org/licenses/LICENSE-2.0".to_string())),
+            ("general.languages", GgufValue::Array(vec![GgufValue::String("en".to_string()), GgufValue::String("fr".to_string())])),
+            ("general.tags", GgufValue::Array(vec![GgufValue::String("text-generation".to_string())])),
+            ("tokenizer.ggml.bos_token_id", GgufValue::Uint32(0)),
+            ("tokenizer.ggml.eos_token_id", GgufValue::Uint32(1)),
+            (
+                "tokenizer.chat_template",
+                GgufValue::String(
+                    "{% for message in messages %}<|im_start|>{{ message['role'] }}\n{{ message['content'] }}<|im_end|>\n{% endfor %}\n"
+                        .to_string(),
+                ),
+            ),
+        ]);
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::Q8_0),
+            tensor("blk.0.attn_q.weight", &[64, 64], QuantizationType::Q8_0),
+            tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+            tensor("output.weight", &[64, 3], QuantizationType::Q8_0),
+        ];
+        let bytes = build_gguf(&metadata, &tensors);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        assert_eq!(gguf_file.model_card(), include_str!("fixtures/model_card.md"));
+    }
+
+    #[test]
+    fn test_quant_comparison() {
+        let variant = |file_type: u32, matrix_type: QuantizationType, output_type: QuantizationType| {
+            let mut metadata = sample_metadata();
+            metadata.push(("general.file_type", GgufValue::Uint32(file_type)));
+            let tensors = [
+                tensor("token_embd.weight", &[256, 3], matrix_type),
+                tensor("blk.0.attn_q.weight", &[256, 64], matrix_type),
+                tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+                tensor("output.weight", &[256, 3], output_type),
+            ];
+            GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &tensors))).unwrap()
+        };
+        // Q5_K_M, Q3_K_M and Q4_K_M, deliberately out of size order
+        let files = [
+            variant(17, QuantizationType::Q5_K, QuantizationType::Q6_K),
+            variant(12, QuantizationType::Q3_K, QuantizationType::Q6_K),
+            variant(15, QuantizationType::Q4_K, QuantizationType::Q6_K),
+        ];
+
+        let comparison = QuantComparison::build_with_context(&files, 1024).unwrap();
+        assert_eq!(comparison.architecture, "llama");
+        assert_eq!(comparison.name.as_deref(), Some("Tiny"));
+        let labels: Vec<&str> = comparison.variants.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(labels, ["Q3_K_M", "Q4_K_M", "Q5_K_M"]);
+        assert!(comparison.variants.windows(2).all(|w| w[0].file_size < w[1].file_size));
+        assert!(comparison.variants.windows(2).all(|w| w[0].bits_per_weight < w[1].bits_per_weight));
+        // 2 layers * 1024 tokens * 2 KV heads * (16 + 16) dims * 2 bytes
+        let kv_cache = 2 * 1024 * 2 * 32 * 2;
+        assert_eq!(comparison.variants[0].estimated_ram, Some(files[1].total_size().unwrap() + kv_cache));
+
+        let names: Vec<&str> = comparison.differing_tensors.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, ["blk.0.attn_q.weight", "token_embd.weight"]);
+        assert_eq!(
+            comparison.differing_tensors[0].types,
+            [Some(QuantizationType::Q3_K), Some(QuantizationType::Q4_K), Some(QuantizationType::Q5_K)]
+        );
+        let table = comparison.to_string();
+        assert!(table.find("Q3_K_M").unwrap() < table.find("Q5_K_M").unwrap());
+        assert!(table.contains("blk.0.attn_q.weight"));
+
+        // A variant of a different model is rejected, naming the field
+        let mut metadata = sample_metadata();
+        metadata[3] = ("llama.block_count", GgufValue::Uint32(3));
+        let other = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        let err = QuantComparison::build(&[files[0].clone(), other]).unwrap_err();
+        assert!(matches!(&err, GgufError::ModelMismatch { field: "block_count", expected, found } if expected == "2" && found == "3"));
+        assert!(err.to_string().contains("block_count is 2"));
+    }
+
+    #[test]
+    fn test_quantization_types_by_frequency() {
+        let tensors = [
+            tensor("token_embd.weight", &[64, 3], QuantizationType::Q6_K),
+            tensor("blk.0.attn_q.weight", &[256, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_k.weight", &[256, 32], QuantizationType::Q4_K),
+            tensor("blk.0.ffn_down.weight", &[256, 64], QuantizationType::Q6_K),
+            tensor("blk.0.ffn_up.weight", &[256, 64], QuantizationType::Q4_K),
+            tensor("blk.0.attn_norm.weight", &[64], QuantizationType::F32),
+            tensor("output_norm.weight", &[64], QuantizationType::F32),
+            tensor("output.weight", &[64, 3], QuantizationType::Q8_0),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors))).unwrap();
+        assert_eq!(
+            gguf_file.quantization_types_by_frequency(),
+            [
+                (QuantizationType::Q4_K, 3),
+                (QuantizationType::F32, 2),
+                (QuantizationType::Q6_K, 2),
+                (QuantizationType::Q8_0, 1),
+            ]
+        );
+        // The deduplicated list keeps its type order
+        assert_eq!(
+            gguf_file.quantization_types(),
+            [QuantizationType::F32, QuantizationType::Q8_0, QuantizationType::Q4_K, QuantizationType::Q6_K]
+        );
+        assert_eq!(gguf_file.inspect().quant_histogram, gguf_file.quantization_types_by_frequency());
+
+        // A tie for most used resolves the same way everywhere, to the lower type id
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&sample_metadata(), &tensors[..2]))).unwrap();
+        assert_eq!(gguf_file.dominant_quantization_type(), Some(QuantizationType::Q4_K));
+        #[cfg(feature = "report")]
+        {
+            let card = gguf_file.model_card();
+            assert!(card.find("| Q4_K |").unwrap() < card.find("| Q6_K |").unwrap());
+        }
+    }
+
+    #[test]
+    fn test_scalar_tensor_size() {
+        let scalar = tensor("rope_freqs.scale", &[], QuantizationType::F32);
+        assert_eq!(scalar.element_count(), 1);
+        assert_eq!(scalar.size_bytes().unwrap(), 4);
+
+        let tensors = [scalar, tensor("output_norm.weight", &[64], QuantizationType::F32)];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&[], &tensors))).unwrap();
+        assert_eq!(gguf_file.tensors[0].dimensions, Vec::<u64>::new());
+        assert_eq!(gguf_file.total_size().unwrap(), 4 + 64 * 4);
+    }
+
+    #[test]
+    fn test_native_and_advertised_context_length() {
+        let config = |scaling: bool, finetuned: bool| {
+            let mut entries = vec![
+                ("general.architecture", GgufValue::String("llama".to_string())),
+                ("llama.vocab_size", GgufValue::Uint32(32000)),
+                ("llama.context_length", GgufValue::Uint32(32768)),
+                ("llama.block_count", GgufValue::Uint32(2)),
+                ("llama.embedding_length", GgufValue::Uint32(64)),
+                ("llama.feed_forward_length", GgufValue::Uint32(256)),
+                ("llama.attention.head_count", GgufValue::Uint32(4)),
+                ("llama.rope.scaling.finetuned", GgufValue::Bool(finetuned)),
+            ];
+            if scaling {
+                entries.push(("llama.rope.scaling.type", GgufValue::String("linear".to_string())));
+                entries.push(("llama.rope.scaling.factor", GgufValue::Float32(8.0)));
+                entries.push(("llama.rope.scaling.original_context_length", GgufValue::Uint32(4096)));
+            }
+            let metadata: GgufMetadata = entries.into_iter().collect();
+            ModelConfig::from_metadata(&metadata).unwrap()
+        };
+
+        // Linearly scaled without finetuning: extended beyond what it was trained at
+        let extended = config(true, false);
+        assert_eq!(extended.rope_scaling_finetuned, Some(false));
+        assert_eq!(extended.rope_scaling_original_context_length, Some(4096));
+        assert_eq!((extended.native_context_length(), extended.advertised_context_length()), (4096, 32768));
+
+        // Finetuned at the scaled length, so the long context is native
+        let finetuned = config(true, true);
+        assert_eq!(finetuned.rope_scaling_finetuned, Some(true));
+        assert_eq!((finetuned.native_context_length(), finetuned.advertised_context_length()), (32768, 32768));
+
+        // Without scaling the finetuned flag changes nothing
+        for finetuned in [false, true] {
+            let native = config(false, finetuned);
+            assert_eq!((native.native_context_length(), native.advertised_context_length()), (32768, 32768));
+        }
+
+        // A scaling type of "none" is not scaling
+        let mut unscaled = config(true, false);
+        unscaled.rope_scaling_type = Some("none".to_string());
+        assert_eq!(unscaled.native_context_length(), 32768);
+        assert_eq!(unscaled.source_keys["rope_scaling_finetuned"], "llama.rope.scaling.finetuned");
+    }
+
+    #[test]
+    fn test_error_categories() {
+        use std::io::ErrorKind;
+        let io = |kind: ErrorKind| GgufError::from(std::io::Error::from(kind));
+        let warning = GgufWarning::new(ParseWarning::DuplicateKey("general.name".to_string()), Some(24));
+        let cases = [
+            (io(ErrorKind::NotFound), ErrorCategory::Io),
+            (GgufError::IoAt { stage: "header", source: ErrorKind::TimedOut.into() }, ErrorCategory::Io),
+            (GgufError::InvalidMagic(*b"GGML"), ErrorCategory::Format),
+            (GgufError::LegacyFormat { magic: "ggjt".to_string() }, ErrorCategory::Unsupported),
+            (GgufError::UnsupportedVersion(9), ErrorCategory::Unsupported),
+            (GgufError::InvalidValueType(99), ErrorCategory::Format),
+            (GgufError::InvalidQuantizationType(99), ErrorCategory::Format),
+            (GgufError::UnknownQuantizationLabel("Q9".to_string()), ErrorCategory::Usage),
+            (GgufError::UnknownQuantizationSize(99), ErrorCategory::Unsupported),
+            (GgufError::MetadataKeyNotFound("general.name".to_string()), ErrorCategory::Content),
+            (
+                GgufError::InvalidMetadataValueType { key: "k".to_string(), expected: "u32".to_string(), found: "String".to_string() },
+                ErrorCategory::Content,
+            ),
+            (GgufError::InvalidUtf8(String::from_utf8(vec![0xff]).unwrap_err()), ErrorCategory::Format),
+            (io(ErrorKind::UnexpectedEof), ErrorCategory::Format),
+            (GgufError::UnexpectedEofAt { stage: "metadata" }, ErrorCategory::Format),
+            (
+                GgufError::ArrayTooLarge { element_type: GgufValueType::Uint8, length: 9, min_bytes: 9, available: 1 },
+                ErrorCategory::Format,
+            ),
+            (GgufError::ArrayNestingTooDeep(8), ErrorCategory::Format),
+            (GgufError::InvalidTensorDimensions { name: "t".to_string(), n_dimensions: 9, max: 4 }, ErrorCategory::Format),
+            (
+                GgufError::TensorNameCollision { name: "a".to_string(), first: "b".to_string(), second: "c".to_string() },
+                ErrorCategory::Usage,
+            ),
+            (GgufError::TruncatedFile { expected: 2, actual: 1, missing: 1 }, ErrorCategory::Format),
+            (GgufError::TrailingData { expected: 1, actual: 2, extra: 1 }, ErrorCategory::Format),
+            (GgufError::NotAModel(GgufKind::Adapter), ErrorCategory::Content),
+            (GgufError::IncompleteModelConfig("block_count".to_string()), ErrorCategory::Content),
+            (
+                GgufError::ModelMismatch { field: "block_count", expected: "2".to_string(), found: "3".to_string() },
+                ErrorCategory::Content,
+            ),
+            (GgufError::InvalidAlignment(3), ErrorCategory::Usage),
+            (GgufError::LimitExceeded { limit: "max_tensors", value: 9 }, ErrorCategory::Limit),
+            (GgufError::PromotedWarning(warning), ErrorCategory::Content),
+            (GgufError::AtOffset { offset: 8, source: Box::new(GgufError::UnsupportedVersion(9)) }, ErrorCategory::Unsupported),
+        ];
+        for (error, category) in &cases {
+            assert_eq!(error.category(), *category, "{error:?}");
+            assert_eq!(error.is_format_error(), *category == ErrorCategory::Format, "{error:?}");
+        }
+
+        // Only transient I/O is worth retrying
+        assert!(io(ErrorKind::Interrupted).is_retryable());
+        assert!(GgufError::IoAt { stage: "header", source: ErrorKind::ConnectionReset.into() }.is_retryable());
+        assert!(!io(ErrorKind::NotFound).is_retryable());
+        assert!(!io(ErrorKind::UnexpectedEof).is_retryable());
+        assert!(!GgufError::InvalidMagic(*b"GGML").is_retryable());
+        #[cfg(feature = "http")]
+        {
+            let http = |status| GgufError::Http { url: "http://host/m.gguf".to_string(), status, message: String::new() };
+            assert!(http(None).is_retryable() && http(Some(503)).is_retryable() && http(Some(429)).is_retryable());
+            assert!(!http(Some(404)).is_retryable() && !http(Some(403)).is_retryable());
+        }
+
+        // Truncated input is a format error that names the stage
+        let bytes = build_gguf(&sample_metadata(), &[]);
+        let error = GgufFile::from_reader(&mut Cursor::new(&bytes[..GgufHeader::SIZE + 4])).unwrap_err();
+        assert_eq!(error, GgufError::UnexpectedEofAt { stage: "metadata" });
+        assert!(error.is_format_error() && !error.is_retryable());
+
+        // Conversion to std::io::Error keeps I/O errors and maps the rest by category
+        let converted = std::io::Error::from(io(ErrorKind::PermissionDenied));
+        assert_eq!(converted.kind(), ErrorKind::PermissionDenied);
+        assert_eq!(std::io::Error::from(error).kind(), ErrorKind::UnexpectedEof);
+        let converted = std::io::Error::from(GgufError::InvalidMagic(*b"GGML"));
+        assert_eq!(converted.kind(), ErrorKind::InvalidData);
+        assert!(converted.to_string().contains("Invalid GGUF magic"));
+        assert_eq!(std::io::Error::from(GgufError::UnsupportedVersion(9)).kind(), ErrorKind::Unsupported);
+        assert_eq!(std::io::Error::from(GgufError::InvalidAlignment(3)).kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_read_zero_dimension_tensor() {
+        let mut bytes = Vec::new();
+        write_string(&mut bytes, "rope_freqs.scale");
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes.extend_from_slice(&QuantizationType::F32.id().to_le_bytes());
+        bytes.extend_from_slice(&64u64.to_le_bytes());
+
+        let tensors = TensorInfo::read_all(&mut Cursor::new(&bytes), 1).unwrap();
+        let scalar = &tensors[0];
+        assert!(scalar.dimensions.is_empty());
+        assert_eq!(scalar.element_count(), 1);
+        assert_eq!(scalar.size_bytes().unwrap(), 4);
+        assert_eq!((scalar.num_cols(), scalar.num_rows()), (1, 1));
+        let mut huge = scalar.clone();
+        huge.dimensions = vec![2, u64::MAX / 2, 4];
+        assert_eq!(huge.num_rows(), u64::MAX);
+        assert_eq!(scalar.shape_string(), "[]");
+        assert_eq!(scalar.shape_string_ordered(DimOrder::RowMajor), "[]");
+        assert_eq!(scalar.byte_range(100).unwrap(), 164..168);
+    }
+
+    #[test]
+    fn test_architecture_without_model_keys() {
+        let metadata = [("general.architecture", GgufValue::String("mamba".to_string()))];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        assert_eq!(gguf_file.architecture().unwrap(), "mamba");
+        assert!(matches!(gguf_file.model_config(), Err(GgufError::IncompleteModelConfig(_))));
+
+        let metadata = [("general.architecture", GgufValue::Uint32(1))];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        assert!(matches!(gguf_file.architecture(), Err(GgufError::InvalidMetadataValueType { .. })));
+
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&[], &[]))).unwrap();
+        assert_eq!(
+            gguf_file.architecture(),
+            Err(GgufError::MetadataKeyNotFound("general.architecture".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_fuzz_regressions() {
+        // Lengths far beyond the input fail like any truncated read instead of allocating
+        let string = include_bytes!("fixtures/fuzz/string_length_2_34.bin");
+        let result = GgufValue::read(&mut Cursor::new(&string[..]), GgufValueType::String);
+        assert!(matches!(result, Err(GgufError::UnexpectedEof)));
+        let strings = include_bytes!("fixtures/fuzz/string_array_element_2_40.bin");
+        let result = GgufValue::read(&mut Cursor::new(&strings[..]), GgufValueType::Array);
+        assert!(matches!(result, Err(GgufError::UnexpectedEof)));
+
+        // Sizes that don't fit in 64 bits are errors, not arithmetic overflow
+        let dims = include_bytes!("fixtures/fuzz/tensor_dims_overflow.gguf");
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&dims[..])).unwrap();
+        assert_eq!(gguf_file.tensors[0].element_count(), u64::MAX);
+        assert!(matches!(gguf_file.total_size(), Err(GgufError::SizeOverflow(_))));
+        assert!(matches!(gguf_file.data_layout(), Err(GgufError::SizeOverflow(_))));
+        assert!(gguf_file.estimated_param_count().is_ok());
+        let offset = include_bytes!("fixtures/fuzz/tensor_offset_overflow.gguf");
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&offset[..])).unwrap();
+        assert!(matches!(gguf_file.data_layout(), Err(GgufError::SizeOverflow(_))));
+        assert!(matches!(gguf_file.verify_complete(offset.len() as u64), Err(GgufError::SizeOverflow(_))));
+        let data_offset = gguf_file.data_offset();
+        assert!(matches!(gguf_file.tensors[0].byte_range(data_offset), Err(GgufError::SizeOverflow(_))));
+        let data = include_bytes!("fixtures/fuzz/inspect_total_size_overflow.gguf");
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&data[..])).unwrap();
+        assert_eq!(gguf_file.total_size().unwrap(), u64::MAX - 3);
+        assert_eq!(gguf_file.inspect().total_size, None);
+
+        // An entry count above the metadata size is capped rather than allocated
+        let count = include_bytes!("fixtures/fuzz/base_model_count_max.gguf");
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&count[..])).unwrap();
+        assert!(gguf_file.model_config().unwrap().base_models.len() <= gguf_file.metadata.len());
+    }
+
+    #[cfg(feature = "arbitrary")]
+    #[test]
+    fn test_arbitrary_values_round_trip() {
+        use arbitrary::{Arbitrary, Unstructured};
+
+        let bytes: Vec<u8> = (0..4096u32).map(|i| (i.wrapping_mul(2654435761) >> 13) as u8).collect();
+        let mut u = Unstructured::new(&bytes);
+        while !u.is_empty() {
+            let value = GgufValue::arbitrary(&mut u).unwrap();
+            let mut written = Vec::new();
+            value.write(&mut written).unwrap();
+            assert_eq!(GgufValue::read(&mut Cursor::new(written), value.value_type()).unwrap(), value);
+
+            let tensor = TensorInfo::arbitrary(&mut u).unwrap();
+            let mut written = Vec::new();
+            tensor.write(&mut written).unwrap();
+            let read = TensorInfo::read_all(&mut Cursor::new(written), 1).unwrap();
+            assert_eq!(read, [tensor]);
+        }
+    }
+
+    #[test]
+    fn test_vocab_size_from_tokens() {
+        let tokens = GgufValue::Array(["<s>", "</s>", "a"].map(|t| GgufValue::String(t.to_string())).to_vec());
+        let metadata = [("tokenizer.ggml.tokens", tokens.clone())];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        assert_eq!(gguf_file.vocab_size(), Some(3));
+        assert!(gguf_file.model_config().is_err());
+
+        let metadata = [
+            ("general.architecture", GgufValue::String("llama".to_string())),
+            ("llama.vocab_size", GgufValue::Uint32(32000)),
+            ("tokenizer.ggml.tokens", tokens),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        assert_eq!(gguf_file.vocab_size(), Some(32000));
+
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&[], &[]))).unwrap();
+        assert_eq!(gguf_file.vocab_size(), None);
+
+        // Keys under a different prefix than general.architecture, and the
+        // embedding rows when no tokens are stored
+        let metadata = [
+            ("general.architecture", GgufValue::String("qwen2".to_string())),
+            ("llama.block_count", GgufValue::Uint32(2)),
+            ("llama.vocab_size", GgufValue::Uint32(151936)),
+        ];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata, &[]))).unwrap();
+        assert_eq!(gguf_file.vocab_size(), Some(151936));
+
+        let tensors = [tensor("token_embd.weight", &[64, 48], QuantizationType::F32)];
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(build_gguf(&metadata[..2], &tensors))).unwrap();
+        assert_eq!(gguf_file.vocab_size(), Some(48));
+    }
+
+    #[test]
+    fn test_many_metadata_keys_keep_order() {
+        let keys: Vec<String> = (0..65536).map(|i| format!("custom.key_{:05}", 65535 - i)).collect();
+        let entries: Vec<(&str, GgufValue)> = keys.iter().map(|key| (key.as_str(), GgufValue::Uint8(1))).collect();
+        let bytes = build_gguf(&entries, &[]);
+
+        let start = std::time::Instant::now();
+        let metadata = GgufFile::from_reader(&mut Cursor::new(bytes)).unwrap().metadata;
+        assert!(metadata.iter().map(|(key, _)| key).eq(keys.iter().map(String::as_str)));
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(metadata.get("custom.key_00000"), Some(&GgufValue::Uint8(1)));
+    }
+
+    #[test]
+    fn test_token_arena() {
+        let mut metadata = sample_metadata();
+        metadata.push(("tokenizer.ggml.token_type", GgufValue::Array(vec![GgufValue::Int32(3), GgufValue::Int32(3), GgufValue::Int32(1)])));
+        let bytes = build_gguf(&metadata, &[]);
+        let gguf_file = GgufFile::from_reader(&mut Cursor::new(&bytes)).unwrap();
+
+        let arena = TokenArena::from_reader(&mut Cursor::new(&bytes)).unwrap().unwrap();
+        assert_eq!(arena.iter().collect::<Vec<_>>(), ["<s>", "</s>", "a"]);
+        assert_eq!((arena.len(), arena.get(1), arena.get(3)), (3, Some("</s>"), None));
+
+        // Same view as the metadata-backed vocabulary, with the tokens left unread
+        let mut reader = Cursor::new(&bytes[gguf_file.header_bytes() as usize..]);
+        let kv_count = gguf_file.header.metadata_kv_count;
+        let others = GgufMetadata::read_selected(&mut reader, kv_count, |key| key != keys::TOKENIZER_GGML_TOKENS).unwrap();
+        assert!(!others.contains(keys::TOKENIZER_GGML_TOKENS));
+        assert_eq!(Vocab::with_tokens(&arena, &others).unwrap(), gguf_file.vocab().unwrap());
+
+        let no_tokens: Vec<_> = metadata.into_iter().filter(|(key, _)| *key != "tokenizer.ggml.tokens").collect();
+        assert_eq!(TokenArena::from_reader(&mut Cursor::new(build_gguf(&no_tokens, &[]))).unwrap(), None);
+        let numbers = [("tokenizer.ggml.tokens", GgufValue::from(vec![1u32, 2]))];
+        assert!(matches!(
+            TokenArena::from_reader(&mut Cursor::new(build_gguf(&numbers, &[]))),
+            Err(GgufError::InvalidMetadataValueType { .. })
+        ));
     }
-}
\ No newline at end of file
+}
@@ -4,10 +4,25 @@
 
 use crate::error::{GgufError, Result};
 use serde::{Deserialize, Serialize};
-use std::io::{Read, Seek};
+use crate::io::{Read, Seek, SeekFrom};
+use crate::limits::{self, ParseLimits};
+use crate::read::{preallocation, read_f32, read_f64, read_string, read_u16, read_u32, read_u64, read_u8};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+/// Maximum nesting depth for arrays of arrays
+pub const MAX_ARRAY_DEPTH: usize = 4;
+
+/// Upper bound on the minimum serialized size of a single array
+pub const MAX_ARRAY_BYTES: u64 = 1 << 32;
+
+/// Array elements shown by `GgufValue::to_canonical_string`
+pub const CANONICAL_ARRAY_ELEMENTS: usize = 16;
 
 /// GGUF value type enumeration
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[repr(u32)]
 pub enum GgufValueType {
     Uint8 = 0,
@@ -48,6 +63,24 @@ impl TryFrom<u32> for GgufValueType {
     }
 }
 
+impl GgufValueType {
+    /// Serialized size of a single value of this type, `None` for String and Array
+    pub fn element_size(&self) -> Option<usize> {
+        match self {
+            GgufValueType::Uint8 | GgufValueType::Int8 | GgufValueType::Bool => Some(1),
+            GgufValueType::Uint16 | GgufValueType::Int16 => Some(2),
+            GgufValueType::Uint32 | GgufValueType::Int32 | GgufValueType::Float32 => Some(4),
+            GgufValueType::Uint64 | GgufValueType::Int64 | GgufValueType::Float64 => Some(8),
+            GgufValueType::String | GgufValueType::Array => None,
+        }
+    }
+
+    /// Check whether values of this type always occupy the same number of bytes
+    pub fn is_fixed_size(&self) -> bool {
+        self.element_size().is_some()
+    }
+}
+
 /// GGUF value container
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum GgufValue {
@@ -66,96 +99,269 @@ pub enum GgufValue {
     Float64(f64),
 }
 
-impl GgufValue {
-    /// Read a GGUF value from a reader
-    pub fn read<R: Read + Seek>(reader: &mut R, value_type: GgufValueType) -> Result<Self> {
-        match value_type {
-            GgufValueType::Uint8 => {
-                let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Uint8(buf[0]))
-            }
-            GgufValueType::Int8 => {
-                let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Int8(buf[0] as i8))
-            }
-            GgufValueType::Uint16 => {
-                let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Uint16(u16::from_le_bytes(buf)))
-            }
-            GgufValueType::Int16 => {
-                let mut buf = [0u8; 2];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Int16(i16::from_le_bytes(buf)))
-            }
-            GgufValueType::Uint32 => {
-                let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Uint32(u32::from_le_bytes(buf)))
-            }
-            GgufValueType::Int32 => {
-                let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Int32(i32::from_le_bytes(buf)))
+macro_rules! impl_from_primitive {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for GgufValue {
+                fn from(value: $ty) -> Self {
+                    GgufValue::$variant(value)
+                }
             }
-            GgufValueType::Float32 => {
-                let mut buf = [0u8; 4];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Float32(f32::from_le_bytes(buf)))
+        )*
+    };
+}
+
+impl_from_primitive! {
+    u8 => Uint8,
+    i8 => Int8,
+    u16 => Uint16,
+    i16 => Int16,
+    u32 => Uint32,
+    i32 => Int32,
+    u64 => Uint64,
+    i64 => Int64,
+    f32 => Float32,
+    f64 => Float64,
+    bool => Bool,
+    String => String,
+}
+
+impl From<&str> for GgufValue {
+    fn from(value: &str) -> Self {
+        GgufValue::String(value.to_string())
+    }
+}
+
+/// Vectors become arrays, e.g. `Vec<String>` a string array
+impl<T: Into<GgufValue>> From<Vec<T>> for GgufValue {
+    fn from(values: Vec<T>) -> Self {
+        GgufValue::Array(values.into_iter().map(Into::into).collect())
+    }
+}
+
+macro_rules! impl_try_from_value {
+    ($($ty:ty => $method:ident),* $(,)?) => {
+        $(
+            impl TryFrom<&GgufValue> for $ty {
+                type Error = GgufError;
+
+                fn try_from(value: &GgufValue) -> Result<Self> {
+                    value.$method().map(Into::into)
+                }
             }
-            GgufValueType::Bool => {
-                let mut buf = [0u8; 1];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Bool(buf[0] != 0))
+        )*
+    };
+}
+
+impl_try_from_value! {
+    u32 => as_u32,
+    u64 => as_u64,
+    i32 => as_i32,
+    i64 => as_i64,
+    f32 => as_f32,
+    f64 => as_f64,
+    bool => as_bool,
+    String => as_string,
+}
+
+/// Exact comparison, floats compare bitwise so `NaN == NaN`
+impl PartialEq for GgufValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GgufValue::Uint8(a), GgufValue::Uint8(b)) => a == b,
+            (GgufValue::Int8(a), GgufValue::Int8(b)) => a == b,
+            (GgufValue::Uint16(a), GgufValue::Uint16(b)) => a == b,
+            (GgufValue::Int16(a), GgufValue::Int16(b)) => a == b,
+            (GgufValue::Uint32(a), GgufValue::Uint32(b)) => a == b,
+            (GgufValue::Int32(a), GgufValue::Int32(b)) => a == b,
+            (GgufValue::Float32(a), GgufValue::Float32(b)) => a.to_bits() == b.to_bits(),
+            (GgufValue::Bool(a), GgufValue::Bool(b)) => a == b,
+            (GgufValue::String(a), GgufValue::String(b)) => a == b,
+            (GgufValue::Array(a), GgufValue::Array(b)) => a == b,
+            (GgufValue::Uint64(a), GgufValue::Uint64(b)) => a == b,
+            (GgufValue::Int64(a), GgufValue::Int64(b)) => a == b,
+            (GgufValue::Float64(a), GgufValue::Float64(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+/// Relative tolerance used by `GgufValue::loosely_equals` for floats
+const FLOAT_RELATIVE_EPSILON: f64 = 1e-6;
+
+impl GgufValue {
+    /// Compare values by meaning rather than storage width
+    ///
+    /// Integers compare by value across widths and signedness, floats compare
+    /// across widths within a relative epsilon (NaN equals NaN), and arrays
+    /// compare element-wise with the same rules.
+    pub fn loosely_equals(&self, other: &Self) -> bool {
+        match (self, other) {
+            (GgufValue::Array(a), GgufValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(x, y)| x.loosely_equals(y))
             }
-            GgufValueType::String => {
-                let length = {
-                    let mut buf = [0u8; 8];
-                    reader.read_exact(&mut buf)?;
-                    u64::from_le_bytes(buf)
-                };
-                
-                let mut string_buf = vec![0u8; length as usize];
-                reader.read_exact(&mut string_buf)?;
-                let string = String::from_utf8(string_buf)?;
-                Ok(GgufValue::String(string))
+            (GgufValue::String(a), GgufValue::String(b)) => a == b,
+            (GgufValue::Bool(a), GgufValue::Bool(b)) => a == b,
+            _ => {
+                if let (Some(a), Some(b)) = (self.integer_value(), other.integer_value()) {
+                    return a == b;
+                }
+                match (self.as_number(), other.as_number()) {
+                    (Some(a), Some(b)) if a.is_nan() || b.is_nan() => a.is_nan() && b.is_nan(),
+                    (Some(a), Some(b)) => {
+                        a == b || (a - b).abs() <= FLOAT_RELATIVE_EPSILON * a.abs().max(b.abs())
+                    }
+                    _ => false,
+                }
             }
+        }
+    }
+
+    pub(crate) fn integer_value(&self) -> Option<i128> {
+        match self {
+            GgufValue::Uint8(v) => Some(*v as i128),
+            GgufValue::Int8(v) => Some(*v as i128),
+            GgufValue::Uint16(v) => Some(*v as i128),
+            GgufValue::Int16(v) => Some(*v as i128),
+            GgufValue::Uint32(v) => Some(*v as i128),
+            GgufValue::Int32(v) => Some(*v as i128),
+            GgufValue::Uint64(v) => Some(*v as i128),
+            GgufValue::Int64(v) => Some(*v as i128),
+            _ => None,
+        }
+    }
+
+    /// Get any integer or float value as `f64`, `None` for strings, bools
+    /// and arrays
+    ///
+    /// Meant for display: `u64` and `i64` values beyond 2^53 lose precision.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            GgufValue::Float32(v) => Some(*v as f64),
+            GgufValue::Float64(v) => Some(*v),
+            other => other.integer_value().map(|v| v as f64),
+        }
+    }
+
+    /// Read a GGUF value from a reader
+    pub fn read<R: Read + Seek>(reader: &mut R, value_type: GgufValueType) -> Result<Self> {
+        Self::read_with_limits(reader, value_type, &ParseLimits::UNLIMITED)
+    }
+
+    /// Read a GGUF value, failing with `LimitExceeded` on strings or arrays
+    /// longer than `limits` allow
+    pub(crate) fn read_with_limits<R: Read + Seek>(
+        reader: &mut R,
+        value_type: GgufValueType,
+        limits: &ParseLimits,
+    ) -> Result<Self> {
+        Self::read_nested(reader, value_type, 0, limits)
+    }
+
+    fn read_nested<R: Read + Seek>(
+        reader: &mut R,
+        value_type: GgufValueType,
+        depth: usize,
+        limits: &ParseLimits,
+    ) -> Result<Self> {
+        match value_type {
+            GgufValueType::Uint8 => Ok(GgufValue::Uint8(read_u8(reader)?)),
+            GgufValueType::Int8 => Ok(GgufValue::Int8(read_u8(reader)? as i8)),
+            GgufValueType::Uint16 => Ok(GgufValue::Uint16(read_u16(reader)?)),
+            GgufValueType::Int16 => Ok(GgufValue::Int16(read_u16(reader)? as i16)),
+            GgufValueType::Uint32 => Ok(GgufValue::Uint32(read_u32(reader)?)),
+            GgufValueType::Int32 => Ok(GgufValue::Int32(read_u32(reader)? as i32)),
+            GgufValueType::Float32 => Ok(GgufValue::Float32(read_f32(reader)?)),
+            GgufValueType::Bool => Ok(GgufValue::Bool(read_u8(reader)? != 0)),
+            GgufValueType::String => Ok(GgufValue::String(read_string(reader, limits)?)),
             GgufValueType::Array => {
-                let array_type = {
-                    let mut buf = [0u8; 4];
-                    reader.read_exact(&mut buf)?;
-                    GgufValueType::try_from(u32::from_le_bytes(buf))?
-                };
+                let array_type = GgufValueType::try_from(read_u32(reader)?)?;
                 
-                let length = {
-                    let mut buf = [0u8; 8];
-                    reader.read_exact(&mut buf)?;
-                    u64::from_le_bytes(buf)
-                };
+                let length = read_u64(reader)?;
+                limits::check("max_array_len", length, limits.max_array_len)?;
                 
-                let mut array = Vec::with_capacity(length as usize);
+                let available = check_array(reader, array_type, length, depth)?;
+
+                if array_type == GgufValueType::String {
+                    return read_string_array(reader, length, available, limits).map(GgufValue::Array);
+                }
+                let mut array = Vec::with_capacity(preallocation(length));
                 for _ in 0..length {
-                    array.push(GgufValue::read(reader, array_type)?);
+                    array.push(GgufValue::read_nested(reader, array_type, depth + 1, limits)?);
                 }
                 Ok(GgufValue::Array(array))
             }
-            GgufValueType::Uint64 => {
-                let mut buf = [0u8; 8];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Uint64(u64::from_le_bytes(buf)))
+            GgufValueType::Uint64 => Ok(GgufValue::Uint64(read_u64(reader)?)),
+            GgufValueType::Int64 => Ok(GgufValue::Int64(read_u64(reader)? as i64)),
+            GgufValueType::Float64 => Ok(GgufValue::Float64(read_f64(reader)?)),
+        }
+    }
+
+    /// Advance a reader past a GGUF value without materializing it
+    pub fn skip<R: Read + Seek>(reader: &mut R, value_type: GgufValueType) -> Result<()> {
+        Self::skip_nested(reader, value_type, 0)
+    }
+
+    fn skip_nested<R: Read + Seek>(reader: &mut R, value_type: GgufValueType, depth: usize) -> Result<()> {
+        match value_type {
+            GgufValueType::String => {
+                let length = read_u64(reader)?;
+                seek_forward(reader, length)
             }
-            GgufValueType::Int64 => {
-                let mut buf = [0u8; 8];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Int64(i64::from_le_bytes(buf)))
+            GgufValueType::Array => {
+                let array_type = GgufValueType::try_from(read_u32(reader)?)?;
+
+                let length = read_u64(reader)?;
+
+                check_array(reader, array_type, length, depth)?;
+
+                match array_type.element_size() {
+                    Some(size) => {
+                        let total = length
+                            .checked_mul(size as u64)
+                            .ok_or(GgufError::UnexpectedEof)?;
+                        seek_forward(reader, total)
+                    }
+                    None => {
+                        for _ in 0..length {
+                            GgufValue::skip_nested(reader, array_type, depth + 1)?;
+                        }
+                        Ok(())
+                    }
+                }
             }
-            GgufValueType::Float64 => {
-                let mut buf = [0u8; 8];
-                reader.read_exact(&mut buf)?;
-                Ok(GgufValue::Float64(f64::from_le_bytes(buf)))
+            other => seek_forward(reader, other.element_size().unwrap_or(0) as u64),
+        }
+    }
+
+    /// Get the value type tag of this value
+    pub fn value_type(&self) -> GgufValueType {
+        match self {
+            GgufValue::Uint8(_) => GgufValueType::Uint8,
+            GgufValue::Int8(_) => GgufValueType::Int8,
+            GgufValue::Uint16(_) => GgufValueType::Uint16,
+            GgufValue::Int16(_) => GgufValueType::Int16,
+            GgufValue::Uint32(_) => GgufValueType::Uint32,
+            GgufValue::Int32(_) => GgufValueType::Int32,
+            GgufValue::Float32(_) => GgufValueType::Float32,
+            GgufValue::Bool(_) => GgufValueType::Bool,
+            GgufValue::String(_) => GgufValueType::String,
+            GgufValue::Array(_) => GgufValueType::Array,
+            GgufValue::Uint64(_) => GgufValueType::Uint64,
+            GgufValue::Int64(_) => GgufValueType::Int64,
+            GgufValue::Float64(_) => GgufValueType::Float64,
+        }
+    }
+
+    /// Size of this value's serialized payload in bytes, excluding the type tag
+    pub fn serialized_size(&self) -> u64 {
+        match self {
+            GgufValue::String(v) => 8 + v.len() as u64,
+            GgufValue::Array(items) => {
+                // element type + length + elements
+                4 + 8 + items.iter().map(|item| item.serialized_size()).sum::<u64>()
             }
+            other => other.value_type().element_size().unwrap_or(0) as u64,
         }
     }
 
@@ -184,6 +390,40 @@ impl GgufValue {
         }
     }
 
+    pub fn as_i32(&self) -> Result<i32> {
+        match self {
+            GgufValue::Int32(v) => Ok(*v),
+            _ => Err(GgufError::InvalidMetadataValueType {
+                key: "unknown".to_string(),
+                expected: "i32".to_string(),
+                found: format!("{self:?}"),
+            }),
+        }
+    }
+
+    pub fn as_i64(&self) -> Result<i64> {
+        match self {
+            GgufValue::Int64(v) => Ok(*v),
+            GgufValue::Int32(v) => Ok(*v as i64),
+            _ => Err(GgufError::InvalidMetadataValueType {
+                key: "unknown".to_string(),
+                expected: "i64".to_string(),
+                found: format!("{self:?}"),
+            }),
+        }
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        match self {
+            GgufValue::Bool(v) => Ok(*v),
+            _ => Err(GgufError::InvalidMetadataValueType {
+                key: "unknown".to_string(),
+                expected: "bool".to_string(),
+                found: format!("{self:?}"),
+            }),
+        }
+    }
+
     pub fn as_string(&self) -> Result<&str> {
         match self {
             GgufValue::String(v) => Ok(v),
@@ -205,4 +445,242 @@ impl GgufValue {
             }),
         }
     }
-}
\ No newline at end of file
+
+    pub fn as_f64(&self) -> Result<f64> {
+        match self {
+            GgufValue::Float64(v) => Ok(*v),
+            GgufValue::Float32(v) => Ok(*v as f64),
+            _ => Err(GgufError::InvalidMetadataValueType {
+                key: "unknown".to_string(),
+                expected: "f64".to_string(),
+                found: format!("{self:?}"),
+            }),
+        }
+    }
+
+    /// Render the value in the same form on every platform, for reports
+    /// and diffs
+    ///
+    /// Floats use the shortest representation that reads back to the same
+    /// bits (`1e-5`, `-0.0`, `NaN`), strings are quoted with control
+    /// characters such as newlines escaped, and arrays show their first
+    /// `CANONICAL_ARRAY_ELEMENTS` elements. Also used by `Display`.
+    pub fn to_canonical_string(&self) -> String {
+        self.display_truncated(CANONICAL_ARRAY_ELEMENTS)
+    }
+
+    /// Render the value like `to_canonical_string`, showing at most
+    /// `max_elems` elements of each array followed by how many were left
+    /// out, e.g. `["a", "b", ... (+149998 more)]`
+    pub fn display_truncated(&self, max_elems: usize) -> String {
+        match self {
+            GgufValue::Uint8(v) => v.to_string(),
+            GgufValue::Int8(v) => v.to_string(),
+            GgufValue::Uint16(v) => v.to_string(),
+            GgufValue::Int16(v) => v.to_string(),
+            GgufValue::Uint32(v) => v.to_string(),
+            GgufValue::Int32(v) => v.to_string(),
+            GgufValue::Float32(v) => format!("{v:?}"),
+            GgufValue::Bool(v) => v.to_string(),
+            GgufValue::String(v) => format!("{v:?}"),
+            GgufValue::Array(items) => {
+                let mut parts: Vec<String> = items
+                    .iter()
+                    .take(max_elems)
+                    .map(|item| item.display_truncated(max_elems))
+                    .collect();
+                if items.len() > max_elems {
+                    parts.push(format!("... (+{} more)", items.len() - max_elems));
+                }
+                format!("[{}]", parts.join(", "))
+            }
+            GgufValue::Uint64(v) => v.to_string(),
+            GgufValue::Int64(v) => v.to_string(),
+            GgufValue::Float64(v) => format!("{v:?}"),
+        }
+    }
+}
+
+impl fmt::Display for GgufValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_canonical_string())
+    }
+}
+
+/// Seek forward by `count` bytes
+fn seek_forward<R: Read + Seek>(reader: &mut R, count: u64) -> Result<()> {
+    let offset = i64::try_from(count).map_err(|_| GgufError::UnexpectedEof)?;
+    reader.seek(SeekFrom::Current(offset))?;
+    Ok(())
+}
+
+/// Smallest possible serialized size of a single value of this type
+fn min_size(value_type: GgufValueType) -> u64 {
+    match value_type {
+        // length prefix
+        GgufValueType::String => 8,
+        // element type + length
+        GgufValueType::Array => 4 + 8,
+        other => other.element_size().unwrap_or(0) as u64,
+    }
+}
+
+/// Reject arrays that nest too deeply or cannot fit in the remaining input,
+/// returning the number of bytes left in the input
+fn check_array<R: Read + Seek>(
+    reader: &mut R,
+    element_type: GgufValueType,
+    length: u64,
+    depth: usize,
+) -> Result<u64> {
+    if element_type == GgufValueType::Array && depth + 1 >= MAX_ARRAY_DEPTH {
+        return Err(GgufError::ArrayNestingTooDeep(MAX_ARRAY_DEPTH));
+    }
+
+    let position = reader.stream_position()?;
+    let end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(position))?;
+    let remaining = end.saturating_sub(position);
+    let available = remaining.min(MAX_ARRAY_BYTES);
+
+    let min_bytes = length.saturating_mul(min_size(element_type));
+    if min_bytes > available {
+        return Err(GgufError::ArrayTooLarge {
+            element_type,
+            length,
+            min_bytes,
+            available,
+        });
+    }
+    Ok(remaining)
+}
+
+/// Read the elements of a string array in large chunks
+///
+/// Each element is still its own `GgufValue::String`, and those allocations
+/// dominate once the reads are chunked, so this is only slightly faster than
+/// reading element by element. `TokenArena` avoids them for vocab loads.
+fn read_string_array<R: Read + Seek>(
+    reader: &mut R,
+    length: u64,
+    available: u64,
+    limits: &ParseLimits,
+) -> Result<Vec<GgufValue>> {
+    let mut strings = Vec::with_capacity(preallocation(length));
+    for_each_string(reader, length, available, limits, |string| strings.push(GgufValue::String(string.to_string())))?;
+    Ok(strings)
+}
+
+/// Read a string array value into one buffer, returning the text and the end
+/// offset of each string in it
+///
+/// The reader is positioned at the array's element type, and an array of
+/// anything but strings fails with `InvalidMetadataValueType` under `key`.
+pub(crate) fn read_string_arena<R: Read + Seek>(
+    reader: &mut R,
+    key: &str,
+    limits: &ParseLimits,
+) -> Result<(String, Vec<usize>)> {
+    let array_type = GgufValueType::try_from(read_u32(reader)?)?;
+    let length = read_u64(reader)?;
+    if array_type != GgufValueType::String {
+        return Err(GgufError::InvalidMetadataValueType {
+            key: key.to_string(),
+            expected: "String array".to_string(),
+            found: format!("{array_type:?} array"),
+        });
+    }
+    limits::check("max_array_len", length, limits.max_array_len)?;
+    let available = check_array(reader, array_type, length, 0)?;
+
+    let mut text = String::new();
+    let mut ends = Vec::with_capacity(preallocation(length));
+    for_each_string(reader, length, available, limits, |string| {
+        text.push_str(string);
+        ends.push(text.len());
+    })?;
+    Ok((text, ends))
+}
+
+/// Hand each string of a string array to `each`, reading in large chunks
+///
+/// Token arrays hold 100k+ short strings, and reading each length prefix and
+/// string separately dominates parsing. Strings are sliced out of a chunk
+/// buffer instead, then the reader is moved back to the end of the array.
+fn for_each_string<R: Read + Seek, F: FnMut(&str)>(
+    reader: &mut R,
+    length: u64,
+    available: u64,
+    limits: &ParseLimits,
+    mut each: F,
+) -> Result<()> {
+    let mut chunk = ChunkReader::new(reader, available)?;
+    for _ in 0..length {
+        let prefix = chunk.take(8)?;
+        let string_len = u64::from_le_bytes(prefix.try_into().unwrap());
+        limits::check("max_string_len", string_len, limits.max_string_len)?;
+        let bytes = chunk.take(usize::try_from(string_len).map_err(|_| GgufError::UnexpectedEof)?)?;
+        match core::str::from_utf8(bytes) {
+            Ok(string) => each(string),
+            Err(_) => return Err(String::from_utf8(bytes.to_vec()).unwrap_err().into()),
+        }
+    }
+    chunk.finish()
+}
+
+/// Bytes read at a time by `ChunkReader`
+const READ_CHUNK_SIZE: usize = 256 * 1024;
+
+/// Buffers a reader in large chunks and hands out byte slices
+struct ChunkReader<'r, R: Read + Seek> {
+    reader: &'r mut R,
+    buffer: Vec<u8>,
+    /// Reader position of `buffer[0]`
+    buffer_start: u64,
+    /// Bytes of `buffer` already handed out
+    consumed: usize,
+    /// Bytes left in the input after `buffer`
+    unread: u64,
+}
+
+impl<'r, R: Read + Seek> ChunkReader<'r, R> {
+    fn new(reader: &'r mut R, available: u64) -> Result<Self> {
+        let buffer_start = reader.stream_position()?;
+        Ok(Self {
+            reader,
+            buffer: Vec::new(),
+            buffer_start,
+            consumed: 0,
+            unread: available,
+        })
+    }
+
+    /// Get the next `len` bytes, reading another chunk when needed
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        let buffered = self.buffer.len() - self.consumed;
+        if buffered < len {
+            self.buffer.drain(..self.consumed);
+            self.buffer_start += self.consumed as u64;
+            self.consumed = 0;
+
+            // Fail like a direct read would, before allocating for bytes that aren't there
+            let wanted = len - buffered;
+            if wanted as u64 > self.unread {
+                return Err(GgufError::UnexpectedEof);
+            }
+            let load = (READ_CHUNK_SIZE as u64).min(self.unread).max(wanted as u64) as usize;
+            self.buffer.resize(buffered + load, 0);
+            self.reader.read_exact(&mut self.buffer[buffered..])?;
+            self.unread = self.unread.saturating_sub(load as u64);
+        }
+        let bytes = &self.buffer[self.consumed..self.consumed + len];
+        self.consumed += len;
+        Ok(bytes)
+    }
+
+    /// Leave the reader right after the last byte handed out
+    fn finish(self) -> Result<()> {
+        self.reader.seek(SeekFrom::Start(self.buffer_start + self.consumed as u64))?;
+        Ok(())
+    }
+}
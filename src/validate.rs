@@ -0,0 +1,200 @@
+/*!
+ * Model Configuration Consistency Checks
+ */
+
+use crate::metadata::ModelConfig;
+use crate::tensor::TensorInfo;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// An internal inconsistency between `ModelConfig` fields
+///
+/// Field names are the `ModelConfig` field names, values are as read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConfigWarning {
+    /// A field that must be positive is zero
+    Zero { field: String },
+    /// `field` is not a multiple of `divisor_field`
+    NotDivisible {
+        field: String,
+        value: u64,
+        divisor_field: String,
+        divisor: u64,
+    },
+    /// `field` is larger than `limit_field`
+    ExceedsLimit {
+        field: String,
+        value: u64,
+        limit_field: String,
+        limit: u64,
+    },
+    /// `field` differs from the value implied by `other_field`
+    Mismatch {
+        field: String,
+        value: u64,
+        other_field: String,
+        other: u64,
+    },
+}
+
+/// A tensor whose dimensions don't fit the model configuration, usually a
+/// sign of a truncated or corrupt download
+///
+/// Dimensions are in GGML order, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShapeMismatch {
+    pub tensor: String,
+    pub expected: Vec<u64>,
+    pub actual: Vec<u64>,
+}
+
+impl fmt::Display for ShapeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tensor '{}' has shape {:?}, expected {:?}", self.tensor, self.actual, self.expected)
+    }
+}
+
+impl fmt::Display for ConfigWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigWarning::Zero { field } => write!(f, "{field} is 0"),
+            ConfigWarning::NotDivisible { field, value, divisor_field, divisor } => {
+                write!(f, "{field} ({value}) is not divisible by {divisor_field} ({divisor})")
+            }
+            ConfigWarning::ExceedsLimit { field, value, limit_field, limit } => {
+                write!(f, "{field} ({value}) exceeds {limit_field} ({limit})")
+            }
+            ConfigWarning::Mismatch { field, value, other_field, other } => {
+                write!(f, "{field} ({value}) disagrees with {other_field} ({other})")
+            }
+        }
+    }
+}
+
+impl ModelConfig {
+    /// Check the configuration for internally inconsistent values
+    ///
+    /// Checks are advisory since exotic architectures legitimately break some
+    /// of these rules:
+    /// - `context_length`, `block_count`, `embedding_length` and
+    ///   `attention_head_count` are positive
+    /// - `embedding_length` divides evenly into heads, unless
+    ///   `attention_key_length` sets the head size explicitly
+    /// - `attention_head_count_kv` is at most, and divides, `attention_head_count`
+    /// - `rope_dimension_count` matches `head_dim()`
+    /// - `vocab_size` matches the length of `tokenizer_ggml_tokens`, or
+    ///   `tokenizer_token_count` when the array isn't materialized
+    /// - `expert_used_count` is at most `expert_count`
+    pub fn validate(&self) -> Vec<ConfigWarning> {
+        let mut warnings = Vec::new();
+
+        let positive = [
+            ("context_length", self.context_length),
+            ("block_count", self.block_count as u64),
+            ("embedding_length", self.embedding_length as u64),
+            ("attention_head_count", self.attention_head_count as u64),
+        ];
+        for (field, value) in positive {
+            if value == 0 {
+                warnings.push(ConfigWarning::Zero { field: field.to_string() });
+            }
+        }
+
+        let heads = self.attention_head_count as u64;
+        if self.attention_key_length.is_none() {
+            not_divisible(&mut warnings, ("embedding_length", self.embedding_length as u64), ("attention_head_count", heads));
+        }
+
+        if let Some(kv_heads) = self.attention_head_count_kv.map(u64::from) {
+            if kv_heads > heads {
+                warnings.push(ConfigWarning::ExceedsLimit {
+                    field: "attention_head_count_kv".to_string(),
+                    value: kv_heads,
+                    limit_field: "attention_head_count".to_string(),
+                    limit: heads,
+                });
+            } else {
+                not_divisible(&mut warnings, ("attention_head_count", heads), ("attention_head_count_kv", kv_heads));
+            }
+        }
+
+        if let Some(rope_dims) = self.rope_dimension_count {
+            mismatch(&mut warnings, ("rope_dimension_count", rope_dims as u64), ("head_dim", self.head_dim() as u64));
+        }
+
+        let token_count = self.tokenizer_ggml_tokens.as_ref().map(|tokens| tokens.len() as u64);
+        if let Some(token_count) = token_count.or(self.tokenizer_token_count) {
+            mismatch(&mut warnings, ("vocab_size", self.vocab_size), ("tokenizer_ggml_tokens", token_count));
+        }
+
+        if let (Some(used), Some(experts)) = (self.expert_used_count, self.expert_count)
+            && used > experts
+        {
+            warnings.push(ConfigWarning::ExceedsLimit {
+                field: "expert_used_count".to_string(),
+                value: used as u64,
+                limit_field: "expert_count".to_string(),
+                limit: experts as u64,
+            });
+        }
+
+        warnings
+    }
+}
+
+/// Warn if `field` isn't a multiple of `divisor`, zero divisors are reported separately
+fn not_divisible(warnings: &mut Vec<ConfigWarning>, (field, value): (&str, u64), (divisor_field, divisor): (&str, u64)) {
+    if divisor != 0 && value % divisor != 0 {
+        warnings.push(ConfigWarning::NotDivisible {
+            field: field.to_string(),
+            value,
+            divisor_field: divisor_field.to_string(),
+            divisor,
+        });
+    }
+}
+
+fn mismatch(warnings: &mut Vec<ConfigWarning>, (field, value): (&str, u64), (other_field, other): (&str, u64)) {
+    if value != other {
+        warnings.push(ConfigWarning::Mismatch {
+            field: field.to_string(),
+            value,
+            other_field: other_field.to_string(),
+            other,
+        });
+    }
+}
+
+/// Compare the shapes of well-known tensors against the configuration
+///
+/// Tensors that are absent, such as `output.weight` with tied embeddings,
+/// and expectations that depend on zero config fields are skipped.
+pub(crate) fn check_tensor_shapes(tensors: &[TensorInfo], config: &ModelConfig) -> Vec<ShapeMismatch> {
+    let embedding = config.embedding_length as u64;
+    let heads = config.attention_head_count as u64;
+    let kv_heads = config.attention_head_count_kv.map_or(heads, u64::from);
+    let head_dim = config.head_dim() as u64;
+
+    let expectations = [
+        ("token_embd.weight", vec![embedding, config.vocab_size]),
+        ("output.weight", vec![embedding, config.vocab_size]),
+        ("blk.0.attn_q.weight", vec![embedding, heads * head_dim]),
+        ("blk.0.attn_k.weight", vec![embedding, kv_heads * head_dim]),
+    ];
+
+    expectations
+        .into_iter()
+        .filter(|(_, expected)| expected.iter().all(|&dim| dim > 0))
+        .filter_map(|(name, expected)| {
+            let tensor = tensors.iter().find(|t| t.name == name)?;
+            (tensor.dimensions != expected).then(|| ShapeMismatch {
+                tensor: name.to_string(),
+                expected,
+                actual: tensor.dimensions.clone(),
+            })
+        })
+        .collect()
+}
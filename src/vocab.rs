@@ -0,0 +1,368 @@
+/*!
+ * Tokenizer Vocabulary View
+ *
+ * `tokenizer.ggml.tokens` and `tokenizer.ggml.token_type` are parallel
+ * arrays. `Vocab` borrows the token strings from the metadata and decodes
+ * the type ids, so special tokens can be listed without copying the vocab.
+ * `TokenArena` reads the token strings into a single buffer instead, for
+ * callers that load the vocabulary without a `GgufValue` per token.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::header::GgufHeader;
+use crate::io::{Read, Seek};
+use crate::keys;
+use crate::limits::ParseLimits;
+use crate::metadata::GgufMetadata;
+use crate::metadata_reader::GgufMetadataReader;
+use crate::types::{read_string_arena, GgufValue, GgufValueType};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use serde::{Deserialize, Serialize};
+
+/// Kind of a vocabulary entry, llama.cpp's `llama_token_type`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TokenType {
+    Undefined,
+    Normal,
+    Unknown,
+    Control,
+    UserDefined,
+    Unused,
+    Byte,
+}
+
+impl TokenType {
+    /// Get the type for a `tokenizer.ggml.token_type` id, `None` for ids
+    /// llama.cpp doesn't define
+    pub fn from_id(id: i64) -> Option<Self> {
+        let token_type = match id {
+            0 => TokenType::Undefined,
+            1 => TokenType::Normal,
+            2 => TokenType::Unknown,
+            3 => TokenType::Control,
+            4 => TokenType::UserDefined,
+            5 => TokenType::Unused,
+            6 => TokenType::Byte,
+            _ => return None,
+        };
+        Some(token_type)
+    }
+
+    /// Get the numeric id stored in `tokenizer.ggml.token_type`
+    pub fn id(&self) -> i32 {
+        match self {
+            TokenType::Undefined => 0,
+            TokenType::Normal => 1,
+            TokenType::Unknown => 2,
+            TokenType::Control => 3,
+            TokenType::UserDefined => 4,
+            TokenType::Unused => 5,
+            TokenType::Byte => 6,
+        }
+    }
+}
+
+/// Token strings and types of a model's vocabulary, borrowed from its metadata
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Vocab<'a> {
+    tokens: Vec<&'a str>,
+    token_types: Vec<TokenType>,
+}
+
+impl<'a> Vocab<'a> {
+    /// Read the vocabulary from `tokenizer.ggml.tokens` and, when present,
+    /// `tokenizer.ggml.token_type`
+    ///
+    /// Tokens without a type, including every token when the type array is
+    /// missing, and undefined type ids are `TokenType::Undefined`.
+    pub fn from_metadata(metadata: &'a GgufMetadata) -> Result<Self> {
+        let key = keys::TOKENIZER_GGML_TOKENS;
+        let tokens = array(metadata.get_required(key)?, key)?
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match item {
+                GgufValue::String(token) => Ok(token.as_str()),
+                other => Err(invalid_element(key, index, "string", other)),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let token_types = token_types(metadata, tokens.len())?;
+        Ok(Self { tokens, token_types })
+    }
+
+    /// Use token strings read into a `TokenArena`, with the types from
+    /// `tokenizer.ggml.token_type` in `metadata` like `from_metadata`
+    ///
+    /// The metadata can be read without the token array, see
+    /// `GgufMetadata::read_selected`.
+    pub fn with_tokens(tokens: &'a TokenArena, metadata: &GgufMetadata) -> Result<Self> {
+        let tokens: Vec<&'a str> = tokens.iter().collect();
+        let token_types = token_types(metadata, tokens.len())?;
+        Ok(Self { tokens, token_types })
+    }
+
+    /// Number of tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Check whether the vocabulary has no tokens
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Get the text of a token
+    pub fn token(&self, id: u32) -> Option<&'a str> {
+        self.tokens.get(id as usize).copied()
+    }
+
+    /// Get the type of a token
+    pub fn token_type(&self, id: u32) -> Option<TokenType> {
+        self.token_types.get(id as usize).copied()
+    }
+
+    /// Find the id of a token by its exact text
+    pub fn find(&self, text: &str) -> Option<u32> {
+        self.tokens.iter().position(|token| *token == text).map(|id| id as u32)
+    }
+
+    /// Check whether a token is a control token such as `<s>` or `</s>`
+    pub fn is_control(&self, id: u32) -> bool {
+        self.token_type(id) == Some(TokenType::Control)
+    }
+
+    /// List every token of the given type with its id
+    pub fn tokens_of_type(&self, token_type: TokenType) -> Vec<(u32, &'a str)> {
+        self.tokens
+            .iter()
+            .zip(&self.token_types)
+            .enumerate()
+            .filter(|(_, (_, t))| **t == token_type)
+            .map(|(id, (token, _))| (id as u32, *token))
+            .collect()
+    }
+
+    /// List control tokens with their ids
+    pub fn control_tokens(&self) -> Vec<(u32, &'a str)> {
+        self.tokens_of_type(TokenType::Control)
+    }
+
+    /// List byte fallback tokens such as `<0x0A>` with their ids
+    pub fn byte_tokens(&self) -> Vec<(u32, &'a str)> {
+        self.tokens_of_type(TokenType::Byte)
+    }
+
+    /// List user-defined tokens with their ids, usually chat markers like `<|im_start|>`
+    pub fn user_defined_tokens(&self) -> Vec<(u32, &'a str)> {
+        self.tokens_of_type(TokenType::UserDefined)
+    }
+
+    /// Get the markers that aren't control or user-defined tokens
+    ///
+    /// A chat template's special strings must each be a single special
+    /// token, otherwise prompts built from it tokenize as plain text.
+    pub fn missing_special_tokens<'m>(&self, markers: &[&'m str]) -> Vec<&'m str> {
+        markers
+            .iter()
+            .copied()
+            .filter(|marker| {
+                !self.tokens.iter().zip(&self.token_types).any(|(token, token_type)| {
+                    token == marker && matches!(token_type, TokenType::Control | TokenType::UserDefined)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Chat templates of a model, borrowed from its metadata: the default under
+/// `tokenizer.chat_template` and named variants such as `tool_use` under
+/// `tokenizer.chat_template.<name>`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChatTemplates<'a> {
+    default: Option<&'a str>,
+    named: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> ChatTemplates<'a> {
+    /// Collect the chat templates, ignoring non-string values
+    pub fn from_metadata(metadata: &'a GgufMetadata) -> Self {
+        let mut templates = Self::default();
+        for (key, value) in metadata.iter() {
+            let GgufValue::String(template) = value else { continue };
+            if key == keys::TOKENIZER_CHAT_TEMPLATE {
+                templates.default = Some(template);
+            } else if let Some(name) = key.strip_prefix(keys::TOKENIZER_CHAT_TEMPLATE).and_then(|rest| rest.strip_prefix('.')) {
+                templates.named.push((name, template));
+            }
+        }
+        templates
+    }
+
+    /// Get the default template
+    pub fn default_template(&self) -> Option<&'a str> {
+        self.default
+    }
+
+    /// Get a named template
+    pub fn get(&self, name: &str) -> Option<&'a str> {
+        self.named.iter().find(|(n, _)| *n == name).map(|(_, template)| *template)
+    }
+
+    /// Check whether there are no templates
+    pub fn is_empty(&self) -> bool {
+        self.default.is_none() && self.named.is_empty()
+    }
+
+    /// List the special token markers the templates write, such as
+    /// `<|im_start|>` or `<start_of_turn>`, in order of first appearance
+    ///
+    /// Markers are angle-bracketed words without spaces. Markers the template
+    /// only reads from variables like `bos_token` aren't found.
+    pub fn markers(&self) -> Vec<&'a str> {
+        let mut markers: Vec<&'a str> = Vec::new();
+        let templates = self.default.into_iter().chain(self.named.iter().map(|(_, template)| *template));
+        for template in templates {
+            for marker in template_markers(template) {
+                if !markers.contains(&marker) {
+                    markers.push(marker);
+                }
+            }
+        }
+        markers
+    }
+
+    /// Get the template markers that aren't a single control or
+    /// user-defined token in the vocabulary
+    pub fn verify_against_vocab(&self, vocab: &Vocab) -> Vec<&'a str> {
+        vocab.missing_special_tokens(&self.markers())
+    }
+}
+
+/// Find `<...>` spans that look like special tokens rather than Jinja
+/// comparisons
+fn template_markers(template: &str) -> impl Iterator<Item = &str> {
+    let is_marker_char = |c: char| c.is_ascii_alphanumeric() || "|_-/.:▁".contains(c);
+    template.match_indices('<').filter_map(move |(start, _)| {
+        let end = start + template[start..].find('>')?;
+        let inner = &template[start + 1..end];
+        let valid = !inner.is_empty() && inner.len() <= 64 && inner.chars().all(is_marker_char);
+        valid.then(|| &template[start..=end])
+    })
+}
+
+/// Token strings packed into one buffer
+///
+/// Reading `tokenizer.ggml.tokens` as a `GgufValue` allocates a `String` and
+/// a value per token, even though the array is read in chunks. The arena
+/// holds all the text in one `String` with the end offset of each token, and
+/// is the only path that loads a Llama 3 sized vocabulary 3-4x faster than
+/// per-token reads (see `bench_token_array`). `Vocab::with_tokens` gives the
+/// usual vocabulary view over it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenArena {
+    text: String,
+    /// End of each token in `text`, the start is the previous token's end
+    ends: Vec<usize>,
+}
+
+impl TokenArena {
+    /// Read `tokenizer.ggml.tokens` from a reader at the start of a GGUF
+    /// file, `Ok(None)` when the file has no token array
+    ///
+    /// Entries before the tokens are skipped without being materialized, and
+    /// reading stops after the tokens.
+    pub fn from_reader<R: Read + Seek>(reader: &mut R) -> Result<Option<Self>> {
+        let header = GgufHeader::read(reader)?;
+        let mut entries = GgufMetadataReader::new(reader, header.metadata_kv_count);
+        while let Some(entry) = entries.step(|reader, limits, key, value_type| {
+            match (key == keys::TOKENIZER_GGML_TOKENS, value_type) {
+                (true, GgufValueType::Array) => Self::read_limited(reader, limits).map(Some),
+                (true, other) => Err(GgufError::InvalidMetadataValueType {
+                    key: key.to_string(),
+                    expected: "String array".to_string(),
+                    found: format!("{other:?}"),
+                }),
+                (false, _) => GgufValue::skip(reader, value_type).map(|_| None),
+            }
+        }) {
+            if let (_, _, Some(tokens)) = entry? {
+                return Ok(Some(tokens));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Read a string array value, the reader positioned just after the
+    /// value's type tag
+    pub fn read_array<R: Read + Seek>(reader: &mut R) -> Result<Self> {
+        Self::read_limited(reader, &ParseLimits::UNLIMITED)
+    }
+
+    fn read_limited<R: Read + Seek>(reader: &mut R, limits: &ParseLimits) -> Result<Self> {
+        let (text, ends) = read_string_arena(reader, keys::TOKENIZER_GGML_TOKENS, limits)?;
+        Ok(Self { text, ends })
+    }
+
+    /// Number of tokens
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Check whether there are no tokens
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Get the text of a token
+    pub fn get(&self, id: u32) -> Option<&str> {
+        let id = id as usize;
+        let end = *self.ends.get(id)?;
+        let start = if id == 0 { 0 } else { self.ends[id - 1] };
+        Some(&self.text[start..end])
+    }
+
+    /// Iterate over the token strings in id order
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        let starts = core::iter::once(0).chain(self.ends.iter().copied());
+        starts.zip(&self.ends).map(|(start, &end)| &self.text[start..end])
+    }
+}
+
+/// Decode `tokenizer.ggml.token_type`, padded with `Undefined` to `len` tokens
+fn token_types(metadata: &GgufMetadata, len: usize) -> Result<Vec<TokenType>> {
+    let key = keys::TOKENIZER_GGML_TOKEN_TYPE;
+    let mut token_types = match metadata.get(key) {
+        Some(value) => array(value, key)?
+            .iter()
+            .enumerate()
+            .map(|(index, item)| match item.integer_value() {
+                Some(id) => Ok(TokenType::from_id(id as i64).unwrap_or(TokenType::Undefined)),
+                None => Err(invalid_element(key, index, "integer", item)),
+            })
+            .collect::<Result<Vec<_>>>()?,
+        None => Vec::new(),
+    };
+    token_types.resize(len, TokenType::Undefined);
+    Ok(token_types)
+}
+
+fn array<'v>(value: &'v GgufValue, key: &str) -> Result<&'v [GgufValue]> {
+    match value {
+        GgufValue::Array(items) => Ok(items),
+        other => Err(GgufError::InvalidMetadataValueType {
+            key: key.to_string(),
+            expected: "array".to_string(),
+            found: format!("{:?}", other.value_type()),
+        }),
+    }
+}
+
+fn invalid_element(key: &str, index: usize, expected: &str, found: &GgufValue) -> GgufError {
+    GgufError::InvalidMetadataValueType {
+        key: format!("{key}[{index}]"),
+        expected: expected.to_string(),
+        found: format!("{:?}", found.value_type()),
+    }
+}
@@ -0,0 +1,138 @@
+/*!
+ * Non-Fatal Oddities Noticed While Parsing
+ */
+
+use crate::EXPECTED_QUANTIZATION_VERSION;
+use alloc::string::{String, ToString};
+use core::fmt;
+use serde::{Deserialize, Serialize};
+
+/// Category of a `ParseWarning`, see `ParseOptions::warnings_as_errors`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WarningKind {
+    DuplicateKey,
+    InferredVocabSize,
+    UnknownQuantType,
+    UnexpectedQuantizationVersion,
+    LossyUtf8,
+    VocabSizeMismatch,
+    KeyPrefixMismatch,
+    SuspiciousValue,
+}
+
+/// Something unusual about a file that didn't stop it from parsing
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParseWarning {
+    /// A metadata key appeared more than once, the last value was kept
+    DuplicateKey(String),
+    /// No vocab size key is present, so it has to come from the token array
+    InferredVocabSize { tokens: u64 },
+    /// A tensor uses a quantization type id this crate doesn't know
+    UnknownQuantType { tensor: String, id: u32 },
+    /// `general.quantization_version` isn't the version current llama.cpp writes
+    UnexpectedQuantizationVersion(u32),
+    /// A string value wasn't valid UTF-8 and was decoded with replacement
+    /// characters, see `ParseOptions::lossy_utf8`
+    LossyUtf8(String),
+    /// The vocab size key disagrees with the length of the token array
+    VocabSizeMismatch { vocab_size: u64, tokens: u64 },
+    /// The hyperparameters are stored under a different prefix than
+    /// `general.architecture`, e.g. `llama.*` keys for a `qwen2` model
+    KeyPrefixMismatch { architecture: String, prefix: String },
+    /// A hyperparameter is far larger than any real model uses
+    SuspiciousValue { key: String, value: u64 },
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::DuplicateKey(key) => write!(f, "metadata key '{key}' appears more than once"),
+            ParseWarning::InferredVocabSize { tokens } => {
+                write!(f, "no vocab_size key, inferring {tokens} from tokenizer.ggml.tokens")
+            }
+            ParseWarning::UnknownQuantType { tensor, id } => {
+                write!(f, "tensor '{tensor}' uses unknown quantization type {id}")
+            }
+            ParseWarning::UnexpectedQuantizationVersion(version) => {
+                write!(f, "general.quantization_version is {version}, expected {EXPECTED_QUANTIZATION_VERSION}")
+            }
+            ParseWarning::LossyUtf8(key) => write!(f, "metadata key '{key}' has invalid UTF-8, replaced lossily"),
+            ParseWarning::VocabSizeMismatch { vocab_size, tokens } => {
+                write!(f, "vocab_size key says {vocab_size} but tokenizer.ggml.tokens has {tokens} entries")
+            }
+            ParseWarning::KeyPrefixMismatch { architecture, prefix } => {
+                write!(f, "general.architecture is '{architecture}' but its keys are stored under '{prefix}.'")
+            }
+            ParseWarning::SuspiciousValue { key, value } => {
+                write!(f, "metadata key '{key}' has an implausibly large value {value}")
+            }
+        }
+    }
+}
+
+impl ParseWarning {
+    /// Get the category of this warning
+    pub fn kind(&self) -> WarningKind {
+        match self {
+            ParseWarning::DuplicateKey(_) => WarningKind::DuplicateKey,
+            ParseWarning::InferredVocabSize { .. } => WarningKind::InferredVocabSize,
+            ParseWarning::UnknownQuantType { .. } => WarningKind::UnknownQuantType,
+            ParseWarning::UnexpectedQuantizationVersion(_) => WarningKind::UnexpectedQuantizationVersion,
+            ParseWarning::LossyUtf8(_) => WarningKind::LossyUtf8,
+            ParseWarning::VocabSizeMismatch { .. } => WarningKind::VocabSizeMismatch,
+            ParseWarning::KeyPrefixMismatch { .. } => WarningKind::KeyPrefixMismatch,
+            ParseWarning::SuspiciousValue { .. } => WarningKind::SuspiciousValue,
+        }
+    }
+
+    /// Metadata key or tensor name the warning is about
+    pub fn subject(&self) -> &str {
+        match self {
+            ParseWarning::DuplicateKey(key) => key,
+            ParseWarning::InferredVocabSize { .. } => crate::keys::TOKENIZER_GGML_TOKENS,
+            ParseWarning::UnknownQuantType { tensor, .. } => tensor,
+            ParseWarning::UnexpectedQuantizationVersion(_) => crate::keys::GENERAL_QUANTIZATION_VERSION,
+            ParseWarning::LossyUtf8(key) | ParseWarning::SuspiciousValue { key, .. } => key,
+            ParseWarning::VocabSizeMismatch { .. } => crate::keys::TOKENIZER_GGML_TOKENS,
+            ParseWarning::KeyPrefixMismatch { .. } => crate::keys::GENERAL_ARCHITECTURE,
+        }
+    }
+}
+
+/// A warning collected while parsing, kept on the `GgufFile`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GgufWarning {
+    pub warning: ParseWarning,
+    /// Position in the file or reader the warning points at, when known
+    pub offset: Option<u64>,
+}
+
+impl GgufWarning {
+    pub(crate) fn new(warning: ParseWarning, offset: Option<u64>) -> Self {
+        Self { warning, offset }
+    }
+
+    /// Get the category of this warning
+    pub fn kind(&self) -> WarningKind {
+        self.warning.kind()
+    }
+
+    /// Metadata key or tensor name the warning is about
+    pub fn subject(&self) -> &str {
+        self.warning.subject()
+    }
+
+    /// Human-readable description without the offset
+    pub fn message(&self) -> String {
+        self.warning.to_string()
+    }
+}
+
+impl fmt::Display for GgufWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.offset {
+            Some(offset) => write!(f, "{} (at byte offset {offset})", self.warning),
+            None => write!(f, "{}", self.warning),
+        }
+    }
+}
@@ -0,0 +1,97 @@
+/*!
+ * GGUF Serialization
+ *
+ * Writes the header, metadata and tensor info sections in the GGUF v3
+ * layout, the inverse of the readers in `header`, `metadata` and `tensor`.
+ * Each section writes itself, so pipelines can combine sections from
+ * different sources.
+ */
+
+use crate::error::{GgufError, Result};
+use crate::header::GgufHeader;
+use crate::metadata::GgufMetadata;
+use crate::tensor::TensorInfo;
+use crate::types::{GgufValue, GgufValueType};
+use std::io::Write;
+
+impl GgufHeader {
+    /// Write the header, the inverse of `GgufHeader::read`
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_all(&self.magic)?;
+        writer.write_all(&self.version.to_le_bytes())?;
+        writer.write_all(&self.tensor_count.to_le_bytes())?;
+        writer.write_all(&self.metadata_kv_count.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl GgufMetadata {
+    /// Write every entry in metadata order, the inverse of `GgufMetadata::read`
+    ///
+    /// The header's `metadata_kv_count` must equal `len()` for the result to
+    /// read back.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        for (key, value) in self.iter() {
+            write_string(writer, key)?;
+            writer.write_all(&(value.value_type() as u32).to_le_bytes())?;
+            value.write(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl TensorInfo {
+    /// Write the tensor info, the inverse of `TensorInfo::read`
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        write_string(writer, &self.name)?;
+        writer.write_all(&(self.dimensions.len() as u32).to_le_bytes())?;
+        for dimension in &self.dimensions {
+            writer.write_all(&dimension.to_le_bytes())?;
+        }
+        writer.write_all(&self.quantization_type.id().to_le_bytes())?;
+        writer.write_all(&self.offset.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+impl GgufValue {
+    /// Write the value payload without its type tag, the inverse of `GgufValue::read`
+    ///
+    /// GGUF arrays carry a single element type, so an array whose elements
+    /// differ in type fails with `MixedArrayTypes` before any of it is written.
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<()> {
+        match self {
+            GgufValue::Uint8(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Int8(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Uint16(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Int16(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Uint32(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Int32(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Float32(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Bool(v) => writer.write_all(&[*v as u8])?,
+            GgufValue::String(v) => write_string(writer, v)?,
+            GgufValue::Array(items) => {
+                // Empty arrays lose their element type when read, any tag will do
+                let element_type = items.first().map_or(GgufValueType::Uint8, GgufValue::value_type);
+                if let Some(found) = items.iter().map(GgufValue::value_type).find(|t| *t != element_type) {
+                    return Err(GgufError::MixedArrayTypes { expected: element_type, found });
+                }
+                writer.write_all(&(element_type as u32).to_le_bytes())?;
+                writer.write_all(&(items.len() as u64).to_le_bytes())?;
+                for item in items {
+                    item.write(writer)?;
+                }
+            }
+            GgufValue::Uint64(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Int64(v) => writer.write_all(&v.to_le_bytes())?,
+            GgufValue::Float64(v) => writer.write_all(&v.to_le_bytes())?,
+        }
+        Ok(())
+    }
+}
+
+fn write_string<W: Write>(writer: &mut W, value: &str) -> Result<()> {
+    writer.write_all(&(value.len() as u64).to_le_bytes())?;
+    writer.write_all(value.as_bytes())?;
+    Ok(())
+}